@@ -11,9 +11,22 @@ Add the crate as a dependency to your codebase
 
 ```toml
 [dependencies]
-pinata_sdk = "1.1.0"
+pinata_sdk = "2.0.0"
 ```
 
+### Upgrading from 1.x
+
+2.0.0 has the following breaking changes:
+- `PinListFilterBuilder::set_page_limit()` and `set_page_offset()` take numbers instead of strings,
+  e.g. `set_page_limit(10)` instead of `set_page_limit("10".to_string())`
+- `PinnedObject` has a new `is_duplicate` field
+- `PinnedObject` keeps the response fields unknown to the SDK in a private field, so it can't be
+  built with a struct literal anymore, use `PinnedObject::new()` instead
+- `PinOptions` has new `group_id` and `wrap_with_directory` fields, struct literals need to set
+  them or end with `..Default::default()`
+- requests failing with a 429 or 5xx status are retried 3 times by default, build the
+  `PinataApi` with `PinataApiBuilder::max_retries(0)` to keep the 1.x behavior
+
 {{readme}}
 
 ## Contribution Guide
@@ -1,15 +1,69 @@
-use std::collections::HashMap;
-use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use derive_builder::Builder;
-use crate::api::metadata::{PinMetadata, PinListMetadata, MetadataKeyValues, MetadataValue};
+use serde_json::value::RawValue;
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, SecondsFormat, Utc};
+use crate::errors::ApiError;
+use crate::upload::CancellationToken;
+use crate::utils;
+#[cfg(feature = "chrono")]
+use crate::utils::parse_timestamp;
+use crate::api::metadata::{
+  self, MetadataFilter, MetadataFilters, MetadataKeyValues, MetadataOp, MetadataValue, PinListMetadata, PinMetadata,
+};
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq)]
 /// All the currently supported regions on Pinata
 pub enum Region {
   /// Frankfurt, Germany (max 2 replications)
   FRA1,
   /// New York City, USA (max 2 replications)
   NYC1,
+  /// Any other region, identified by its raw Pinata region code (e.g. `SGP1`)
+  Custom(String),
+}
+
+impl Region {
+  /// The Pinata code for the region
+  pub fn code(&self) -> &str {
+    match self {
+      Region::FRA1 => "FRA1",
+      Region::NYC1 => "NYC1",
+      Region::Custom(code) => code,
+    }
+  }
+
+  /// Maximum replication count Pinata allows in the region, or `None` if it isn't known to the SDK
+  pub fn max_replication_count(&self) -> Option<u8> {
+    match self {
+      Region::FRA1 | Region::NYC1 => Some(2),
+      Region::Custom(_) => None,
+    }
+  }
+}
+
+impl From<String> for Region {
+  fn from(code: String) -> Region {
+    match code.as_str() {
+      "FRA1" => Region::FRA1,
+      "NYC1" => Region::NYC1,
+      _ => Region::Custom(code),
+    }
+  }
+}
+
+impl Serialize for Region {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(self.code())
+  }
+}
+
+impl<'de> Deserialize<'de> for Region {
+  fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Region, D::Error> {
+    String::deserialize(deserializer).map(Region::from)
+  }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -22,19 +76,40 @@ pub struct RegionPolicy {
   pub desired_replication_count: u8,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 /// Pinata Pin Policy Regions
 pub struct PinPolicy {
   /// List of regions and their Policy
   pub regions: Vec<RegionPolicy>,
 }
 
+impl PinPolicy {
+  /// Checks that no region asks for more replications than Pinata allows in it, returning
+  /// `ApiError::InvalidReplicationCount` otherwise. Custom regions aren't checked.
+  pub fn validate(&self) -> Result<(), ApiError> {
+    for policy in &self.regions {
+      match policy.id.max_replication_count() {
+        Some(max) if policy.desired_replication_count > max => {
+          return Err(ApiError::InvalidReplicationCount {
+            region: policy.id.clone(),
+            requested: policy.desired_replication_count,
+            max,
+          });
+        },
+        _ => (),
+      }
+    }
+
+    Ok(())
+  }
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 /// Represents a PinPolicy linked to a particular ipfs pinned hash
 pub struct HashPinPolicy {
-  ipfs_pin_hash: String,
-  new_pin_policy: PinPolicy,
+  pub(crate) ipfs_pin_hash: String,
+  pub(crate) new_pin_policy: PinPolicy,
 }
 
 impl HashPinPolicy {
@@ -51,7 +126,15 @@ impl HashPinPolicy {
   }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+/// Request body to change the account level pin policy
+pub(crate) struct UserPinPolicy {
+  pub(crate) new_pin_policy: PinPolicy,
+  pub(crate) migrate_previous_pins: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 /// Status of Jobs
 #[serde(rename_all = "snake_case")]
 pub enum JobStatus {
@@ -75,6 +158,13 @@ pub enum JobStatus {
   BadHostNode,
 }
 
+impl JobStatus {
+  /// Whether Pinata is still working on the pin job, i.e. it is prechecking, searching or retrieving.
+  pub fn is_pending(&self) -> bool {
+    matches!(self, JobStatus::Prechecking | JobStatus::Searching | JobStatus::Retrieving)
+  }
+}
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 /// Represents response of a pinByHash request.
@@ -89,16 +179,94 @@ pub struct PinByHashResult {
   pub name: Option<String>,
 }
 
-#[derive(Serialize)]
+#[derive(Clone, Debug, Default, Serialize)]
 #[serde(rename_all = "camelCase")]
-/// Used to add additional options when pinning by hash
+/// Used to add additional options when pinning content
+///
+/// Options that are not set are not sent to Pinata.
+///
+/// ```
+/// use pinata_sdk::PinOptions;
+///
+/// let options = PinOptions::new()
+///   .cid_version(1)
+///   .wrap_with_directory(true);
+/// ```
+///
+/// When building it with a struct literal, end it with `..Default::default()` so options added
+/// to later versions don't break your code:
+///
+/// ```
+/// use pinata_sdk::PinOptions;
+///
+/// let options = PinOptions { group_id: Some("group_id".to_string()), ..Default::default() };
+/// ```
 pub struct PinOptions {
+  #[serde(skip_serializing_if = "Option::is_none")]
   /// multiaddresses of nodes your content is already stored on
   pub host_nodes: Option<Vec<String>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
   /// Custom pin policy for the piece of content being pinned
   pub custom_pin_policy: Option<PinPolicy>,
+  #[serde(skip_serializing_if = "Option::is_none")]
   /// CID Version IPFS will use when creating a hash for your content
   pub cid_version: Option<u8>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  /// Wrap the content in a directory, keeping its file name in the resulting IPFS path
+  pub wrap_with_directory: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  /// Id of the [Group](struct.Group.html) the content is added to once pinned
+  pub group_id: Option<String>,
+}
+
+impl PinOptions {
+  /// Create PinOptions with no option set.
+  pub fn new() -> PinOptions {
+    PinOptions::default()
+  }
+
+  /// Consumes the PinOptions and returns a new PinOptions with the CID version set.
+  pub fn cid_version(mut self, cid_version: u8) -> PinOptions {
+    self.cid_version = Some(cid_version);
+    self
+  }
+
+  /// Consumes the PinOptions and returns a new PinOptions with the host nodes set.
+  pub fn host_nodes(mut self, host_nodes: Vec<String>) -> PinOptions {
+    self.host_nodes = Some(host_nodes);
+    self
+  }
+
+  /// Consumes the PinOptions and returns a new PinOptions with the custom pin policy set.
+  pub fn custom_pin_policy(mut self, policy: PinPolicy) -> PinOptions {
+    self.custom_pin_policy = Some(policy);
+    self
+  }
+
+  /// Consumes the PinOptions and returns a new PinOptions with wrapWithDirectory set.
+  pub fn wrap_with_directory(mut self, wrap: bool) -> PinOptions {
+    self.wrap_with_directory = Some(wrap);
+    self
+  }
+
+  /// Checks that the host nodes look like multiaddrs with a peer id, returning
+  /// `ApiError::InvalidMultiaddr` for the first one that doesn't.
+  pub fn validate(&self) -> Result<(), ApiError> {
+    for host_node in self.host_nodes.iter().flatten() {
+      utils::validate_multiaddr(host_node)?;
+    }
+
+    Ok(())
+  }
+}
+
+/// Sets the CID version on `options`, creating them if needed
+fn with_cid_version(options: Option<PinOptions>, version: u8) -> Result<PinOptions, ApiError> {
+  if version > 1 {
+    return Err(ApiError::InvalidCidVersion(version));
+  }
+
+  Ok(options.unwrap_or_default().cid_version(version))
 }
 
 #[derive(Serialize)]
@@ -111,7 +279,10 @@ pub struct PinOptions {
 /// # async fn run() -> Result<(), ApiError> {
 /// let api = PinataApi::new("api_key", "secret_api_key").unwrap();
 /// 
-/// let result = api.pin_by_hash(PinByHash::new("hash")).await;
+/// let result = api.pin_by_hash(
+///   PinByHash::new("hash")
+///     .add_host_node("/ip4/1.2.3.4/tcp/4001/p2p/QmNode")
+/// ).await;
 /// 
 /// if let Ok(pinning_job) = result {
 ///   // track result job here
@@ -120,9 +291,11 @@ pub struct PinOptions {
 /// # }
 /// ```
 pub struct PinByHash {
-  hash_to_pin: String,
-  pinata_metadata: Option<PinMetadata>,
-  pinata_option: Option<PinOptions>,
+  pub(crate) hash_to_pin: String,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) pinata_metadata: Option<PinMetadata>,
+  #[serde(rename = "pinataOptions", skip_serializing_if = "Option::is_none")]
+  pub(crate) pinata_option: Option<PinOptions>,
 }
 
 impl PinByHash {
@@ -140,19 +313,35 @@ impl PinByHash {
     }
   }
 
-  /// Consumes the current PinByHash and returns a new PinByHash with keyvalues metadata set
-  pub fn set_metadata(self, keyvalues: MetadataKeyValues) -> PinByHash {
-    PinByHash {
-      hash_to_pin: self.hash_to_pin,
-      pinata_metadata: Some(PinMetadata {
-        keyvalues,
-        name: None,
-      }),
-      pinata_option: self.pinata_option,
-    }
+  /// Consumes the current PinByHash and returns a new PinByHash with keyvalues metadata set.
+  /// A name set with `set_name()` is kept. The keyvalues are checked against the
+  /// [metadata limits](struct.PinMetadata.html) when pinning.
+  pub fn set_metadata(mut self, keyvalues: MetadataKeyValues) -> PinByHash {
+    self.pinata_metadata = Some(metadata::with_keyvalues(self.pinata_metadata, keyvalues));
+    self
+  }
+
+  /// Sets the keyvalues metadata from JSON values, e.g. values parsed from user input.
+  ///
+  /// Only strings, numbers and booleans are valid keyvalues, any other value fails with
+  /// `ApiError::InvalidMetadataValue`. Keyvalues over the [metadata limits](struct.PinMetadata.html)
+  /// fail with `ApiError::TooManyKeyValues`.
+  pub fn set_metadata_json(self, keyvalues: HashMap<String, serde_json::Value>) -> Result<PinByHash, ApiError> {
+    self.try_set_metadata(metadata::keyvalues_from_json(keyvalues)?)
+  }
+
+  /// Consumes the current PinByHash and returns a new PinByHash with the metadata name set.
+  /// Keyvalues set with `set_metadata()` are kept. The name is trimmed and checked as described
+  /// on [PinMetadata](struct.PinMetadata.html).
+  pub fn set_name<IntoStr: Into<String>>(mut self, name: IntoStr) -> PinByHash {
+    self.pinata_metadata = Some(metadata::with_name(self.pinata_metadata, name.into()));
+    self
   }
 
   /// Consumes the current PinByHash and returns a new PinByHash with metadata name and keyvalues set
+  ///
+  /// The name is trimmed like with `set_name()`, both are checked against the
+  /// [metadata limits](struct.PinMetadata.html) when pinning.
   pub fn set_metadata_with_name<S>(self, name: S, keyvalues: HashMap<String, MetadataValue>) -> PinByHash 
     where S: Into<String>
   {
@@ -160,12 +349,28 @@ impl PinByHash {
       hash_to_pin: self.hash_to_pin,
       pinata_metadata: Some(PinMetadata {
         keyvalues,
-        name: Some(name.into()),
+        name: metadata::normalize_name(name.into()),
       }),
       pinata_option: self.pinata_option,
     }
   }
 
+  /// Sets the keyvalues metadata like `set_metadata()`, but returns `ApiError::TooManyKeyValues`
+  /// right away if there are more keyvalues than Pinata allows.
+  pub fn try_set_metadata(self, keyvalues: MetadataKeyValues) -> Result<PinByHash, ApiError> {
+    metadata::validate_keyvalues(&keyvalues)?;
+    Ok(self.set_metadata(keyvalues))
+  }
+
+  /// Sets the metadata name and keyvalues like `set_metadata_with_name()`, but returns
+  /// `ApiError::TooManyKeyValues` right away if there are more keyvalues than Pinata allows.
+  pub fn try_set_metadata_with_name<IntoStr>(self, name: IntoStr, keyvalues: MetadataKeyValues) -> Result<PinByHash, ApiError>
+    where IntoStr: Into<String>
+  {
+    metadata::validate_keyvalues(&keyvalues)?;
+    Ok(self.set_metadata_with_name(name, keyvalues))
+  }
+
   /// Consumes the PinByHash and returns a new PinByHash with pinata options set.
   pub fn set_options(self, options: PinOptions) -> PinByHash {
     PinByHash {
@@ -174,6 +379,31 @@ impl PinByHash {
       pinata_option: Some(options),
     }
   }
+
+  /// Consumes the current PinByHash and returns a new PinByHash with the CID version set in its pinata
+  /// options, keeping the other options already set.
+  ///
+  /// Returns `ApiError::InvalidCidVersion` if `version` isn't 0 or 1.
+  pub fn set_cid_version(mut self, version: u8) -> Result<PinByHash, ApiError> {
+    self.pinata_option = Some(with_cid_version(self.pinata_option, version)?);
+    Ok(self)
+  }
+
+  /// Consumes the PinByHash and returns a new PinByHash with `multiaddr` added to the host nodes
+  /// in its pinata options.
+  ///
+  /// Host nodes are the multiaddresses of nodes your content is already stored on, which helps
+  /// Pinata find the content faster.
+  pub fn add_host_node<S>(mut self, multiaddr: S) -> PinByHash
+    where S: Into<String>
+  {
+    self.pinata_option
+      .get_or_insert_with(PinOptions::default)
+      .host_nodes
+      .get_or_insert_with(Vec::new)
+      .push(multiaddr.into());
+    self
+  }
 }
 
 #[derive(Serialize)]
@@ -198,10 +428,17 @@ impl PinByHash {
 /// # Ok(())
 /// # }
 /// ```
+///
+/// The content is always sent wrapped in the request's `pinataContent` field, so any JSON value,
+/// including a top level array, is pinned as is.
 pub struct PinByJson<S: Serialize> {
   pinata_content: S,
-  pinata_metadata: Option<PinMetadata>,
-  pinata_option: Option<PinOptions>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) pinata_metadata: Option<PinMetadata>,
+  #[serde(rename = "pinataOptions", skip_serializing_if = "Option::is_none")]
+  pub(crate) pinata_option: Option<PinOptions>,
+  #[serde(skip)]
+  pretty: bool,
 }
 
 impl <S> PinByJson<S>
@@ -216,19 +453,65 @@ impl <S> PinByJson<S>
       pinata_content: json_data,
       pinata_metadata: None,
       pinata_option: None,
+      pretty: false,
     }
   }
 
-  /// Consumes the current PinByJson<S> and returns a new PinByJson<S> with keyvalues metadata set
+  /// Consumes the current PinByJson<S> and returns a new PinByJson<S> sending the content
+  /// pretty printed when `pretty` is true, e.g. to make it readable on the gateway. By default
+  /// the content is sent compact.
+  ///
+  /// The formatting changes the bytes sent and so can change the CID of the pinned content: the
+  /// same value pinned compact and pretty printed can get different CIDs. Content created with
+  /// [from_raw](#method.from_raw) is always sent unchanged.
+  pub fn pretty(mut self, pretty: bool) -> PinByJson<S> {
+    self.pretty = pretty;
+    self
+  }
+
+  /// The content as Pinata stores it, serialized as compact JSON
+  pub(crate) fn content_bytes(&self) -> Result<Vec<u8>, ApiError> {
+    serde_json::to_vec(&self.pinata_content).map_err(|err| ApiError::InvalidJson(err.to_string()))
+  }
+
+  /// JSON body of the pin request, pretty printed if set with `pretty()`
+  pub(crate) fn to_body(&self) -> Result<Vec<u8>, ApiError> {
+    let body = match self.pretty {
+      true => serde_json::to_vec_pretty(self),
+      false => serde_json::to_vec(self),
+    };
+    body.map_err(|err| ApiError::InvalidJson(err.to_string()))
+  }
+
+  /// Consumes the current PinByJson<S> and returns a new PinByJson<S> with keyvalues metadata set.
+  /// A name set with `set_name()` is kept. The keyvalues are checked against the
+  /// [metadata limits](struct.PinMetadata.html) when pinning.
   pub fn set_metadata(mut self, keyvalues: MetadataKeyValues) -> PinByJson<S> {
-    self.pinata_metadata = Some(PinMetadata {
-      name: None,
-      keyvalues,
-    });
+    self.pinata_metadata = Some(metadata::with_keyvalues(self.pinata_metadata, keyvalues));
+    self
+  }
+
+  /// Sets the keyvalues metadata from JSON values, e.g. values parsed from user input.
+  ///
+  /// Only strings, numbers and booleans are valid keyvalues, any other value fails with
+  /// `ApiError::InvalidMetadataValue`. Keyvalues over the [metadata limits](struct.PinMetadata.html)
+  /// fail with `ApiError::TooManyKeyValues`.
+  pub fn set_metadata_json(self, keyvalues: HashMap<String, serde_json::Value>) -> Result<PinByJson<S>, ApiError> {
+    self.try_set_metadata(metadata::keyvalues_from_json(keyvalues)?)
+  }
+
+  /// Consumes the current PinByJson<S> and returns a new PinByJson<S> with the metadata name set.
+  /// Keyvalues set with `set_metadata()` are kept. The name is trimmed and checked as described
+  /// on [PinMetadata](struct.PinMetadata.html).
+  pub fn set_name<IntoStr: Into<String>>(mut self, name: IntoStr) -> PinByJson<S> {
+    self.pinata_metadata = Some(metadata::with_name(self.pinata_metadata, name.into()));
     self
   }
 
   /// Consumes the current PinByJson<S> and returns a new PinByJson<S> with keyvalues metadata set
+  ///
+  /// The name is trimmed like with `set_name()`, both are checked against the
+  /// [metadata limits](struct.PinMetadata.html) when pinning.
   pub fn set_metadata_with_name<IntoStr>(
     mut self, name: IntoStr,
     keyvalues: MetadataKeyValues
@@ -236,23 +519,156 @@ impl <S> PinByJson<S>
     where IntoStr: Into<String>
   {
     self.pinata_metadata = Some(PinMetadata {
-      name: Some(name.into()),
+      name: metadata::normalize_name(name.into()),
       keyvalues,
     });
     self
   }
 
+  /// Sets the keyvalues metadata like `set_metadata()`, but returns `ApiError::TooManyKeyValues`
+  /// right away if there are more keyvalues than Pinata allows.
+  pub fn try_set_metadata(self, keyvalues: MetadataKeyValues) -> Result<PinByJson<S>, ApiError> {
+    metadata::validate_keyvalues(&keyvalues)?;
+    Ok(self.set_metadata(keyvalues))
+  }
+
+  /// Sets the metadata name and keyvalues like `set_metadata_with_name()`, but returns
+  /// `ApiError::TooManyKeyValues` right away if there are more keyvalues than Pinata allows.
+  pub fn try_set_metadata_with_name<IntoStr>(self, name: IntoStr, keyvalues: MetadataKeyValues) -> Result<PinByJson<S>, ApiError>
+    where IntoStr: Into<String>
+  {
+    metadata::validate_keyvalues(&keyvalues)?;
+    Ok(self.set_metadata_with_name(name, keyvalues))
+  }
+
   /// Consumes the PinByHash and returns a new PinByHash with pinata options set.
   pub fn set_options(mut self, options: PinOptions) -> PinByJson<S> {
     self.pinata_option = Some(options);
     self
   }
+
+  /// Consumes the current PinByJson<S> and returns a new PinByJson<S> with the CID version set in its pinata
+  /// options, keeping the other options already set.
+  ///
+  /// Returns `ApiError::InvalidCidVersion` if `version` isn't 0 or 1.
+  pub fn set_cid_version(mut self, version: u8) -> Result<PinByJson<S>, ApiError> {
+    self.pinata_option = Some(with_cid_version(self.pinata_option, version)?);
+    Ok(self)
+  }
+}
+
+impl PinByJson<Box<RawValue>> {
+  /// Create a PinByJson pinning an already serialized JSON string.
+  ///
+  /// The exact bytes of `json` are sent as the content, they are not parsed and serialized again,
+  /// so key order and formatting, and with them the resulting CID, are kept. Returns
+  /// `ApiError::InvalidJson` if `json` isn't valid JSON.
+  pub fn from_raw(json: String) -> Result<PinByJson<Box<RawValue>>, ApiError> {
+    let raw = RawValue::from_string(json).map_err(|err| ApiError::InvalidJson(err.to_string()))?;
+    Ok(PinByJson::new(raw))
+  }
 }
 
 #[derive(Clone)]
 ///  Internal structure use to know how to read a file or structure
-pub(crate) struct FileData {
-  pub(crate) file_path: String,
+pub(crate) enum FileData {
+  /// Path to a file or directory on disk, uploaded as `name` when set instead of its own name
+  #[cfg(not(target_arch = "wasm32"))]
+  Path {
+    path: String,
+    name: Option<String>,
+  },
+  /// File content already in memory
+  Bytes {
+    file_name: String,
+    bytes: Vec<u8>,
+  },
+}
+
+#[derive(Clone, Debug)]
+/// Content of a file added to a [PinByFile](struct.PinByFile.html) with `add_file()`
+pub enum FileSource {
+  /// Path to a file or directory on disk. Not available on wasm.
+  #[cfg(not(target_arch = "wasm32"))]
+  Path(String),
+  /// File content already in memory
+  Bytes(Vec<u8>),
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<String> for FileSource {
+  fn from(path: String) -> FileSource {
+    FileSource::Path(path)
+  }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<&str> for FileSource {
+  fn from(path: &str) -> FileSource {
+    FileSource::Path(path.to_string())
+  }
+}
+
+impl From<Vec<u8>> for FileSource {
+  fn from(bytes: Vec<u8>) -> FileSource {
+    FileSource::Bytes(bytes)
+  }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+/// What to do when two files would be uploaded with the same path in a [PinByFile](struct.PinByFile.html)
+pub enum DuplicatePathPolicy {
+  /// Fail the upload with `ApiError::DuplicatePath`. This is the default.
+  #[default]
+  Reject,
+  /// Upload only the first file added for the path and ignore the others.
+  KeepFirst,
+}
+
+#[derive(Clone, Default)]
+/// Internal options controlling how the files of a [PinByFile](struct.PinByFile.html) are collected and sent
+pub(crate) struct UploadOptions {
+  pub(crate) duplicate_path_policy: DuplicatePathPolicy,
+  /// Content type overriding the one guessed from the file extension, for single file uploads
+  pub(crate) content_type: Option<String>,
+  /// Size in bytes above which a file is rejected instead of uploaded, when set
+  pub(crate) max_file_size: Option<u64>,
+  /// Multipart field name the files are sent as, instead of `file`
+  pub(crate) field_name: Option<String>,
+  /// Token cancelling the upload when cancelled
+  pub(crate) cancellation: Option<CancellationToken>,
+  /// Follow symlinks found while walking directories, instead of skipping them
+  #[cfg(not(target_arch = "wasm32"))]
+  pub(crate) follow_symlinks: bool,
+  /// Number of files read into memory at the same time before uploading, when set
+  #[cfg(not(target_arch = "wasm32"))]
+  pub(crate) read_concurrency: Option<usize>,
+  /// Globs matching the only directory entries uploaded, all entries are uploaded when empty
+  #[cfg(not(target_arch = "wasm32"))]
+  pub(crate) include_globs: Vec<String>,
+  /// Globs matching directory entries that are not uploaded
+  #[cfg(not(target_arch = "wasm32"))]
+  pub(crate) exclude_globs: Vec<String>,
+}
+
+impl UploadOptions {
+  /// Multipart field name the files are sent as
+  pub(crate) fn field_name(&self) -> &str {
+    self.field_name.as_deref().unwrap_or(crate::upload::FILE_FIELD_NAME)
+  }
+}
+
+/// Joins the segments of a relative path with forward slashes, dropping empty and `.` segments.
+/// Returns `ApiError::InvalidPath` for a path with a `..` segment, which would leave the folder.
+fn normalize_relative_path(path: &str) -> Result<String, ApiError> {
+  let segments: Vec<&str> = path.split(['/', '\\'])
+    .filter(|segment| !segment.is_empty() && *segment != ".")
+    .collect();
+  if segments.contains(&"..") {
+    return Err(ApiError::InvalidPath(path.into()));
+  }
+
+  Ok(segments.join("/"))
 }
 
 /// Request object to pin a file
@@ -273,8 +689,11 @@ pub(crate) struct FileData {
 /// ```
 pub struct PinByFile {
   pub(crate) files: Vec<FileData>,
+  /// Folder the files added with `add_file()` are uploaded in
+  pub(crate) folder: Option<String>,
   pub(crate) pinata_metadata: Option<PinMetadata>,
   pub(crate) pinata_option: Option<PinOptions>,
+  pub(crate) upload_options: UploadOptions,
 }
 
 impl PinByFile {
@@ -282,27 +701,130 @@ impl PinByFile {
   /// 
   /// `file_or_dir_path` can be path to a file or to a directory.
   /// If a directory is provided
+  ///
+  /// Not available on wasm, use [from_bytes](#method.from_bytes) instead.
+  #[cfg(not(target_arch = "wasm32"))]
   pub fn new<S: Into<String>>(file_or_dir_path: S) -> PinByFile {
     let owned_file_path = file_or_dir_path.into();
     PinByFile {
       files: [
-        FileData { file_path: owned_file_path }
+        FileData::Path { path: owned_file_path, name: None }
       ].to_vec(),
+      folder: None,
       pinata_metadata: None,
       pinata_option: None,
+      upload_options: UploadOptions::default(),
     }
   }
 
-  /// Consumes the current PinByFile and returns a new PinByFile with keyvalues metadata set
-  pub fn set_metadata(mut self, keyvalues: MetadataKeyValues) -> PinByFile {
-    self.pinata_metadata = Some(PinMetadata {
-      name: None,
-      keyvalues,
+  /// Create a PinByFile object from file content in memory.
+  ///
+  /// The content is uploaded as a file named `file_name`, without being written to disk.
+  pub fn from_bytes<S: Into<String>>(file_name: S, bytes: Vec<u8>) -> PinByFile {
+    PinByFile {
+      files: [
+        FileData::Bytes { file_name: file_name.into(), bytes }
+      ].to_vec(),
+      folder: None,
+      pinata_metadata: None,
+      pinata_option: None,
+      upload_options: UploadOptions::default(),
+    }
+  }
+
+  /// Create a PinByFile uploading the files added with [add_file](#method.add_file) inside a
+  /// folder named `folder_name`, pinned as a single directory.
+  ///
+  /// ```
+  /// use pinata_sdk::PinByFile;
+  ///
+  /// let pin = PinByFile::folder("site")
+  ///   .add_file(b"<h1>hello</h1>".to_vec(), "index.html")
+  ///   .add_file(b"h1 { color: red }".to_vec(), "css/style.css");
+  /// ```
+  pub fn folder<S: Into<String>>(folder_name: S) -> PinByFile {
+    PinByFile {
+      files: Vec::new(),
+      folder: Some(folder_name.into()),
+      pinata_metadata: None,
+      pinata_option: None,
+      upload_options: UploadOptions::default(),
+    }
+  }
+
+  /// Create a PinByFile uploading a directory built in memory, e.g. a generated static site,
+  /// without writing it to disk.
+  ///
+  /// Each entry is the path of a file relative to the directory and its content. The files are
+  /// uploaded inside a folder named `folder_name`, the same way `new()` uploads a directory on
+  /// disk. Backslashes in the paths are replaced by forward slashes. Returns
+  /// `ApiError::InvalidPath` if a path has a `..` segment.
+  ///
+  /// ```
+  /// use pinata_sdk::PinByFile;
+  ///
+  /// let pin = PinByFile::from_entries("site", vec![
+  ///   ("index.html".to_string(), b"<h1>hello</h1>".to_vec()),
+  ///   ("css\\style.css".to_string(), b"h1 { color: red }".to_vec()),
+  /// ]).unwrap();
+  /// ```
+  pub fn from_entries<S: Into<String>>(folder_name: S, entries: Vec<(String, Vec<u8>)>) -> Result<PinByFile, ApiError> {
+    entries.into_iter().try_fold(PinByFile::folder(folder_name), |pin, (path, bytes)| {
+      Ok(pin.add_file(bytes, normalize_relative_path(&path)?))
+    })
+  }
+
+  /// Consumes the current PinByFile and returns a new PinByFile with `file` added to the upload
+  /// as `name`, e.g. `"docs/readme.md"`. The file is uploaded inside the folder of a PinByFile
+  /// created with [folder](#method.folder).
+  ///
+  /// `file` is either a path on disk, which can be a directory uploaded as `name`, or the file
+  /// content in memory as a `Vec<u8>`.
+  pub fn add_file<F, S>(mut self, file: F, name: S) -> PinByFile
+    where F: Into<FileSource>, S: Into<String>
+  {
+    let name = match &self.folder {
+      Some(folder) => format!("{}/{}", folder, name.into().trim_start_matches('/')),
+      None => name.into(),
+    };
+
+    self.files.push(match file.into() {
+      #[cfg(not(target_arch = "wasm32"))]
+      FileSource::Path(path) => FileData::Path { path, name: Some(name) },
+      FileSource::Bytes(bytes) => FileData::Bytes { file_name: name, bytes },
     });
     self
   }
 
+  /// Consumes the current PinByFile and returns a new PinByFile with keyvalues metadata set.
+  /// A name set with `set_name()` is kept. The keyvalues are checked against the
+  /// [metadata limits](struct.PinMetadata.html) when pinning.
+  pub fn set_metadata(mut self, keyvalues: MetadataKeyValues) -> PinByFile {
+    self.pinata_metadata = Some(metadata::with_keyvalues(self.pinata_metadata, keyvalues));
+    self
+  }
+
+  /// Sets the keyvalues metadata from JSON values, e.g. values parsed from user input.
+  ///
+  /// Only strings, numbers and booleans are valid keyvalues, any other value fails with
+  /// `ApiError::InvalidMetadataValue`. Keyvalues over the [metadata limits](struct.PinMetadata.html)
+  /// fail with `ApiError::TooManyKeyValues`.
+  pub fn set_metadata_json(self, keyvalues: HashMap<String, serde_json::Value>) -> Result<PinByFile, ApiError> {
+    self.try_set_metadata(metadata::keyvalues_from_json(keyvalues)?)
+  }
+
+  /// Consumes the current PinByFile and returns a new PinByFile with the metadata name set.
+  /// Keyvalues set with `set_metadata()` are kept. The name is trimmed and checked as described
+  /// on [PinMetadata](struct.PinMetadata.html).
+  pub fn set_name<IntoStr: Into<String>>(mut self, name: IntoStr) -> PinByFile {
+    self.pinata_metadata = Some(metadata::with_name(self.pinata_metadata, name.into()));
+    self
+  }
+
   /// Consumes the current PinByFile and returns a new PinByFile with keyvalues metadata set
+  ///
+  /// The name is trimmed like with `set_name()`, both are checked against the
+  /// [metadata limits](struct.PinMetadata.html) when pinning.
   pub fn set_metadata_with_name<IntoStr>(
     mut self, name: IntoStr,
     keyvalues: MetadataKeyValues
@@ -310,20 +832,148 @@ impl PinByFile {
     where IntoStr: Into<String>
   {
     self.pinata_metadata = Some(PinMetadata {
-      name: Some(name.into()),
+      name: metadata::normalize_name(name.into()),
       keyvalues,
     });
     self
   }
 
+  /// Sets the keyvalues metadata like `set_metadata()`, but returns `ApiError::TooManyKeyValues`
+  /// right away if there are more keyvalues than Pinata allows.
+  pub fn try_set_metadata(self, keyvalues: MetadataKeyValues) -> Result<PinByFile, ApiError> {
+    metadata::validate_keyvalues(&keyvalues)?;
+    Ok(self.set_metadata(keyvalues))
+  }
+
+  /// Sets the metadata name and keyvalues like `set_metadata_with_name()`, but returns
+  /// `ApiError::TooManyKeyValues` right away if there are more keyvalues than Pinata allows.
+  pub fn try_set_metadata_with_name<IntoStr>(self, name: IntoStr, keyvalues: MetadataKeyValues) -> Result<PinByFile, ApiError>
+    where IntoStr: Into<String>
+  {
+    metadata::validate_keyvalues(&keyvalues)?;
+    Ok(self.set_metadata_with_name(name, keyvalues))
+  }
+
   /// Consumes the PinByHash and returns a new PinByHash with pinata options set.
   pub fn set_options(mut self, options: PinOptions) -> PinByFile {
     self.pinata_option = Some(options);
     self
   }
+
+  /// Consumes the current PinByFile and returns a new PinByFile with the CID version set in its pinata
+  /// options, keeping the other options already set.
+  ///
+  /// Returns `ApiError::InvalidCidVersion` if `version` isn't 0 or 1.
+  pub fn set_cid_version(mut self, version: u8) -> Result<PinByFile, ApiError> {
+    self.pinata_option = Some(with_cid_version(self.pinata_option, version)?);
+    Ok(self)
+  }
+
+  /// Consumes the PinByFile and returns a new PinByFile with the policy used when two files
+  /// map to the same path in the upload.
+  pub fn set_duplicate_path_policy(mut self, policy: DuplicatePathPolicy) -> PinByFile {
+    self.upload_options.duplicate_path_policy = policy;
+    self
+  }
+
+  /// Consumes the PinByFile and returns a new PinByFile with the content type the file is uploaded with.
+  ///
+  /// By default the content type is guessed from the file extension, falling back to
+  /// `application/octet-stream`. This is only used when a single file is uploaded.
+  pub fn set_content_type<S: Into<String>>(mut self, content_type: S) -> PinByFile {
+    self.upload_options.content_type = Some(content_type.into());
+    self
+  }
+
+  /// Consumes the PinByFile and returns a new PinByFile that fails with `ApiError::FileTooLarge`
+  /// before uploading anything if one of the files is larger than `max_size` bytes.
+  ///
+  /// By default there is no limit.
+  pub fn max_file_size(mut self, max_size: u64) -> PinByFile {
+    self.upload_options.max_file_size = Some(max_size);
+    self
+  }
+
+  /// Consumes the PinByFile and returns a new PinByFile sending the files as the multipart field
+  /// `field_name`, e.g. to upload to another pinning service sharing Pinata's multipart upload.
+  ///
+  /// Defaults to `file`, the field Pinata expects.
+  pub fn field_name<S: Into<String>>(mut self, field_name: S) -> PinByFile {
+    self.upload_options.field_name = Some(field_name.into());
+    self
+  }
+
+  /// Consumes the PinByFile and returns a new PinByFile whose upload is cancelled when `token` is
+  /// cancelled, e.g. when the user cancels the operation in a UI.
+  ///
+  /// The files being read and the request being sent are dropped as soon as the token is cancelled,
+  /// and pinning fails with `ApiError::Cancelled`. Pinata may still pin the content if the upload
+  /// was already complete. Not available on wasm.
+  ///
+  /// ```
+  /// use pinata_sdk::{CancellationToken, PinByFile};
+  ///
+  /// let token = CancellationToken::new();
+  /// let pin = PinByFile::new("large_dir").cancellation_token(token.clone());
+  /// // later, from the cancel button
+  /// token.cancel();
+  /// ```
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn cancellation_token(mut self, token: CancellationToken) -> PinByFile {
+    self.upload_options.cancellation = Some(token);
+    self
+  }
+
+  /// Consumes the PinByFile and returns a new PinByFile that follows symlinks found in directories.
+  ///
+  /// By default symlinked files found while walking a directory are uploaded with the content they
+  /// point to, and symlinked directories are skipped. When following symlinks, the content of
+  /// symlinked directories is uploaded too, and a symlink pointing back to one of its parent
+  /// directories fails the upload with `ApiError::SymlinkLoop`.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn follow_symlinks(mut self, follow: bool) -> PinByFile {
+    self.upload_options.follow_symlinks = follow;
+    self
+  }
+
+  /// Consumes the PinByFile and returns a new PinByFile that reads the files into memory
+  /// `concurrency` files at a time before uploading them.
+  ///
+  /// By default files are streamed from disk one after the other while uploading. Reading them
+  /// concurrently is faster for directories with many small files, but keeps all of them in memory.
+  /// The files are uploaded in the same order and with the same paths either way, so the resulting
+  /// CID is unchanged.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn read_concurrency(mut self, concurrency: usize) -> PinByFile {
+    self.upload_options.read_concurrency = Some(concurrency);
+    self
+  }
+
+  /// Consumes the PinByFile and returns a new PinByFile that only uploads the files of directories
+  /// matching one of `globs`, e.g. `*.html`.
+  ///
+  /// Globs are matched against the path of the file relative to the directory being pinned.
+  /// An invalid glob fails the pin with an error.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn include_globs(mut self, globs: Vec<String>) -> PinByFile {
+    self.upload_options.include_globs = globs;
+    self
+  }
+
+  /// Consumes the PinByFile and returns a new PinByFile that skips the entries of directories
+  /// matching one of `globs`, e.g. `.git`, `node_modules` or `*.log`.
+  ///
+  /// Globs are matched against the path relative to the directory being pinned. When a directory
+  /// matches, nothing inside it is uploaded. Excludes win over [include_globs](#method.include_globs).
+  /// An invalid glob fails the pin with an error.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn exclude_globs(mut self, globs: Vec<String>) -> PinByFile {
+    self.upload_options.exclude_globs = globs;
+    self
+  }
 }
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Debug, Serialize)]
 /// Sort Direction
 pub enum SortDirection {
   /// Sort by ascending dates
@@ -348,16 +998,21 @@ pub enum SortDirection {
 ///   .build().unwrap();
 /// ```
 pub struct PinJobsFilter {
+  #[serde(skip_serializing_if = "Option::is_none")]
   /// Sort the results by the date added to the pinning queue
   sort: Option<SortDirection>,
+  #[serde(skip_serializing_if = "Option::is_none")]
   /// Set a status on the PinJobsFilter
   status: Option<JobStatus>,
+  #[serde(skip_serializing_if = "Option::is_none")]
   /// Set a IPFS pin hash on the PinJobsFilter
   ipfs_pin_hash: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
   /// Set limit on the amount of results per page
-  limit: Option<u16>,
+  pub(crate) limit: Option<u16>,
+  #[serde(skip_serializing_if = "Option::is_none")]
   /// Set the record offset for records returned. This is how to retrieve additional pages
-  offset: Option<u64>,
+  pub(crate) offset: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -373,14 +1028,25 @@ pub struct PinJob {
   pub status: JobStatus,
   /// Optional name passed for hash
   pub name: Option<String>,
-  /// Optional keyvalues metadata passsed for hash
-  pub keyvalues: Option<HashMap<String, String>>,
+  /// Optional keyvalues metadata passsed for hash. Values keep the type they have in Pinata's
+  /// response, a number Pinata returns as a string stays a `MetadataValue::String`, see
+  /// [MetadataValue::coerce_number](enum.MetadataValue.html#method.coerce_number).
+  #[serde(default, deserialize_with = "metadata::deserialize_job_keyvalues")]
+  pub keyvalues: Option<MetadataKeyValues>,
   /// Optional list of host nodes passed for the hash
   pub host_nodes: Option<Vec<String>>,
   /// PinPolicy applied to content once it is found
   pub pin_policy: Option<PinPolicy>,
 }
 
+#[cfg(feature = "chrono")]
+impl PinJob {
+  /// The date the hash was queued parsed as a UTC datetime. Only available with the `chrono` feature.
+  pub fn date_queued_utc(&self) -> Result<DateTime<Utc>, ApiError> {
+    parse_timestamp(&self.date_queued)
+  }
+}
+
 #[derive(Debug, Deserialize)]
 /// Represents a list of pin job records for a set of filters.
 pub struct PinJobs {
@@ -399,7 +1065,105 @@ pub struct PinnedObject {
   /// This is how large (in bytes) the content you just pinned is
   pub pin_size: u64,
   /// Timestamp for your content pinning in ISO8601 format
-  pub timestamp: String
+  pub timestamp: String,
+  /// Whether the content was already pinned on the account, when Pinata reports it
+  #[serde(default, alias = "isDuplicate")]
+  pub is_duplicate: Option<bool>,
+  /// Fields of the response not known to the SDK
+  #[serde(flatten)]
+  pub(crate) extra: HashMap<String, serde_json::Value>,
+}
+
+impl PinnedObject {
+  /// Create a PinnedObject, e.g. to mock the responses of the SDK in tests. No duplicate flag or
+  /// extra fields are set.
+  ///
+  /// ```
+  /// use pinata_sdk::PinnedObject;
+  ///
+  /// let pinned = PinnedObject::new("QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH", 25, "2020-04-19T15:07:36.700Z");
+  /// assert!(!pinned.is_duplicate());
+  /// ```
+  pub fn new<S: Into<String>>(ipfs_hash: S, pin_size: u64, timestamp: S) -> PinnedObject {
+    PinnedObject {
+      ipfs_hash: ipfs_hash.into(),
+      pin_size,
+      timestamp: timestamp.into(),
+      is_duplicate: None,
+      extra: HashMap::new(),
+    }
+  }
+
+  /// Fields of the Pinata response that the SDK doesn't know about yet, e.g. fields added to the
+  /// API after this version of the SDK was released.
+  pub fn extra(&self) -> &HashMap<String, serde_json::Value> {
+    &self.extra
+  }
+
+  /// Whether the content was already pinned on the account, so pinning it again didn't pin new
+  /// content. `false` when Pinata doesn't report it.
+  pub fn is_duplicate(&self) -> bool {
+    self.is_duplicate.unwrap_or(false)
+  }
+
+  /// Version of the CID the content was pinned as, 0 for a `Qm...` hash and 1 for a CIDv1 like
+  /// `bafy...`, e.g. to check the `cid_version` of the pin options was honored. `None` when the
+  /// hash isn't a valid CID.
+  pub fn cid_version(&self) -> Option<u8> {
+    crate::utils::cid_version(&self.ipfs_hash)
+  }
+}
+
+#[derive(Debug)]
+/// Result of pinning content only if it isn't pinned yet with
+/// [pin_json_if_absent](struct.PinataApi.html#method.pin_json_if_absent)
+pub enum PinOutcome {
+  /// The content was already pinned under this CID, nothing was uploaded
+  AlreadyPinned(String),
+  /// The content wasn't pinned yet and was uploaded
+  NewlyPinned(PinnedObject),
+}
+
+#[derive(Debug)]
+/// Result of unpinning the pins matching a filter with
+/// [unpin_by_filter](struct.PinataApi.html#method.unpin_by_filter)
+pub struct UnpinSummary {
+  /// Number of hashes unpinned
+  pub unpinned: usize,
+  /// Hashes that couldn't be unpinned, with the error returned for each
+  pub failed: Vec<(String, ApiError)>,
+}
+
+#[derive(Debug)]
+/// Result of pinning a directory with
+/// [pin_directory_detailed](struct.PinataApi.html#method.pin_directory_detailed)
+pub struct DirectoryPinResult {
+  /// The pinned parent directory
+  pub pinned: PinnedObject,
+  /// Path relative to the pinned directory and size in bytes of each uploaded file,
+  /// in the order they were uploaded
+  pub files: Vec<(String, u64)>,
+}
+
+#[cfg(feature = "chrono")]
+impl PinnedObject {
+  /// The pinning timestamp parsed as a UTC datetime. Only available with the `chrono` feature.
+  pub fn timestamp_utc(&self) -> Result<DateTime<Utc>, ApiError> {
+    parse_timestamp(&self.timestamp)
+  }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+/// Rate limit state reported by Pinata in the headers of a response.
+///
+/// Each value is `None` when Pinata didn't send the matching header.
+pub struct RateLimitInfo {
+  /// Number of requests allowed in the current rate limit window (`x-ratelimit-limit`)
+  pub limit: Option<u64>,
+  /// Number of requests left in the current rate limit window (`x-ratelimit-remaining`)
+  pub remaining: Option<u64>,
+  /// When the current rate limit window resets, as sent by Pinata (`x-ratelimit-reset`)
+  pub reset: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -413,11 +1177,64 @@ pub struct TotalPinnedData {
   pub pin_size_with_replications_total: String,
 }
 
-#[derive(Clone, Debug, Serialize)]
+impl TotalPinnedData {
+  /// The total size of all unique content pinned, in bytes.
+  ///
+  /// Pinata returns the sizes as strings because they can exceed the precision of JavaScript numbers.
+  pub fn pin_size_total_bytes(&self) -> Result<u64, ApiError> {
+    parse_size(&self.pin_size_total)
+  }
+
+  /// The total size of all content pinned including replications, in bytes.
+  ///
+  /// Pinata returns the sizes as strings because they can exceed the precision of JavaScript numbers.
+  pub fn pin_size_with_replications_total_bytes(&self) -> Result<u64, ApiError> {
+    parse_size(&self.pin_size_with_replications_total)
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// Usage of the account, returned by [get_account_usage](struct.PinataApi.html#method.get_account_usage)
+pub struct AccountUsage {
+  /// The number of pins currently pinned
+  pub pin_count: u64,
+  /// The total size of all unique content pinned, in bytes
+  pub total_size: u64,
+  /// The total size of all content pinned including replications, in bytes
+  pub total_size_with_replications: u64,
+  /// The storage limit of the account's plan, in bytes.
+  ///
+  /// Pinata's pinning API doesn't expose the plan's limit, so it is `None` until an endpoint returns it.
+  pub storage_limit: Option<u64>,
+  /// Storage left before reaching `storage_limit`, in bytes. `None` when the limit isn't known.
+  pub remaining: Option<u64>,
+}
+
+impl AccountUsage {
+  pub(crate) fn from_total(total: &TotalPinnedData) -> Result<AccountUsage, ApiError> {
+    let pin_count = u64::try_from(total.pin_count)
+      .map_err(|_| ApiError::Parse(format!("invalid pin count {}", total.pin_count)))?;
+
+    Ok(AccountUsage {
+      pin_count,
+      total_size: total.pin_size_total_bytes()?,
+      total_size_with_replications: total.pin_size_with_replications_total_bytes()?,
+      storage_limit: None,
+      remaining: None,
+    })
+  }
+}
+
+fn parse_size(size: &str) -> Result<u64, ApiError> {
+  size.parse()
+    .map_err(|err| ApiError::Parse(format!("invalid size {:?}: {}", size, err)))
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
 #[serde(rename_all = "lowercase")]
 /// Status used with [PinListFilterBuilder](struct.PinListFilterBuilder.html)
 /// to filter on pin list results.
-pub enum PinListFilterStatus {
+pub enum PinStatus {
   /// For both pinned and unpinned records
   All,
   /// For just pinned records (hashes that are currently pinned)
@@ -426,7 +1243,37 @@ pub enum PinListFilterStatus {
   Unpinned,
 }
 
-#[derive(Builder, Default, Debug, Serialize)]
+/// Former name of [PinStatus](enum.PinStatus.html)
+#[deprecated(note = "renamed to PinStatus")]
+pub type PinListFilterStatus = PinStatus;
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(transparent)]
+/// ISO8601 datetime used to bound pin list queries.
+///
+/// Created from an already formatted string, or from a `chrono::DateTime<Utc>` with the `chrono` feature.
+pub struct FilterDate(String);
+
+impl From<String> for FilterDate {
+  fn from(date: String) -> FilterDate {
+    FilterDate(date)
+  }
+}
+
+impl From<&str> for FilterDate {
+  fn from(date: &str) -> FilterDate {
+    FilterDate(date.to_string())
+  }
+}
+
+#[cfg(feature = "chrono")]
+impl From<DateTime<Utc>> for FilterDate {
+  fn from(date: DateTime<Utc>) -> FilterDate {
+    FilterDate(date.to_rfc3339_opts(SecondsFormat::Millis, true))
+  }
+}
+
+#[derive(Builder, Clone, Default, Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 #[builder(setter(strip_option, prefix = "set"), default)]
 /// Options to filter your pin list based on a number of different options
@@ -441,23 +1288,46 @@ pub enum PinListFilterStatus {
 ///   .build()
 ///   .unwrap();
 /// ```
+///
+/// Date bounds accept an ISO8601 string, or a `chrono::DateTime<Utc>` with the `chrono` feature:
+///
+/// ```
+/// # #[cfg(feature = "chrono")]
+/// # fn main() {
+/// use chrono::{Duration, Utc};
+/// use pinata_sdk::PinListFilterBuilder;
+///
+/// // everything pinned in the last 24 hours
+/// let filter = PinListFilterBuilder::default()
+///   .set_pin_start(Utc::now() - Duration::hours(24))
+///   .set_pin_end("2030-01-01T00:00:00.000Z")
+///   .build()
+///   .unwrap();
+/// # }
+/// # #[cfg(not(feature = "chrono"))]
+/// # fn main() {}
+/// ```
 pub struct PinListFilter {
   #[serde(skip_serializing_if = "Option::is_none")]
   /// Filter on alphanumeric characters inside of pin hashes. Hashes which do not include the characters passed in will not be returned 
-  hash_contains: Option<String>,
+  pub(crate) hash_contains: Option<String>,
   #[serde(skip_serializing_if = "Option::is_none")]
   /// Exclude pin records that were pinned before the passed in "pinStart" datetime
   /// (must be in ISO_8601 format)
-  pin_start: Option<String>,
+  #[builder(setter(into))]
+  pin_start: Option<FilterDate>,
   #[serde(skip_serializing_if = "Option::is_none")]
   /// (must be in ISO_8601 format) - Exclude pin records that were pinned after the passed in "pinEnd" datetime
-  pin_end: Option<String>,
+  #[builder(setter(into))]
+  pin_end: Option<FilterDate>,
   #[serde(skip_serializing_if = "Option::is_none")]
   /// (must be in ISO_8601 format) - Exclude pin records that were unpinned before the passed in "unpinStart" datetime
-  unpin_start: Option<String>,
+  #[builder(setter(into))]
+  unpin_start: Option<FilterDate>,
   #[serde(skip_serializing_if = "Option::is_none")]
   /// (must be in ISO_8601 format) - Exclude pin records that were unpinned after the passed in "unpinEnd" datetime
-  unpin_end: Option<String>,
+  #[builder(setter(into))]
+  unpin_end: Option<FilterDate>,
   #[serde(skip_serializing_if = "Option::is_none")]
   /// The minimum byte size that pin record you're looking for can have
   pin_size_min: Option<usize>,
@@ -465,25 +1335,69 @@ pub struct PinListFilter {
   /// The maximum byte size that pin record you're looking for can have
   pin_size_max: Option<usize>,
   #[serde(skip_serializing_if = "Option::is_none")]
-  /// The status of pin lists results
-  status: Option<PinListFilterStatus>,
+  /// The status of pin lists results. When not set, Pinata returns both pinned and unpinned records
+  pub(crate) status: Option<PinStatus>,
   #[serde(skip_serializing_if = "Option::is_none")]
   /// Filter on metadata name or metadata keyvalues.
   /// If specifying a `metadata[keyvalues]` filter, you need to ensure that you encode the values as the recommended
   /// JSON accordingly. See the pinata docs [here](https://pinata.cloud/documentation#PinList) under the 'Metadata Querying'
   /// section for more details.
   metadata: Option<HashMap<String, String>>,
+  #[serde(
+    rename = "metadata[keyvalues]",
+    serialize_with = "metadata::serialize_metadata_filters",
+    skip_serializing_if = "BTreeMap::is_empty",
+  )]
+  #[builder(setter(skip))]
+  /// Conditions on metadata keyvalues, added with `add_metadata_filter()`
+  metadata_filters: MetadataFilters,
   #[serde(skip_serializing_if = "Option::is_none")]
   /// This sets the amount of records that will be returned per API response. (Max 1000)
-  page_limit: Option<String>,
+  pub(crate) page_limit: Option<u16>,
   #[serde(skip_serializing_if = "Option::is_none")]
   /// This tells the API how far to offset the record responses. For example, if there's 30 records that match your query, and you passed in a pageLimit of 10, providing a pageOffset of 10 would return records 11-20
-  page_offset: Option<String>,
+  pub(crate) page_offset: Option<u64>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  /// Sort the results by the date the content was pinned
+  sort: Option<SortDirection>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  /// Only return pin records of content in the [Group](struct.Group.html) with this id
+  pub(crate) group_id: Option<String>,
+}
+
+impl PinListFilter {
+  /// Consumes the filter and returns a new filter that only matches pins whose `key` metadata
+  /// value compares to `value` with `op`. Adding a second filter for the same key replaces the first one.
+  ///
+  /// ```
+  /// use pinata_sdk::{MetadataOp, MetadataValue, PinListFilter};
+  ///
+  /// // all pins where version > 2
+  /// let filter = PinListFilter::default()
+  ///   .add_metadata_filter("version", MetadataOp::Gt, MetadataValue::Integer(2));
+  /// ```
+  pub fn add_metadata_filter(mut self, key: &str, op: MetadataOp, value: MetadataValue) -> PinListFilter {
+    self.metadata_filters.insert(key.to_string(), MetadataFilter::new(op, value));
+    self
+  }
+
+  /// Whether the filter restricts which pins match. The status, paging and sort settings don't count.
+  pub(crate) fn has_constraints(&self) -> bool {
+    self.hash_contains.is_some()
+      || self.pin_start.is_some()
+      || self.pin_end.is_some()
+      || self.unpin_start.is_some()
+      || self.unpin_end.is_some()
+      || self.pin_size_min.is_some()
+      || self.pin_size_max.is_some()
+      || self.metadata.iter().any(|metadata| !metadata.is_empty())
+      || !self.metadata_filters.is_empty()
+  }
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
-/// RegionPolicy active on the PinListItem
+/// RegionPolicy active on the PinRecord
 pub struct PinListItemRegionPolicy {
   /// Region this content is expected to be pinned in
   pub region_id: Region,
@@ -494,35 +1408,484 @@ pub struct PinListItemRegionPolicy {
 }
 
 #[derive(Debug, Deserialize)]
-/// A pinned item gotten from get PinList request
+/// A pin record gotten from get PinList request
 /// 
 /// This is usually as part of the PinList struct which is gotten as response
-pub struct PinListItem {
+pub struct PinRecord {
   /// The id of this pin item record
   pub id: String,
   /// The IPFS multihash for this items content
   pub ipfs_pin_hash: String,
   /// Size in bytes of the content pinned
-  pub size: usize,
+  pub size: u64,
   /// Users Pinata id
   pub user_id: String,
   /// ISO 8601 timestamp for when this content was pinned.
-  pub date_pinned: String,
+  pub date_pinned: Option<String>,
   /// ISO 8601 timestamp for when this content was unpinned.
   /// 
   /// Is None for content that is not yet unpinned
-  pub data_unpinned: Option<String>,
+  pub date_unpinned: Option<String>,
   /// Metadata of the original uploaded files
   pub metadata: PinListMetadata,
   /// Region Policy set on the item
   pub regions: Vec<PinListItemRegionPolicy>,
 }
 
+impl PinRecord {
+  /// The number of replications of the content so far, summed over all of its regions.
+  ///
+  /// Use `regions` to check the replications of a single region, e.g. that the content is
+  /// replicated to `Region::FRA1`.
+  pub fn replication_count(&self) -> u8 {
+    self.regions.iter()
+      .fold(0u8, |count, region| count.saturating_add(region.current_replication_count))
+  }
+}
+
+#[cfg(feature = "chrono")]
+impl PinRecord {
+  /// The date the content was pinned parsed as a UTC datetime. Only available with the `chrono` feature.
+  pub fn date_pinned_utc(&self) -> Result<Option<DateTime<Utc>>, ApiError> {
+    self.date_pinned.as_deref().map(parse_timestamp).transpose()
+  }
+
+  /// The date the content was unpinned parsed as a UTC datetime. Only available with the `chrono` feature.
+  pub fn date_unpinned_utc(&self) -> Result<Option<DateTime<Utc>>, ApiError> {
+    self.date_unpinned.as_deref().map(parse_timestamp).transpose()
+  }
+}
+
+/// Former name of [PinRecord](struct.PinRecord.html)
+#[deprecated(note = "renamed to PinRecord")]
+pub type PinListItem = PinRecord;
+
 #[derive(Debug, Deserialize)]
 /// Result of request to get pinList
 pub struct PinList {
   /// Total number of pin records that exist for the query filters passed
-  pub count: u128,
-  /// List of pinned item in the result set
-  pub rows: Vec<PinListItem>,
-}
\ No newline at end of file
+  pub count: u64,
+  /// List of pin records in the result set
+  pub rows: Vec<PinRecord>,
+}
+
+#[derive(Clone, Debug)]
+/// Key used to group pins when computing a cost breakdown
+pub enum BreakdownKey {
+  /// Group pins by the value of this metadata keyvalue
+  MetadataKey(String),
+  /// Group pins by the id of the [Group](struct.Group.html) they were added to
+  Group,
+}
+
+#[derive(Clone, Debug, Default)]
+/// Storage cost for a group of pins in a cost breakdown
+pub struct Cost {
+  /// Number of pins in the group
+  pub pin_count: u64,
+  /// Total size in bytes of the pins in the group
+  pub total_size: u64,
+  /// Cost of storing the pins in the group based on the per GB price
+  pub cost: f64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+/// Number and total size of the pins in a size bucket
+pub struct SizeBucket {
+  /// Number of pins in the bucket
+  pub pin_count: u64,
+  /// Total size in bytes of the pins in the bucket
+  pub total_size: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+/// Distribution of the size of the pinned content, computed with
+/// [pinned_data_summary](struct.PinataApi.html#method.pinned_data_summary).
+///
+/// A MB is 1024^2 bytes. Each bucket includes its lower bound and excludes its upper bound.
+pub struct PinnedDataSummary {
+  /// Pins smaller than 1MB
+  pub under_1mb: SizeBucket,
+  /// Pins from 1MB to 10MB
+  pub from_1mb_to_10mb: SizeBucket,
+  /// Pins from 10MB to 100MB
+  pub from_10mb_to_100mb: SizeBucket,
+  /// Pins of 100MB or more
+  pub over_100mb: SizeBucket,
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+  #[cfg(feature = "chrono")]
+  use chrono::{TimeZone, Utc};
+  use serde_json::json;
+  use crate::api::metadata::{MetadataOp, MetadataValue};
+  use crate::errors::ApiError;
+  use super::{
+    PinByHash, PinByJson, PinListFilter, PinList, PinOptions, PinListFilterBuilder, PinPolicy, PinStatus, Region,
+    RegionPolicy, SortDirection,
+  };
+
+  #[test]
+  fn test_add_host_node_creates_and_appends_options() {
+    let pin = PinByHash::new("QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH")
+      .add_host_node("/ip4/1.2.3.4/tcp/4001/p2p/QmNodeA")
+      .add_host_node("/ip4/5.6.7.8/tcp/4001/p2p/QmNodeB");
+
+    let value = serde_json::to_value(&pin).unwrap();
+    assert_eq!(value["pinataOptions"]["hostNodes"], json!([
+      "/ip4/1.2.3.4/tcp/4001/p2p/QmNodeA",
+      "/ip4/5.6.7.8/tcp/4001/p2p/QmNodeB",
+    ]));
+  }
+
+  #[cfg(feature = "chrono")]
+  #[test]
+  fn test_pin_list_date_bounds_accept_dates_and_strings() {
+    let filter = PinListFilterBuilder::default()
+      .set_pin_start(Utc.with_ymd_and_hms(2021, 3, 4, 5, 6, 7).unwrap())
+      .set_unpin_end("2021-04-01T00:00:00.000Z")
+      .build()
+      .unwrap();
+
+    let value = serde_json::to_value(&filter).unwrap();
+    assert_eq!(value["pinStart"], json!("2021-03-04T05:06:07.000Z"));
+    assert_eq!(value["unpinEnd"], json!("2021-04-01T00:00:00.000Z"));
+    assert!(value.get("pinEnd").is_none());
+  }
+
+  #[test]
+  fn test_pin_list_status_paging_and_sort_query() {
+    let filter = PinListFilterBuilder::default()
+      .set_status(PinStatus::Unpinned)
+      .set_page_limit(10)
+      .set_page_offset(20)
+      .set_sort(SortDirection::DESC)
+      .build()
+      .unwrap();
+
+    let value = serde_json::to_value(&filter).unwrap();
+    assert_eq!(value, json!({
+      "status": "unpinned",
+      "pageLimit": 10,
+      "pageOffset": 20,
+      "sort": "DESC",
+    }));
+
+    let default_value = serde_json::to_value(PinListFilterBuilder::default().build().unwrap()).unwrap();
+    assert_eq!(default_value, json!({}));
+  }
+
+  #[test]
+  fn test_pin_list_metadata_filters_query() {
+    let filter = PinListFilter::default()
+      .add_metadata_filter("version", MetadataOp::Gt, MetadataValue::Integer(2))
+      .add_metadata_filter("year", MetadataOp::Between(MetadataValue::Integer(2021)), MetadataValue::Integer(2019));
+
+    let request = reqwest::Client::new()
+      .get("https://api.pinata.cloud/data/pinList")
+      .query(&filter)
+      .build()
+      .unwrap();
+    let query: Vec<(String, String)> = request.url().query_pairs().into_owned().collect();
+
+    assert_eq!(query, vec![(
+      "metadata[keyvalues]".to_string(),
+      r#"{"version":{"value":2,"op":"gt"},"year":{"value":2019,"secondValue":2021,"op":"between"}}"#.to_string(),
+    )]);
+  }
+
+  #[test]
+  fn test_pin_list_deserializes_pin_records() {
+    let pin_list: PinList = serde_json::from_str(r#"{
+      "count": 2,
+      "rows": [
+        {
+          "id": "cc7f924f-7246-42ce-a1c7-f0067ac02144",
+          "ipfs_pin_hash": "Qme5npQ51psDHssScnXqeKoUvA7UizxhRCfZi6ewMiiHcn",
+          "size": 36,
+          "user_id": "6176135e-fd99-4af9-a27c-23dd9d8e0461",
+          "date_pinned": "2020-04-19T15:07:36.700Z",
+          "date_unpinned": "2020-04-20T10:00:00.000Z",
+          "metadata": {
+            "name": "old-metadata-name",
+            "keyvalues": { "to_be_preserved": 5.5, "new_value": "awesome" }
+          },
+          "regions": [
+            { "regionId": "FRA1", "desiredReplicationCount": 1, "currentReplicationCount": 1 }
+          ]
+        },
+        {
+          "id": "4d1c35ea-0b34-4a4e-9c4c-7c7c9f2f2a55",
+          "ipfs_pin_hash": "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH",
+          "size": 5368709120,
+          "user_id": "6176135e-fd99-4af9-a27c-23dd9d8e0461",
+          "date_pinned": "2020-04-19T15:07:36.700Z",
+          "date_unpinned": null,
+          "metadata": { "name": null, "keyvalues": null },
+          "regions": []
+        }
+      ]
+    }"#).unwrap();
+
+    assert_eq!(pin_list.count, 2);
+    let record = &pin_list.rows[0];
+    assert_eq!(record.date_unpinned.as_deref(), Some("2020-04-20T10:00:00.000Z"));
+    let keyvalues = record.metadata.keyvalues.as_ref().unwrap();
+    assert_eq!(keyvalues["to_be_preserved"], MetadataValue::Float(5.5));
+    assert_eq!(keyvalues["new_value"], MetadataValue::String("awesome".to_string()));
+    assert_eq!(record.regions[0].region_id, Region::FRA1);
+
+    assert_eq!(pin_list.rows[1].size, 5_368_709_120);
+    assert!(pin_list.rows[1].metadata.keyvalues.is_none());
+  }
+
+  #[test]
+  fn test_pin_policy_validates_replication_counts() {
+    let policy = |id, count| PinPolicy { regions: vec![RegionPolicy { id, desired_replication_count: count }] };
+
+    assert!(policy(Region::FRA1, 2).validate().is_ok());
+    assert!(policy(Region::Custom("SGP1".into()), 5).validate().is_ok());
+    match policy(Region::NYC1, 10).validate() {
+      Err(ApiError::InvalidReplicationCount { region: Region::NYC1, requested: 10, max: 2 }) => (),
+      other => panic!("expected InvalidReplicationCount, got {:?}", other),
+    }
+  }
+
+  #[test]
+  fn test_region_round_trips_unknown_codes() {
+    let regions: Vec<Region> = serde_json::from_str(r#"["FRA1", "NYC1", "SGP1"]"#).unwrap();
+    assert_eq!(regions, vec![Region::FRA1, Region::NYC1, Region::Custom("SGP1".to_string())]);
+
+    assert_eq!(serde_json::to_value(&regions).unwrap(), json!(["FRA1", "NYC1", "SGP1"]));
+  }
+
+  #[test]
+  fn test_pin_options_with_only_cid_version_has_one_key() {
+    let options = serde_json::to_value(PinOptions::new().cid_version(0)).unwrap();
+    assert_eq!(options.as_object().unwrap().len(), 1);
+    assert_eq!(options["cidVersion"], json!(0));
+  }
+
+  #[test]
+  fn test_pin_requests_skip_unset_metadata_and_options() {
+    assert_eq!(serde_json::to_value(PinByHash::new("QmHash")).unwrap(), json!({ "hashToPin": "QmHash" }));
+    assert_eq!(serde_json::to_value(PinByJson::new("data")).unwrap(), json!({ "pinataContent": "data" }));
+  }
+
+  #[test]
+  fn test_set_name_and_metadata_compose() {
+    let mut keyvalues = HashMap::new();
+    keyvalues.insert("year".to_string(), MetadataValue::Integer(2021));
+    let expected = json!({ "name": "report", "keyvalues": { "year": 2021 } });
+
+    let name_first = PinByJson::new("data").set_name("report").set_metadata(keyvalues.clone());
+    let metadata_first = PinByJson::new("data").set_metadata(keyvalues).set_name("report");
+    assert_eq!(serde_json::to_value(&name_first).unwrap()["pinataMetadata"], expected);
+    assert_eq!(serde_json::to_value(&metadata_first).unwrap()["pinataMetadata"], expected);
+
+    let name_only = PinByHash::new("QmHash").set_name("report");
+    assert_eq!(serde_json::to_value(&name_only).unwrap()["pinataMetadata"], json!({ "name": "report", "keyvalues": {} }));
+  }
+
+  #[test]
+  fn test_set_cid_version_keeps_other_options() {
+    let pin = PinByHash::new("QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH")
+      .set_options(PinOptions::new().wrap_with_directory(true))
+      .set_cid_version(1)
+      .unwrap();
+    let value = serde_json::to_value(&pin).unwrap();
+    assert_eq!(value["pinataOptions"], json!({ "cidVersion": 1, "wrapWithDirectory": true }));
+
+    let value = serde_json::to_value(PinByJson::new("data").set_cid_version(0).unwrap()).unwrap();
+    assert_eq!(value["pinataOptions"], json!({ "cidVersion": 0 }));
+
+    match PinByJson::new("data").set_cid_version(2) {
+      Err(ApiError::InvalidCidVersion(2)) => (),
+      _ => panic!("CID version 2 should be rejected"),
+    }
+  }
+
+  #[test]
+  fn test_raw_json_is_sent_unchanged() {
+    let pin = PinByJson::from_raw(r#"{"z": 1,  "a": [true]}"#.to_string()).unwrap();
+    let body = serde_json::to_string(&pin).unwrap();
+    assert_eq!(body, r#"{"pinataContent":{"z": 1,  "a": [true]}}"#);
+
+    match PinByJson::from_raw("{\"unterminated\": ".to_string()) {
+      Err(ApiError::InvalidJson(_)) => (),
+      _ => panic!("invalid JSON should be rejected"),
+    }
+  }
+
+  #[test]
+  fn test_json_is_compact_unless_pretty() {
+    let pin = PinByJson::new(json!([{ "name": "user" }]));
+    let compact = String::from_utf8(pin.to_body().unwrap()).unwrap();
+    assert_eq!(compact, r#"{"pinataContent":[{"name":"user"}]}"#);
+
+    let pretty = String::from_utf8(pin.pretty(true).to_body().unwrap()).unwrap();
+    assert_eq!(pretty, "{\n  \"pinataContent\": [\n    {\n      \"name\": \"user\"\n    }\n  ]\n}");
+    let parse = |body: &str| serde_json::from_str::<serde_json::Value>(body).unwrap();
+    assert_eq!(parse(&pretty), parse(&compact));
+  }
+
+  #[test]
+  fn test_pin_options_skip_unset_fields() {
+    let options = PinOptions::new()
+      .cid_version(1)
+      .wrap_with_directory(true);
+
+    assert_eq!(serde_json::to_value(&options).unwrap(), json!({
+      "cidVersion": 1,
+      "wrapWithDirectory": true,
+    }));
+    assert_eq!(serde_json::to_value(PinOptions::new()).unwrap(), json!({}));
+  }
+
+  #[cfg(feature = "chrono")]
+  #[test]
+  fn test_timestamps_parse_as_utc() {
+    let pinned: super::PinnedObject = serde_json::from_value(json!({
+      "IpfsHash": "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH",
+      "PinSize": 36,
+      "Timestamp": "2020-04-19T15:07:36.700Z"
+    })).unwrap();
+
+    let expected = Utc.with_ymd_and_hms(2020, 4, 19, 15, 7, 36).unwrap() + chrono::Duration::milliseconds(700);
+    assert_eq!(pinned.timestamp_utc().unwrap(), expected);
+
+    let pinned = super::PinnedObject { timestamp: "yesterday".to_string(), ..pinned };
+    assert!(pinned.timestamp_utc().is_err());
+  }
+
+  #[test]
+  fn test_pinned_object_keeps_unknown_fields() {
+    let pinned: super::PinnedObject = serde_json::from_value(json!({
+      "IpfsHash": "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH",
+      "PinSize": 36,
+      "Timestamp": "2020-04-19T15:07:36.700Z",
+      "NewPinataField": { "nested": true }
+    })).unwrap();
+
+    assert_eq!(pinned.ipfs_hash, "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH");
+    assert_eq!(pinned.extra().len(), 1);
+    assert_eq!(pinned.extra()["NewPinataField"], json!({ "nested": true }));
+  }
+
+  #[test]
+  fn test_pinned_object_duplicate_flag() {
+    let pinned = |flag: serde_json::Value| -> super::PinnedObject {
+      let mut response = json!({
+        "IpfsHash": "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH",
+        "PinSize": 36,
+        "Timestamp": "2020-04-19T15:07:36.700Z"
+      });
+      response.as_object_mut().unwrap().extend(flag.as_object().unwrap().clone());
+      serde_json::from_value(response).unwrap()
+    };
+
+    let duplicate = pinned(json!({ "isDuplicate": true }));
+    assert_eq!(duplicate.is_duplicate, Some(true));
+    assert!(duplicate.is_duplicate());
+    assert!(duplicate.extra().is_empty());
+
+    assert!(pinned(json!({ "IsDuplicate": true })).is_duplicate());
+    assert!(!pinned(json!({ "isDuplicate": false })).is_duplicate());
+
+    let fresh = pinned(json!({}));
+    assert_eq!(fresh.is_duplicate, None);
+    assert!(!fresh.is_duplicate());
+  }
+
+  #[test]
+  fn test_total_pinned_data_sizes_parse() {
+    let total: super::TotalPinnedData = serde_json::from_value(json!({
+      "pin_count": 3,
+      "pin_size_total": "9007199254740993",
+      "pin_size_with_replications_total": "not a number"
+    })).unwrap();
+
+    assert_eq!(total.pin_size_total_bytes().unwrap(), 9_007_199_254_740_993);
+    match total.pin_size_with_replications_total_bytes() {
+      Err(crate::errors::ApiError::Parse(message)) => assert!(message.contains("not a number")),
+      other => panic!("expected a parse error, got {:?}", other),
+    }
+  }
+
+  #[test]
+  fn test_pin_record_replication_count() {
+    let record: super::PinRecord = serde_json::from_value(json!({
+      "id": "a",
+      "ipfs_pin_hash": "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH",
+      "size": 10,
+      "user_id": "user",
+      "date_pinned": "2020-04-19T15:07:36.700Z",
+      "date_unpinned": null,
+      "metadata": { "name": null, "keyvalues": null },
+      "regions": [
+        { "regionId": "FRA1", "desiredReplicationCount": 2, "currentReplicationCount": 2 },
+        { "regionId": "NYC1", "desiredReplicationCount": 2, "currentReplicationCount": 1 }
+      ]
+    })).unwrap();
+
+    assert_eq!(record.regions.len(), 2);
+    assert_eq!(record.regions[0].region_id, Region::FRA1);
+    assert_eq!(record.regions[1].region_id, Region::NYC1);
+    assert_eq!(record.regions[1].desired_replication_count, 2);
+    assert_eq!(record.replication_count(), 3);
+  }
+
+  #[test]
+  fn test_pinned_object_cid_version() {
+    let pinned = |hash: &str| -> super::PinnedObject {
+      serde_json::from_value(json!({
+        "IpfsHash": hash,
+        "PinSize": 10,
+        "Timestamp": "2020-04-19T15:07:36.700Z"
+      })).unwrap()
+    };
+
+    assert_eq!(pinned("QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH").cid_version(), Some(0));
+    assert_eq!(pinned("bafybeieu6apdz5r6ws2vxs2fc7o5nvg6nbsqzlr7iacs3d4j5brb3dqjhq").cid_version(), Some(1));
+    assert_eq!(pinned("not-a-cid").cid_version(), None);
+  }
+
+  #[test]
+  fn test_pin_job_keyvalues_keep_their_types() {
+    let job: super::PinJob = serde_json::from_value(json!({
+      "id": "5f8f8f1e-1c2d-4b3a-9e8f-7a6b5c4d3e2f",
+      "ipfs_pin_hash": "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH",
+      "date_queued": "2020-04-19T15:07:36.700Z",
+      "status": "searching",
+      "name": "report",
+      "keyvalues": {
+        "title": "annual",
+        "zip": "007",
+        "pages": 12,
+        "ratio": 0.5,
+        "copies": "3",
+        "score": "1.5",
+        "postcode": "12345",
+        "draft": false,
+        "removed": null
+      },
+      "host_nodes": null,
+      "pin_policy": null
+    })).unwrap();
+
+    let keyvalues = job.keyvalues.unwrap();
+    assert_eq!(keyvalues.len(), 8);
+    assert_eq!(keyvalues["title"], MetadataValue::String("annual".to_string()));
+    assert_eq!(keyvalues["zip"], MetadataValue::String("007".to_string()));
+    assert_eq!(keyvalues["pages"], MetadataValue::Integer(12));
+    assert_eq!(keyvalues["ratio"], MetadataValue::Float(0.5));
+    assert_eq!(keyvalues["postcode"], MetadataValue::String("12345".to_string()));
+    assert_eq!(keyvalues["draft"], MetadataValue::Bool(false));
+
+    assert_eq!(keyvalues["copies"].clone().coerce_number(), MetadataValue::Integer(3));
+    assert_eq!(keyvalues["score"].clone().coerce_number(), MetadataValue::Float(1.5));
+    assert_eq!(keyvalues["zip"].clone().coerce_number(), MetadataValue::String("007".to_string()));
+  }
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// A dedicated gateway of the account
+pub struct Gateway {
+  /// Pinata's id for the gateway
+  pub id: String,
+  /// Subdomain of the gateway, e.g. `example` for `example.mypinata.cloud`
+  pub domain: String,
+  /// Whether the gateway only serves content pinned by the account
+  #[serde(rename = "restrict")]
+  pub restricted: bool,
+  /// Custom domains pointing at the gateway
+  #[serde(default)]
+  pub custom_domains: Vec<CustomDomain>,
+  /// Date the gateway was created
+  pub created_at: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// A custom domain pointing at a dedicated gateway
+pub struct CustomDomain {
+  /// Pinata's id for the custom domain
+  pub id: String,
+  /// The custom domain, e.g. `ipfs.example.com`
+  pub domain: String,
+  /// Verification status of the domain reported by Pinata, when available
+  pub domain_status: Option<String>,
+}
+
+#[derive(Deserialize)]
+/// Response listing the dedicated gateways
+pub(crate) struct GatewayList {
+  pub(crate) rows: Vec<Gateway>,
+}
+
+#[derive(Serialize)]
+/// Request body used to change who can access a gateway
+pub(crate) struct GatewayAccess {
+  pub(crate) restrict: bool,
+}
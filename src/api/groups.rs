@@ -0,0 +1,30 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// A Pinata group used to organize pinned content
+pub struct Group {
+  /// Pinata's id for the group
+  pub id: String,
+  /// Name of the group
+  pub name: String,
+  /// Id of the user owning the group
+  #[serde(rename = "user_id")]
+  pub user_id: Option<String>,
+  /// Date the group was created
+  pub created_at: String,
+  /// Date the group was last updated
+  pub updated_at: String,
+}
+
+#[derive(Serialize)]
+/// Request body used to create or rename a group
+pub(crate) struct GroupName {
+  pub(crate) name: String,
+}
+
+#[derive(Serialize)]
+/// Request body used to add or remove content from a group
+pub(crate) struct GroupCids {
+  pub(crate) cids: Vec<String>,
+}
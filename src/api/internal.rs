@@ -5,11 +5,52 @@ use serde::Deserialize;
 #[derive(Deserialize)]
 /// Error response structure from pinata
 pub(crate) struct PinataApiError {
-  error: String
+  error: PinataErrorBody,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+/// Pinata returns errors either as a plain message or as a structured reason with details
+enum PinataErrorBody {
+  Message(String),
+  Structured {
+    reason: Option<String>,
+    details: Option<String>,
+  },
 }
 
 impl PinataApiError {
     pub fn message(&self) -> String {
-      self.error.clone()
+      match &self.error {
+        PinataErrorBody::Message(message) => message.clone(),
+        PinataErrorBody::Structured { reason, details } => details.clone()
+          .or_else(|| reason.clone())
+          .unwrap_or_default(),
+      }
+    }
+
+    pub fn reason(&self) -> Option<String> {
+      match &self.error {
+        PinataErrorBody::Message(_) => None,
+        PinataErrorBody::Structured { reason, .. } => reason.clone(),
+      }
     }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::PinataApiError;
+
+  #[test]
+  fn test_error_message_formats() {
+    let error: PinataApiError = serde_json::from_str(r#"{"error": "Invalid hash"}"#).unwrap();
+    assert_eq!(error.message(), "Invalid hash");
+    assert_eq!(error.reason(), None);
+
+    let error: PinataApiError = serde_json::from_str(
+      r#"{"error": {"reason": "KEY_REVOKED", "details": "This API key has been revoked"}}"#
+    ).unwrap();
+    assert_eq!(error.message(), "This API key has been revoked");
+    assert_eq!(error.reason(), Some("KEY_REVOKED".to_string()));
+  }
+}
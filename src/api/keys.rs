@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+/// Data endpoints a scoped api key can call
+pub struct DataScopes {
+  /// Allow `get_pin_list` and the functions built on it
+  pub pin_list: bool,
+  /// Allow `get_total_user_pinned_data`
+  pub user_pinned_data_total: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+/// Pinning endpoints a scoped api key can call
+pub struct PinningScopes {
+  /// Allow `change_hash_metadata`
+  pub hash_metadata: bool,
+  /// Allow `set_hash_pin_policy`
+  pub hash_pin_policy: bool,
+  /// Allow `pin_by_hash`
+  pub pin_by_hash: bool,
+  /// Allow `pin_file` and the other file uploads
+  #[serde(rename = "pinFileToIPFS")]
+  pub pin_file_to_ipfs: bool,
+  /// Allow `pin_json`
+  #[serde(rename = "pinJSONToIPFS")]
+  pub pin_json_to_ipfs: bool,
+  /// Allow `get_pin_jobs`
+  pub pin_jobs: bool,
+  /// Allow `unpin`
+  pub unpin: bool,
+  /// Allow `set_user_pin_policy`
+  pub user_pin_policy: bool,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+/// Endpoints a scoped api key can call, grouped like the Pinata API
+pub struct EndpointScopes {
+  /// Data endpoints
+  pub data: DataScopes,
+  /// Pinning endpoints
+  pub pinning: PinningScopes,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+/// Permissions of a scoped api key.
+///
+/// The default scopes allow nothing, enable the endpoints the key needs:
+/// ```
+/// use pinata_sdk::ApiKeyScopes;
+///
+/// let mut scopes = ApiKeyScopes::default();
+/// scopes.endpoints.pinning.pin_file_to_ipfs = true;
+/// scopes.endpoints.pinning.pin_json_to_ipfs = true;
+/// ```
+pub struct ApiKeyScopes {
+  /// Allow every endpoint, including managing api keys. `endpoints` is ignored when set.
+  pub admin: bool,
+  /// Endpoints the key can call
+  pub endpoints: EndpointScopes,
+}
+
+impl ApiKeyScopes {
+  /// Scopes allowing every endpoint
+  pub fn admin() -> ApiKeyScopes {
+    ApiKeyScopes { admin: true, ..Default::default() }
+  }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+/// Request used to generate a new scoped api key with
+/// [PinataApi::generate_api_key](struct.PinataApi.html#method.generate_api_key)
+pub struct GenerateApiKeyRequest {
+  pub(crate) key_name: String,
+  pub(crate) permissions: ApiKeyScopes,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) max_uses: Option<u32>,
+}
+
+impl GenerateApiKeyRequest {
+  /// Create a request for a key named `key_name` allowed to call the endpoints in `scopes`.
+  pub fn new<S: Into<String>>(key_name: S, scopes: ApiKeyScopes) -> GenerateApiKeyRequest {
+    GenerateApiKeyRequest {
+      key_name: key_name.into(),
+      permissions: scopes,
+      max_uses: None,
+    }
+  }
+
+  /// Consumes the current GenerateApiKeyRequest and returns a new one limiting the number of
+  /// requests the key can make. By default the key can be used without limit.
+  pub fn set_max_uses(mut self, max_uses: u32) -> GenerateApiKeyRequest {
+    self.max_uses = Some(max_uses);
+    self
+  }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+/// Credentials of a newly generated api key. The secret is only returned once, when the key is generated.
+pub struct GeneratedApiKey {
+  /// The api key
+  #[serde(rename = "pinata_api_key")]
+  pub api_key: String,
+  /// The secret api key
+  #[serde(rename = "pinata_api_secret")]
+  pub secret_api_key: String,
+  /// JWT authenticating as the key
+  #[serde(rename = "JWT")]
+  pub jwt: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+/// An api key of the account, as listed by
+/// [PinataApi::list_api_keys](struct.PinataApi.html#method.list_api_keys)
+pub struct ApiKeyInfo {
+  /// Pinata's id for the key
+  pub id: String,
+  /// Name given to the key when it was generated
+  pub name: String,
+  /// The api key
+  pub key: String,
+  /// Endpoints the key can call
+  pub scopes: ApiKeyScopes,
+  /// Maximum number of requests the key can make, if limited
+  pub max_uses: Option<u32>,
+  /// Number of requests made with the key
+  #[serde(default)]
+  pub uses: u32,
+  /// Whether the key was revoked
+  pub revoked: bool,
+  /// Date the key was generated
+  #[serde(rename = "createdAt")]
+  pub created_at: String,
+}
+
+#[derive(Deserialize)]
+/// A page of the api keys listing
+pub(crate) struct ApiKeyPage {
+  pub(crate) keys: Vec<ApiKeyInfo>,
+  pub(crate) count: u64,
+}
+
+#[derive(Serialize)]
+/// Query used to request a page of the api keys listing
+pub(crate) struct ApiKeyPageQuery {
+  pub(crate) offset: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+/// Request body used to revoke an api key
+pub(crate) struct RevokeApiKey {
+  pub(crate) api_key: String,
+}
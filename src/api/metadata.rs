@@ -1,25 +1,146 @@
-use std::collections::HashMap;
-use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use crate::errors::ApiError;
 
-#[derive(Debug, Deserialize, Serialize)]
+/// Maximum number of keyvalues Pinata accepts in the metadata of a pin
+pub(crate) const MAX_KEYVALUES: usize = 10;
+/// Maximum number of characters Pinata accepts in the name of a pin
+pub(crate) const MAX_NAME_LENGTH: usize = 255;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 #[serde(untagged)]
 /// Possible MetadaValues
+///
+/// Values are (de)serialized as plain JSON values. Whole numbers are read back as `Integer`
+/// and numbers with a fraction as `Float`.
 pub enum MetadataValue {
   /// Represents a String metadata value
   String(String),
+  /// Represents an integer value
+  Integer(i64),
   /// Represents a float metadata value
   Float(f64),
-  /// Represents an integer value
-  Integer(u64),
-  /// Only valid when used with a ChangePinMetadata request
+  /// Represents a boolean value
+  Bool(bool),
+  /// Only valid when used with a ChangePinMetadata request, serialized as `null`
   Delete,
 }
 
+impl MetadataValue {
+  /// Reads a string holding a number back as an `Integer` or a `Float`, e.g. a number Pinata
+  /// returned as a string. Only numbers written the way they would be serialized are converted,
+  /// `"10"` and `"1.5"` are but `"007"` isn't. Other values are returned unchanged.
+  ///
+  /// Strings are kept as strings when deserializing, call this only for keys known to hold numbers:
+  /// a value stored as the string `"12345"`, e.g. a zip code, would be converted too.
+  pub fn coerce_number(self) -> MetadataValue {
+    match self {
+      MetadataValue::String(text) => number_in_string(&text).unwrap_or(MetadataValue::String(text)),
+      value => value,
+    }
+  }
+}
+
 /// alias type for HashMap<String, MetadataValue>
 pub type MetadataKeyValues = HashMap<String, MetadataValue>;
 
+#[derive(Clone, Debug, PartialEq)]
+/// Comparison operators supported when filtering the pin list on metadata keyvalues.
+///
+/// Used with [PinListFilter::add_metadata_filter](struct.PinListFilter.html#method.add_metadata_filter).
+pub enum MetadataOp {
+  /// Value equals the filter value
+  Eq,
+  /// Value doesn't equal the filter value
+  Ne,
+  /// Value is greater than the filter value
+  Gt,
+  /// Value is greater than or equal to the filter value
+  Gte,
+  /// Value is less than the filter value
+  Lt,
+  /// Value is less than or equal to the filter value
+  Lte,
+  /// Value is between the filter value and the upper bound held by this variant
+  Between(MetadataValue),
+  /// Value is not between the filter value and the upper bound held by this variant
+  NotBetween(MetadataValue),
+  /// Value matches the filter value used as a pattern, e.g. `%report%`
+  Like,
+  /// Value doesn't match the filter value used as a pattern
+  NotLike,
+  /// Value matches the filter value used as a case insensitive pattern
+  ILike,
+  /// Value doesn't match the filter value used as a case insensitive pattern
+  NotILike,
+  /// Value matches the filter value used as a regular expression
+  Regexp,
+  /// Value matches the filter value used as a case insensitive regular expression
+  IRegexp,
+}
+
+impl MetadataOp {
+  /// Name of the operator in Pinata's metadata queries
+  fn code(&self) -> &'static str {
+    match self {
+      MetadataOp::Eq => "eq",
+      MetadataOp::Ne => "ne",
+      MetadataOp::Gt => "gt",
+      MetadataOp::Gte => "gte",
+      MetadataOp::Lt => "lt",
+      MetadataOp::Lte => "lte",
+      MetadataOp::Between(_) => "between",
+      MetadataOp::NotBetween(_) => "notBetween",
+      MetadataOp::Like => "like",
+      MetadataOp::NotLike => "notLike",
+      MetadataOp::ILike => "iLike",
+      MetadataOp::NotILike => "notILike",
+      MetadataOp::Regexp => "regexp",
+      MetadataOp::IRegexp => "iRegexp",
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+/// Condition on a single metadata key of a pin list query
+pub(crate) struct MetadataFilter {
+  value: MetadataValue,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  second_value: Option<MetadataValue>,
+  op: &'static str,
+}
+
+impl MetadataFilter {
+  pub(crate) fn new(op: MetadataOp, value: MetadataValue) -> MetadataFilter {
+    let code = op.code();
+    let second_value = match op {
+      MetadataOp::Between(upper) | MetadataOp::NotBetween(upper) => Some(upper),
+      _ => None,
+    };
+
+    MetadataFilter { value, second_value, op: code }
+  }
+}
+
+/// Metadata filters of a pin list query, keyed by metadata key
+pub(crate) type MetadataFilters = BTreeMap<String, MetadataFilter>;
+
+/// Pinata expects the keyvalues filters as a single JSON encoded query parameter
+pub(crate) fn serialize_metadata_filters<S>(filters: &MetadataFilters, serializer: S) -> Result<S::Ok, S::Error>
+  where S: Serializer
+{
+  let json = serde_json::to_string(filters).map_err(serde::ser::Error::custom)?;
+  serializer.serialize_str(&json)
+}
+
 #[derive(Debug, Serialize)]
 /// Pin metadata stored along with files pinned.
+///
+/// Pinata allows at most 10 keyvalues and names of at most 255 characters. Pinning with more
+/// keyvalues fails with `ApiError::TooManyKeyValues`, and with a longer name with
+/// `ApiError::NameTooLong`. The setters of the pin requests trim whitespace around the name, a
+/// blank name being no name.
 pub struct PinMetadata {
   #[serde(skip_serializing_if = "Option::is_none")]
   /// Custom name used for referencing your pinned content.
@@ -28,6 +149,98 @@ pub struct PinMetadata {
   pub keyvalues: MetadataKeyValues,
 }
 
+impl PinMetadata {
+  /// Checks the metadata is within the limits Pinata accepts
+  pub(crate) fn validate(&self) -> Result<(), ApiError> {
+    if let Some(name) = &self.name {
+      let len = name.chars().count();
+      if len > MAX_NAME_LENGTH {
+        return Err(ApiError::NameTooLong { len, max: MAX_NAME_LENGTH });
+      }
+    }
+    validate_keyvalues(&self.keyvalues)
+  }
+}
+
+/// Checks there are no more than `MAX_KEYVALUES` keyvalues. Keys marked with `MetadataValue::Delete`
+/// are removed by Pinata, so they are not counted.
+pub(crate) fn validate_keyvalues(keyvalues: &MetadataKeyValues) -> Result<(), ApiError> {
+  let count = keyvalues.values()
+    .filter(|value| **value != MetadataValue::Delete)
+    .count();
+
+  if count > MAX_KEYVALUES {
+    Err(ApiError::TooManyKeyValues { count })
+  } else {
+    Ok(())
+  }
+}
+
+/// Converts JSON keyvalues to metadata values. Only strings, numbers and booleans are valid values.
+pub(crate) fn keyvalues_from_json(keyvalues: HashMap<String, serde_json::Value>) -> Result<MetadataKeyValues, ApiError> {
+  keyvalues.into_iter()
+    .map(|(key, value)| {
+      let value = match value {
+        serde_json::Value::String(value) => MetadataValue::String(value),
+        serde_json::Value::Bool(value) => MetadataValue::Bool(value),
+        serde_json::Value::Number(number) => match (number.as_i64(), number.as_f64()) {
+          (Some(value), _) => MetadataValue::Integer(value),
+          (None, Some(value)) => MetadataValue::Float(value),
+          (None, None) => return Err(ApiError::InvalidMetadataValue { key }),
+        },
+        _ => return Err(ApiError::InvalidMetadataValue { key }),
+      };
+      Ok((key, value))
+    })
+    .collect()
+}
+
+/// Deserializes the keyvalues of a pin job, keeping each value the type it has in the response.
+/// Null values are skipped.
+pub(crate) fn deserialize_job_keyvalues<'de, D>(deserializer: D) -> Result<Option<MetadataKeyValues>, D::Error>
+  where D: Deserializer<'de>
+{
+  let keyvalues: Option<HashMap<String, serde_json::Value>> = Option::deserialize(deserializer)?;
+  keyvalues
+    .map(|keyvalues| {
+      let keyvalues = keyvalues.into_iter().filter(|(_, value)| !value.is_null()).collect();
+      keyvalues_from_json(keyvalues).map_err(de::Error::custom)
+    })
+    .transpose()
+}
+
+/// The number held by `text`, if it is written exactly as the number would be serialized
+fn number_in_string(text: &str) -> Option<MetadataValue> {
+  let (number, written) = match text.parse::<i64>() {
+    Ok(integer) => (MetadataValue::Integer(integer), integer.to_string()),
+    Err(_) => {
+      let float = text.parse::<f64>().ok().filter(|float| float.is_finite())?;
+      (MetadataValue::Float(float), serde_json::Value::from(float).to_string())
+    },
+  };
+  Some(number).filter(|_| written == text)
+}
+
+/// The metadata with its keyvalues replaced by `keyvalues`, keeping its name
+pub(crate) fn with_keyvalues(metadata: Option<PinMetadata>, keyvalues: MetadataKeyValues) -> PinMetadata {
+  PinMetadata { name: metadata.and_then(|metadata| metadata.name), keyvalues }
+}
+
+/// The metadata with its name replaced by `name`, keeping its keyvalues
+pub(crate) fn with_name(metadata: Option<PinMetadata>, name: String) -> PinMetadata {
+  PinMetadata { name: normalize_name(name), keyvalues: metadata.map(|metadata| metadata.keyvalues).unwrap_or_default() }
+}
+
+/// `name` without the whitespace around it, a blank name being no name
+pub(crate) fn normalize_name(name: String) -> Option<String> {
+  Some(name.trim().to_string()).filter(|name| !name.is_empty())
+}
+
+/// Checks the optional metadata of a pin request is within the limits Pinata accepts
+pub(crate) fn validate_metadata(metadata: Option<&PinMetadata>) -> Result<(), ApiError> {
+  metadata.map_or(Ok(()), PinMetadata::validate)
+}
+
 #[derive(Debug, Deserialize)]
 /// Pin metadata returns from PinList query
 /// 
@@ -40,6 +253,44 @@ pub struct PinListMetadata {
   pub keyvalues: Option<MetadataKeyValues>,
 }
 
+impl From<PinListMetadata> for PinMetadata {
+  fn from(metadata: PinListMetadata) -> PinMetadata {
+    PinMetadata {
+      name: metadata.name,
+      keyvalues: metadata.keyvalues.unwrap_or_default(),
+    }
+  }
+}
+
+impl PinListMetadata {
+  /// Returns the metadata change needed to update this metadata to `desired`,
+  /// or `None` if it already matches.
+  ///
+  /// A `None` name in `desired` leaves the current name as it is, while keys missing
+  /// in `desired.keyvalues` are marked with `MetadataValue::Delete`.
+  pub(crate) fn changes_to(&self, desired: PinMetadata) -> Option<PinMetadata> {
+    let PinMetadata { name, keyvalues: desired_keyvalues } = desired;
+    let name = name.filter(|name| self.name.as_ref() != Some(name));
+
+    let empty = MetadataKeyValues::new();
+    let current = self.keyvalues.as_ref().unwrap_or(&empty);
+    let mut keyvalues: MetadataKeyValues = current.keys()
+      .filter(|key| !desired_keyvalues.contains_key(*key))
+      .map(|key| (key.clone(), MetadataValue::Delete))
+      .collect();
+    keyvalues.extend(
+      desired_keyvalues.into_iter()
+        .filter(|(key, value)| current.get(key) != Some(value))
+    );
+
+    if name.is_none() && keyvalues.is_empty() {
+      None
+    } else {
+      Some(PinMetadata { name, keyvalues })
+    }
+  }
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 /// Pin metadata struct to update metadata of pinned items.
@@ -53,11 +304,54 @@ pub struct ChangePinMetadata {
   pub metadata: PinMetadata,
 }
 
+impl ChangePinMetadata {
+  /// Create a change of the metadata of `ipfs_pin_hash` leaving the name and keyvalues as they are.
+  ///
+  /// Use the `set_name()`, `add_keyvalue()` and `delete_keyvalue()` chainable functions to
+  /// describe the updates:
+  /// ```
+  /// use pinata_sdk::{ChangePinMetadata, MetadataValue};
+  ///
+  /// let change = ChangePinMetadata::new("QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH")
+  ///   .set_name("report.pdf")
+  ///   .add_keyvalue("reviewed", MetadataValue::Bool(true))
+  ///   .delete_keyvalue("draft");
+  /// ```
+  pub fn new<S: Into<String>>(ipfs_pin_hash: S) -> ChangePinMetadata {
+    ChangePinMetadata {
+      ipfs_pin_hash: ipfs_pin_hash.into(),
+      metadata: PinMetadata { name: None, keyvalues: MetadataKeyValues::new() },
+    }
+  }
+
+  /// Consumes the current ChangePinMetadata and returns a new one renaming the pin to `name`.
+  ///
+  /// Whitespace around the name is trimmed and a blank name leaves the name as it is. Names over
+  /// [Pinata's limit](struct.PinMetadata.html) fail with `ApiError::NameTooLong`.
+  pub fn set_name<S: Into<String>>(mut self, name: S) -> ChangePinMetadata {
+    self.metadata.name = normalize_name(name.into());
+    self
+  }
+
+  /// Consumes the current ChangePinMetadata and returns a new one setting the keyvalue `key` to `value`.
+  pub fn add_keyvalue<S: Into<String>>(mut self, key: S, value: MetadataValue) -> ChangePinMetadata {
+    self.metadata.keyvalues.insert(key.into(), value);
+    self
+  }
+
+  /// Consumes the current ChangePinMetadata and returns a new one removing the keyvalue `key`
+  /// from the pin, by setting it to `MetadataValue::Delete`.
+  pub fn delete_keyvalue<S: Into<String>>(self, key: S) -> ChangePinMetadata {
+    self.add_keyvalue(key, MetadataValue::Delete)
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use std::collections::HashMap;
   use serde_json::Value;
-  use super::{PinMetadata, MetadataValue};
+  use crate::errors::ApiError;
+  use super::{keyvalues_from_json, validate_keyvalues, ChangePinMetadata, PinListMetadata, PinMetadata, MetadataValue};
 
   #[test]
   fn test_serialization_of_metadata() {
@@ -100,7 +394,7 @@ mod tests {
           }
 
           if let Value::Number(number) = keyvalues.get("number").unwrap() {
-            assert_eq!(10, number.as_u64().unwrap());
+            assert_eq!(10, number.as_i64().unwrap());
           } else {
             assert!(false, "keyvalues.number is not a number");
           }
@@ -113,4 +407,130 @@ mod tests {
       assert!(false, "metadata not serialized as object");
     }
   }
-}
\ No newline at end of file
+
+  #[test]
+  fn test_change_pin_metadata_builder() {
+    let change = ChangePinMetadata::new("QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH")
+      .set_name("report.pdf")
+      .add_keyvalue("pages", MetadataValue::Integer(12))
+      .delete_keyvalue("draft");
+
+    assert_eq!(serde_json::to_value(&change).unwrap(), serde_json::json!({
+      "ipfsPinHash": "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH",
+      "name": "report.pdf",
+      "keyvalues": {
+        "pages": 12,
+        "draft": null,
+      },
+    }));
+  }
+
+  #[test]
+  fn test_metadata_value_json_shapes() {
+    let values = vec![
+      (MetadataValue::String("value".to_string()), r#""value""#),
+      (MetadataValue::Integer(-10), "-10"),
+      (MetadataValue::Float(1.5), "1.5"),
+      (MetadataValue::Bool(true), "true"),
+      (MetadataValue::Delete, "null"),
+    ];
+
+    for (value, json) in values {
+      assert_eq!(serde_json::to_string(&value).unwrap(), json);
+      assert_eq!(serde_json::from_str::<MetadataValue>(json).unwrap(), value);
+    }
+  }
+
+  #[test]
+  fn test_keyvalues_from_json() {
+    let mut json = HashMap::new();
+    json.insert("name".to_string(), serde_json::json!("report"));
+    json.insert("count".to_string(), serde_json::json!(3));
+    json.insert("ratio".to_string(), serde_json::json!(0.5));
+    json.insert("public".to_string(), serde_json::json!(true));
+
+    let keyvalues = keyvalues_from_json(json.clone()).unwrap();
+    assert_eq!(keyvalues["name"], MetadataValue::String("report".to_string()));
+    assert_eq!(keyvalues["count"], MetadataValue::Integer(3));
+    assert_eq!(keyvalues["ratio"], MetadataValue::Float(0.5));
+    assert_eq!(keyvalues["public"], MetadataValue::Bool(true));
+
+    for invalid in [serde_json::json!({ "nested": 1 }), serde_json::json!([1, 2]), serde_json::Value::Null] {
+      let mut json = json.clone();
+      json.insert("invalid".to_string(), invalid);
+      match keyvalues_from_json(json) {
+        Err(ApiError::InvalidMetadataValue { key }) => assert_eq!(key, "invalid"),
+        other => panic!("expected InvalidMetadataValue, got {:?}", other),
+      }
+    }
+  }
+
+  #[test]
+  fn test_keyvalues_limit() {
+    let mut keyvalues: HashMap<_, _> = (0..10)
+      .map(|index| (index.to_string(), MetadataValue::Integer(index)))
+      .collect();
+    keyvalues.insert("deleted".to_string(), MetadataValue::Delete);
+    assert!(validate_keyvalues(&keyvalues).is_ok());
+
+    keyvalues.insert("extra".to_string(), MetadataValue::Bool(true));
+    match validate_keyvalues(&keyvalues) {
+      Err(ApiError::TooManyKeyValues { count }) => assert_eq!(count, 11),
+      other => panic!("expected TooManyKeyValues, got {:?}", other),
+    }
+  }
+
+  #[test]
+  fn test_name_is_trimmed_and_limited() {
+    let change = ChangePinMetadata::new("QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH").set_name("  report.pdf\n");
+    assert_eq!(change.metadata.name, Some("report.pdf".to_string()));
+    assert_eq!(ChangePinMetadata::new("QmHash").set_name("   ").metadata.name, None);
+
+    let name = "é".repeat(255);
+    let metadata = PinMetadata { name: Some(name), keyvalues: HashMap::new() };
+    assert!(metadata.validate().is_ok());
+
+    let metadata = PinMetadata { name: Some("a".repeat(256)), keyvalues: HashMap::new() };
+    match metadata.validate() {
+      Err(ApiError::NameTooLong { len, max }) => assert_eq!((len, max), (256, 255)),
+      other => panic!("expected NameTooLong, got {:?}", other),
+    }
+  }
+
+  #[test]
+  fn test_metadata_changes_only_include_differences() {
+    let mut current_keyvalues = HashMap::new();
+    current_keyvalues.insert("same".to_string(), MetadataValue::Integer(1));
+    current_keyvalues.insert("updated".to_string(), MetadataValue::String("old".to_string()));
+    current_keyvalues.insert("removed".to_string(), MetadataValue::Float(1.5));
+    let current = PinListMetadata {
+      name: Some("name".to_string()),
+      keyvalues: Some(current_keyvalues),
+    };
+
+    let mut desired_keyvalues = HashMap::new();
+    desired_keyvalues.insert("same".to_string(), MetadataValue::Integer(1));
+    desired_keyvalues.insert("updated".to_string(), MetadataValue::String("new".to_string()));
+    let changes = current.changes_to(PinMetadata {
+      name: Some("name".to_string()),
+      keyvalues: desired_keyvalues,
+    }).unwrap();
+
+    assert_eq!(changes.name, None);
+    assert_eq!(changes.keyvalues.len(), 2);
+    assert_eq!(changes.keyvalues["updated"], MetadataValue::String("new".to_string()));
+    assert_eq!(changes.keyvalues["removed"], MetadataValue::Delete);
+  }
+
+  #[test]
+  fn test_no_metadata_changes_when_unchanged() {
+    let mut keyvalues = HashMap::new();
+    keyvalues.insert("key".to_string(), MetadataValue::String("value".to_string()));
+    let current = PinListMetadata {
+      name: Some("name".to_string()),
+      keyvalues: Some(keyvalues.clone()),
+    };
+
+    assert!(current.changes_to(PinMetadata { name: None, keyvalues }).is_none());
+  }
+}
@@ -1,3 +1,6 @@
 pub mod metadata;
 pub mod data;
-pub mod internal;
\ No newline at end of file
+pub mod internal;
+pub mod groups;
+pub mod gateways;
+pub mod keys;
\ No newline at end of file
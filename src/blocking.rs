@@ -0,0 +1,403 @@
+//! A blocking version of the Pinata API, for programs that don't run a tokio runtime.
+//!
+//! The blocking [PinataApi](struct.PinataApi.html) wraps the async [PinataApi](../struct.PinataApi.html)
+//! and drives each request to completion on a single threaded tokio runtime it owns. Unlike
+//! `reqwest::blocking` no separate client is used: all requests are built, sent and parsed by the
+//! async api, so both behave the same.
+//!
+//! Calls made from within an async runtime don't panic, the request is then driven on a separate
+//! thread while the calling thread waits for it. The caller's thread is still blocked for the
+//! whole request, so async code should use the async api instead.
+//!
+//! This module is only available with the `blocking` feature.
+//!
+//! ## Example
+//! ```no_run
+//! use pinata_sdk::{ApiError, PinByFile};
+//! use pinata_sdk::blocking::PinataApi;
+//!
+//! # fn run() -> Result<(), ApiError> {
+//! let api = PinataApi::new("api_key", "secret_api_key").unwrap();
+//!
+//! let pinned_object = api.pin_file(PinByFile::new("file_or_dir_path"))?;
+//! println!("pinned {}", pinned_object.ipfs_hash);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::panic;
+use std::thread;
+use std::time::Duration;
+use bytes::Bytes;
+use futures::StreamExt;
+use reqwest::Method;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tokio::runtime::{Builder, Runtime};
+use crate::errors::{ApiError, Error};
+use crate::{
+  AccountUsage, ApiKeyInfo, BreakdownKey, ChangePinMetadata, Cost, DirectoryPinResult, Gateway, GenerateApiKeyRequest,
+  GeneratedApiKey, Group, HashPinPolicy, MetadataKeyValues, PinByFile, PinByHash, PinByHashResult, PinByJson, PinJob, PinJobs,
+  PinJobsFilter, PinList, PinListFilter, PinMetadata, PinOutcome, PinPolicy, PinRecord, PinnedDataSummary,
+  PinnedObject, RateLimitInfo, TotalPinnedData, UnpinSummary,
+};
+
+/// Blocking API struct. Exposes the functions of the async [PinataApi](../struct.PinataApi.html)
+/// as functions that block until the request is done.
+pub struct PinataApi {
+  inner: crate::PinataApi,
+  // only taken when dropped
+  runtime: Option<Runtime>,
+}
+
+impl PinataApi {
+  /// Creates a new instance of the blocking PinataApi using the provided keys.
+  pub fn new<S: Into<String>>(api_key: S, secret_api_key: S) -> Result<PinataApi, Error> {
+    PinataApi::from_async(crate::PinataApi::new(api_key, secret_api_key)?)
+  }
+
+  /// Creates a new instance of the blocking PinataApi authenticated with a JWT.
+  pub fn new_with_jwt<S: Into<String>>(jwt: S) -> Result<PinataApi, Error> {
+    PinataApi::from_async(crate::PinataApi::new_with_jwt(jwt)?)
+  }
+
+  /// Creates a new instance of the blocking PinataApi using credentials read from the environment.
+  ///
+  /// See [PinataApi::from_env](../struct.PinataApi.html#method.from_env) for the variables read.
+  pub fn from_env() -> Result<PinataApi, Error> {
+    PinataApi::from_async(crate::PinataApi::from_env()?)
+  }
+
+  /// Creates a blocking PinataApi from an async one, e.g. one configured with
+  /// [PinataApiBuilder](../struct.PinataApiBuilder.html).
+  pub fn from_async(api: crate::PinataApi) -> Result<PinataApi, Error> {
+    let runtime = Builder::new_current_thread()
+      .enable_all()
+      .build()?;
+
+    Ok(PinataApi { inner: api, runtime: Some(runtime) })
+  }
+
+  /// Drives `future` to completion on the runtime, from a separate thread when called from within
+  /// an async runtime as blocking its thread would panic.
+  fn block_on<F>(&self, future: F) -> F::Output
+    where F: Future + Send, F::Output: Send
+  {
+    let runtime = self.runtime.as_ref().expect("runtime is only taken on drop");
+    if tokio::runtime::Handle::try_current().is_err() {
+      return runtime.block_on(future);
+    }
+
+    thread::scope(|scope| {
+      scope.spawn(|| runtime.block_on(future))
+        .join()
+        .unwrap_or_else(|payload| panic::resume_unwind(payload))
+    })
+  }
+
+  /// Test if your credentials are corrects. It returns an error if credentials are not correct
+  pub fn test_authentication(&self) -> Result<(), ApiError> {
+    self.block_on(self.inner.test_authentication())
+  }
+
+  /// Test if Pinata can be reached with your credentials, telling bad credentials apart from
+  /// network failures and outages of Pinata. See
+  /// [PinataApi::check_connection](../struct.PinataApi.html#method.check_connection).
+  pub fn check_connection(&self) -> Result<(), ApiError> {
+    self.block_on(self.inner.check_connection())
+  }
+
+  /// Change the pin policy for an individual piece of content.
+  pub fn set_hash_pin_policy(&self, policy: HashPinPolicy) -> Result<(), ApiError> {
+    self.block_on(self.inner.set_hash_pin_policy(policy))
+  }
+
+  /// Change the account level pin policy, used by default for all content pinned afterwards.
+  pub fn set_user_pin_policy(&self, policy: PinPolicy, migrate_existing: bool) -> Result<(), ApiError> {
+    self.block_on(self.inner.set_user_pin_policy(policy, migrate_existing))
+  }
+
+  /// Add a hash to Pinata for asynchronous pinning.
+  pub fn pin_by_hash(&self, hash: PinByHash) -> Result<PinByHashResult, ApiError> {
+    self.block_on(self.inner.pin_by_hash(hash))
+  }
+
+  /// Retrieve a list of all the pins that are currently in the pin queue for your user
+  pub fn get_pin_jobs(&self, filters: PinJobsFilter) -> Result<PinJobs, ApiError> {
+    self.block_on(self.inner.get_pin_jobs(filters))
+  }
+
+  /// Get the pin job with id `job_id`.
+  pub fn get_pin_job(&self, job_id: &str) -> Result<PinJob, ApiError> {
+    self.block_on(self.inner.get_pin_job(job_id))
+  }
+
+  /// Iterate over all the jobs in the pin queue matching `filters`, fetching the pages as they are read.
+  pub fn get_pin_jobs_iter(&self, filters: PinJobsFilter) -> impl Iterator<Item = Result<PinJob, ApiError>> + '_ {
+    let mut jobs = Box::pin(self.inner.get_pin_jobs_stream(filters));
+    std::iter::from_fn(move || self.block_on(jobs.next()))
+  }
+
+  /// Wait for the pin by hash job with id `job_id` to finish, checking its status every `poll_interval`.
+  pub fn wait_for_pin(&self, job_id: &str, poll_interval: Duration, timeout: Duration) -> Result<PinJob, ApiError> {
+    self.block_on(self.inner.wait_for_pin(job_id, poll_interval, timeout))
+  }
+
+  /// Cancel the pin by hash job with id `job_id`, so Pinata stops searching for its content.
+  pub fn cancel_pin_job(&self, job_id: &str) -> Result<(), ApiError> {
+    self.block_on(self.inner.cancel_pin_job(job_id))
+  }
+
+  /// Pin any JSON serializable object to Pinata IPFS nodes.
+  pub fn pin_json<S>(&self, pin_data: PinByJson<S>) -> Result<PinnedObject, ApiError>
+    where S: Serialize + Send
+  {
+    self.block_on(self.inner.pin_json(pin_data))
+  }
+
+  /// Pin a JSON serializable object with a name, keyvalues metadata, a group and a CID version in one call.
+  pub fn pin_json_full<S>(
+    &self,
+    content: S,
+    name: &str,
+    keyvalues: MetadataKeyValues,
+    group_id: &str,
+    cid_version: u8,
+  ) -> Result<PinnedObject, ApiError>
+    where S: Serialize + Send
+  {
+    self.block_on(self.inner.pin_json_full(content, name, keyvalues, group_id, cid_version))
+  }
+
+  /// Pin a JSON object only if it isn't pinned on the account yet.
+  pub fn pin_json_if_absent<S>(&self, pin_data: PinByJson<S>) -> Result<PinOutcome, ApiError>
+    where S: Serialize + Send
+  {
+    self.block_on(self.inner.pin_json_if_absent(pin_data))
+  }
+
+  /// Pin several JSON objects, returning the result of pinning each of them in the same order as `items`.
+  pub fn pin_json_many<S>(
+    &self,
+    items: Vec<PinByJson<S>>,
+    concurrency: Option<usize>,
+  ) -> Vec<Result<PinnedObject, ApiError>>
+    where S: Serialize + Send
+  {
+    self.block_on(self.inner.pin_json_many(items, concurrency))
+  }
+
+  /// Pin any file or folder to Pinata's IPFS nodes.
+  pub fn pin_file(&self, pin_data: PinByFile) -> Result<PinnedObject, ApiError> {
+    self.block_on(self.inner.pin_file(pin_data))
+  }
+
+  /// Pin a single file, then fetch it back through the gateway to verify the pinned content.
+  pub fn pin_file_verified(&self, pin_data: PinByFile) -> Result<PinnedObject, ApiError> {
+    self.block_on(self.inner.pin_file_verified(pin_data))
+  }
+
+  /// Pin a directory, also returning the path and size of every file uploaded.
+  pub fn pin_directory_detailed(&self, pin_data: PinByFile) -> Result<DirectoryPinResult, ApiError> {
+    self.block_on(self.inner.pin_directory_detailed(pin_data))
+  }
+
+  /// Pin any file or folder to Pinata's IPFS nodes, reporting the upload progress.
+  pub fn pin_file_with_progress<F>(&self, pin_data: PinByFile, progress: F) -> Result<PinnedObject, ApiError>
+    where F: FnMut(u64, u64) + Send + 'static
+  {
+    self.block_on(self.inner.pin_file_with_progress(pin_data, progress))
+  }
+
+  /// Unpin content previously uploaded to the Pinata's IPFS nodes.
+  pub fn unpin(&self, hash: &str) -> Result<(), ApiError> {
+    self.block_on(self.inner.unpin(hash))
+  }
+
+  /// Unpin several hashes, returning the result of unpinning each of them in the same order as `hashes`.
+  pub fn unpin_many(&self, hashes: &[String], concurrency: Option<usize>) -> Vec<(String, Result<(), ApiError>)> {
+    self.block_on(self.inner.unpin_many(hashes, concurrency))
+  }
+
+  /// Unpin the content for `hash`, returning whether it was pinned.
+  pub fn try_unpin(&self, hash: &str) -> Result<bool, ApiError> {
+    self.block_on(self.inner.try_unpin(hash))
+  }
+
+  /// Unpin all the content currently pinned that matches `filters`.
+  ///
+  /// See [PinataApi::unpin_by_filter](../struct.PinataApi.html#method.unpin_by_filter) for the safety check on the filter.
+  pub fn unpin_by_filter(&self, filters: PinListFilter) -> Result<UnpinSummary, ApiError> {
+    self.block_on(self.inner.unpin_by_filter(filters))
+  }
+
+  /// Change name and custom key values associated for a piece of content stored on Pinata.
+  pub fn change_hash_metadata(&self, change: ChangePinMetadata) -> Result<(), ApiError> {
+    self.block_on(self.inner.change_hash_metadata(change))
+  }
+
+  /// Change the metadata of several pins, returning the result of each change with the hash it was made
+  /// to in the same order as `changes`.
+  pub fn change_hash_metadata_many(
+    &self,
+    changes: Vec<ChangePinMetadata>,
+    concurrency: Option<usize>,
+  ) -> Vec<(String, Result<(), ApiError>)> {
+    self.block_on(self.inner.change_hash_metadata_many(changes, concurrency))
+  }
+
+  /// Get the name and keyvalues metadata of a piece of content pinned on Pinata.
+  pub fn get_hash_metadata(&self, ipfs_pin_hash: &str) -> Result<PinMetadata, ApiError> {
+    self.block_on(self.inner.get_hash_metadata(ipfs_pin_hash))
+  }
+
+  /// Change the metadata of a piece of content only if it differs from its current metadata.
+  pub fn set_metadata_if_changed<S>(&self, ipfs_pin_hash: S, desired: PinMetadata) -> Result<bool, ApiError>
+    where S: Into<String>
+  {
+    self.block_on(self.inner.set_metadata_if_changed(ipfs_pin_hash.into(), desired))
+  }
+
+  /// This endpoint returns the total combined size for all content that you've pinned through Pinata
+  pub fn get_total_user_pinned_data(&self) -> Result<TotalPinnedData, ApiError> {
+    self.block_on(self.inner.get_total_user_pinned_data())
+  }
+
+  /// Get the number of pins and the storage used by your account.
+  pub fn get_account_usage(&self) -> Result<AccountUsage, ApiError> {
+    self.block_on(self.inner.get_account_usage())
+  }
+
+  /// This returns data on what content the sender has pinned to IPFS from pinata
+  pub fn get_pin_list(&self, filters: PinListFilter) -> Result<PinList, ApiError> {
+    self.block_on(self.inner.get_pin_list(filters))
+  }
+
+  /// Fetch all the pins matching `filters`, reading the pin list page by page.
+  pub fn get_all_pins(&self, filters: PinListFilter) -> Result<Vec<PinRecord>, ApiError> {
+    self.block_on(self.inner.get_all_pins(filters))
+  }
+
+  /// Compute the storage cost of the pinned content grouped by `by`.
+  pub fn cost_breakdown(&self, by: BreakdownKey) -> Result<HashMap<String, Cost>, ApiError> {
+    self.block_on(self.inner.cost_breakdown(by))
+  }
+
+  /// Summarize the size of your pinned content in size buckets.
+  pub fn pinned_data_summary(&self) -> Result<PinnedDataSummary, ApiError> {
+    self.block_on(self.inner.pinned_data_summary())
+  }
+
+  /// Export all the pins on your account as CSV to the provided writer.
+  #[cfg(feature = "csv")]
+  pub fn export_pins_csv<W>(&self, writer: W) -> Result<u64, ApiError>
+    where W: std::io::Write + Send
+  {
+    self.block_on(self.inner.export_pins_csv(writer))
+  }
+
+  /// Create a new group named `name` to organize pinned content.
+  pub fn create_group<S: Into<String>>(&self, name: S) -> Result<Group, ApiError> {
+    self.block_on(self.inner.create_group(name.into()))
+  }
+
+  /// List the groups of the authenticated user.
+  pub fn list_groups(&self) -> Result<Vec<Group>, ApiError> {
+    self.block_on(self.inner.list_groups())
+  }
+
+  /// Get the group with id `group_id`.
+  pub fn get_group(&self, group_id: &str) -> Result<Group, ApiError> {
+    self.block_on(self.inner.get_group(group_id))
+  }
+
+  /// Rename the group with id `group_id`.
+  pub fn update_group<S: Into<String>>(&self, group_id: &str, name: S) -> Result<Group, ApiError> {
+    self.block_on(self.inner.update_group(group_id, name.into()))
+  }
+
+  /// Delete the group with id `group_id`. Content in the group stays pinned.
+  pub fn delete_group(&self, group_id: &str) -> Result<(), ApiError> {
+    self.block_on(self.inner.delete_group(group_id))
+  }
+
+  /// Add already pinned content to the group with id `group_id`.
+  pub fn add_cids_to_group(&self, group_id: &str, cids: Vec<String>) -> Result<(), ApiError> {
+    self.block_on(self.inner.add_cids_to_group(group_id, cids))
+  }
+
+  /// Remove content from the group with id `group_id`. The content stays pinned.
+  pub fn remove_cids_from_group(&self, group_id: &str, cids: Vec<String>) -> Result<(), ApiError> {
+    self.block_on(self.inner.remove_cids_from_group(group_id, cids))
+  }
+
+  /// Generate a new api key allowed to call the endpoints in the request's scopes.
+  pub fn generate_api_key(&self, request: GenerateApiKeyRequest) -> Result<GeneratedApiKey, ApiError> {
+    self.block_on(self.inner.generate_api_key(request))
+  }
+
+  /// List the api keys of the account, including revoked keys.
+  pub fn list_api_keys(&self) -> Result<Vec<ApiKeyInfo>, ApiError> {
+    self.block_on(self.inner.list_api_keys())
+  }
+
+  /// Revoke the api key `api_key`.
+  pub fn revoke_api_key(&self, api_key: &str) -> Result<(), ApiError> {
+    self.block_on(self.inner.revoke_api_key(api_key))
+  }
+
+  /// List the dedicated gateways of the account.
+  pub fn list_gateways(&self) -> Result<Vec<Gateway>, ApiError> {
+    self.block_on(self.inner.list_gateways())
+  }
+
+  /// Change who can access the dedicated gateway `domain`.
+  pub fn update_gateway_access(&self, domain: &str, restricted: bool) -> Result<(), ApiError> {
+    self.block_on(self.inner.update_gateway_access(domain, restricted))
+  }
+
+  /// Returns the url to retrieve the content for `cid` through the configured gateway.
+  pub fn gateway_url(&self, cid: &str) -> String {
+    self.inner.gateway_url(cid)
+  }
+
+  /// Returns the url to retrieve the file at `path` inside the directory pinned as `cid`
+  /// through the configured gateway.
+  pub fn gateway_url_with_path(&self, cid: &str, path: &str) -> String {
+    self.inner.gateway_url_with_path(cid, path)
+  }
+
+  /// Returns the rate limit state reported by the most recent Pinata API response.
+  pub fn last_rate_limit(&self) -> Option<RateLimitInfo> {
+    self.inner.last_rate_limit()
+  }
+
+  /// Send a request to any Pinata API endpoint, e.g. one this crate doesn't support yet.
+  pub fn request<T>(
+    &self,
+    method: Method,
+    path: &str,
+    body: Option<serde_json::Value>,
+    query: Option<serde_json::Value>,
+  ) -> Result<T, ApiError>
+    where T: DeserializeOwned + Send
+  {
+    self.block_on(self.inner.request(method, path, body, query))
+  }
+
+  /// Fetch the content for `cid` through the configured gateway.
+  pub fn fetch(&self, cid: &str) -> Result<Bytes, ApiError> {
+    self.block_on(self.inner.fetch(cid))
+  }
+}
+
+impl Drop for PinataApi {
+  fn drop(&mut self) {
+    // dropping a runtime from within an async runtime panics, shutting it down in the background doesn't
+    if let Some(runtime) = self.runtime.take() {
+      runtime.shutdown_background();
+    }
+  }
+}
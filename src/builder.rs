@@ -0,0 +1,368 @@
+use std::time::Duration;
+use reqwest::ClientBuilder;
+#[cfg(not(target_arch = "wasm32"))]
+use reqwest::{Proxy, Url};
+#[cfg(not(target_arch = "wasm32"))]
+use reqwest::redirect::Policy;
+use reqwest::header::{HeaderMap, HeaderName};
+use crate::{HttpTransport, PinataApi, PinOptions};
+use crate::errors::{ApiError, Error};
+use crate::utils::{self, Credentials, RetryConfig};
+
+/// Maximum number of redirects followed by default
+#[cfg(not(target_arch = "wasm32"))]
+const MAX_REDIRECTS: usize = 10;
+
+/// Builder used to configure and create a [PinataApi](struct.PinataApi.html).
+///
+/// ## Example
+/// ```
+/// use pinata_sdk::PinataApi;
+/// use std::time::Duration;
+///
+/// let api = PinataApi::builder()
+///   .api_keys("api_key", "secret_api_key")
+///   .timeout(Duration::from_secs(120))
+///   .connect_timeout(Duration::from_secs(10))
+///   .build()
+///   .unwrap();
+/// ```
+#[derive(Default)]
+pub struct PinataApiBuilder {
+  credentials: Option<Credentials>,
+  base_url: Option<String>,
+  gateway_url: Option<String>,
+  #[cfg(not(target_arch = "wasm32"))]
+  timeout: Option<Duration>,
+  #[cfg(not(target_arch = "wasm32"))]
+  connect_timeout: Option<Duration>,
+  #[cfg(not(target_arch = "wasm32"))]
+  proxies: Vec<Proxy>,
+  #[cfg(not(target_arch = "wasm32"))]
+  pool_max_idle_per_host: Option<usize>,
+  #[cfg(not(target_arch = "wasm32"))]
+  pool_idle_timeout: Option<Duration>,
+  #[cfg(not(target_arch = "wasm32"))]
+  user_agent: Option<String>,
+  #[cfg(not(target_arch = "wasm32"))]
+  danger_accept_invalid_certs: bool,
+  #[cfg(not(target_arch = "wasm32"))]
+  redirect: Option<Policy>,
+  #[cfg(all(feature = "gzip", not(target_arch = "wasm32")))]
+  gzip: Option<bool>,
+  #[cfg(all(feature = "brotli", not(target_arch = "wasm32")))]
+  brotli: Option<bool>,
+  #[cfg(all(feature = "deflate", not(target_arch = "wasm32")))]
+  deflate: Option<bool>,
+  retry: RetryConfig,
+  default_pin_options: Option<PinOptions>,
+  price_per_gb: Option<f64>,
+  default_headers: Vec<(String, String)>,
+  transport: Option<Box<dyn HttpTransport>>,
+}
+
+impl PinataApiBuilder {
+  /// Authenticate using the api key and secret api key
+  pub fn api_keys<S: Into<String>>(mut self, api_key: S, secret_api_key: S) -> PinataApiBuilder {
+    self.credentials = Some(Credentials::ApiKeys(api_key.into(), secret_api_key.into()));
+    self
+  }
+
+  /// Authenticate using a JWT sent as a bearer token
+  pub fn jwt<S: Into<String>>(mut self, jwt: S) -> PinataApiBuilder {
+    self.credentials = Some(Credentials::Jwt(jwt.into()));
+    self
+  }
+
+  /// Send requests to `base_url` instead of `https://api.pinata.cloud`
+  pub fn base_url<S: Into<String>>(mut self, base_url: S) -> PinataApiBuilder {
+    self.base_url = Some(base_url.into());
+    self
+  }
+
+  /// Set the IPFS gateway used to build content urls and fetch pinned content.
+  ///
+  /// Defaults to the public Pinata gateway `https://gateway.pinata.cloud`.
+  pub fn gateway_url<S: Into<String>>(mut self, gateway_url: S) -> PinataApiBuilder {
+    self.gateway_url = Some(gateway_url.into());
+    self
+  }
+
+  /// Set a timeout for each request, from when the request starts connecting until the response
+  /// body has finished. Requests that time out fail with `ApiError::Timeout`.
+  ///
+  /// By default there is no timeout. Not available on wasm.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn timeout(mut self, timeout: Duration) -> PinataApiBuilder {
+    self.timeout = Some(timeout);
+    self
+  }
+
+  /// Set a timeout for only the connect phase of each request.
+  ///
+  /// By default there is no connect timeout. Not available on wasm.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn connect_timeout(mut self, timeout: Duration) -> PinataApiBuilder {
+    self.connect_timeout = Some(timeout);
+    self
+  }
+
+  /// Send requests through `proxy`, e.g. `reqwest::Proxy::https("http://proxy.local:3128")`.
+  /// Can be called more than once to use different proxies for different urls.
+  ///
+  /// By default the proxies set in the `HTTP_PROXY`, `HTTPS_PROXY` and `NO_PROXY` environment
+  /// variables are used, setting a proxy here replaces them. Not available on wasm, where requests
+  /// are sent by the browser.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn proxy(mut self, proxy: Proxy) -> PinataApiBuilder {
+    self.proxies.push(proxy);
+    self
+  }
+
+  /// Set the maximum number of idle connections kept open to each host, to be reused by later requests.
+  ///
+  /// Services sending many requests, e.g. pinning thousands of objects, can raise it to avoid
+  /// reconnecting to Pinata. By default reqwest's limit is used, which doesn't limit idle connections.
+  /// Not available on wasm.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn pool_max_idle_per_host(mut self, max: usize) -> PinataApiBuilder {
+    self.pool_max_idle_per_host = Some(max);
+    self
+  }
+
+  /// Set how long an idle connection is kept open before it's closed.
+  ///
+  /// By default reqwest's timeout of 90 seconds is used. Not available on wasm.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn pool_idle_timeout(mut self, timeout: Duration) -> PinataApiBuilder {
+    self.pool_idle_timeout = Some(timeout);
+    self
+  }
+
+  /// Set the `User-Agent` header sent with every request.
+  ///
+  /// Defaults to `pinata-sdk-rust/<version>`, so SDK traffic can be told apart in Pinata's logs.
+  /// Not available on wasm, where the browser sets the `User-Agent`.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn user_agent<S: Into<String>>(mut self, user_agent: S) -> PinataApiBuilder {
+    self.user_agent = Some(user_agent.into());
+    self
+  }
+
+  /// Accept any TLS certificate, including self-signed and expired ones, e.g. to test against a
+  /// local Pinata mock served over HTTPS with [base_url](#method.base_url).
+  ///
+  /// **Only use this in tests.** Anyone on the network can impersonate the Pinata API and read the
+  /// credentials once certificates aren't validated. Certificates are validated by default.
+  /// Not available on wasm, where the browser validates certificates.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn danger_accept_invalid_certs(mut self, accept: bool) -> PinataApiBuilder {
+    self.danger_accept_invalid_certs = accept;
+    self
+  }
+
+  /// Set how redirects returned by Pinata, or a proxy in front of it, are followed, e.g.
+  /// `reqwest::redirect::Policy::none()` to never follow them.
+  ///
+  /// By default at most 10 redirects are followed, and none that lead from the Pinata API to
+  /// another origin: the `pinata_secret_api_key` header isn't one of the headers reqwest strips on
+  /// cross-origin redirects, so following them would send the secret api key to whatever host the
+  /// redirect points to. Such a redirect fails the request instead. Redirects of the IPFS gateway
+  /// used by `fetch()` are followed to any origin, no credentials are sent to it.
+  ///
+  /// A policy set here replaces the default one entirely. Not available on wasm, where the
+  /// browser follows redirects.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn redirect(mut self, policy: Policy) -> PinataApiBuilder {
+    self.redirect = Some(policy);
+    self
+  }
+
+  /// Enable or disable gzip compression of responses. Enabled by default.
+  ///
+  /// Only available with the `gzip` feature, which is a default feature and enables reqwest's
+  /// `gzip` feature. Not available on wasm, where the browser handles compression.
+  #[cfg(all(feature = "gzip", not(target_arch = "wasm32")))]
+  pub fn gzip(mut self, enable: bool) -> PinataApiBuilder {
+    self.gzip = Some(enable);
+    self
+  }
+
+  /// Enable or disable brotli compression of responses. Enabled by default.
+  ///
+  /// Only available with the `brotli` feature, which enables reqwest's `brotli` feature.
+  /// Not available on wasm.
+  #[cfg(all(feature = "brotli", not(target_arch = "wasm32")))]
+  pub fn brotli(mut self, enable: bool) -> PinataApiBuilder {
+    self.brotli = Some(enable);
+    self
+  }
+
+  /// Enable or disable deflate compression of responses. Enabled by default.
+  ///
+  /// Only available with the `deflate` feature, which enables reqwest's `deflate` feature.
+  /// Not available on wasm.
+  #[cfg(all(feature = "deflate", not(target_arch = "wasm32")))]
+  pub fn deflate(mut self, enable: bool) -> PinataApiBuilder {
+    self.deflate = Some(enable);
+    self
+  }
+
+  /// Set how many times a request is retried when Pinata responds with a 429 or 5xx status.
+  ///
+  /// Only GET, PUT and DELETE requests and file uploads are retried, pinning the same files again
+  /// gives the same CID. Uploads of content read from a reader, e.g. with `pin_stream`, can't be
+  /// sent again so they are not retried. The delay between retries is taken from the
+  /// `Retry-After` header when present, otherwise it grows exponentially from the
+  /// [retry_base_delay](#method.retry_base_delay) with some jitter added. Either way it's capped
+  /// to the [retry_max_delay](#method.retry_max_delay).
+  ///
+  /// By default requests are retried 3 times, set it to 0 to never retry them.
+  pub fn max_retries(mut self, max_retries: u32) -> PinataApiBuilder {
+    self.retry.max_retries = max_retries;
+    self
+  }
+
+  /// Set the delay before the first retry, doubled for each following retry. Defaults to 500ms.
+  pub fn retry_base_delay(mut self, delay: Duration) -> PinataApiBuilder {
+    self.retry.base_delay = delay;
+    self
+  }
+
+  /// Set the longest delay waited before a retry, including delays asked for by Pinata with the
+  /// `Retry-After` header. Defaults to 30 seconds.
+  pub fn retry_max_delay(mut self, delay: Duration) -> PinataApiBuilder {
+    self.retry.max_delay = delay;
+    self
+  }
+
+  /// Set the pin options used by `pin_by_hash`, `pin_json` and `pin_file` for requests without
+  /// their own options.
+  ///
+  /// Options set on the request with `set_options()` always take precedence, they are not merged
+  /// with these defaults.
+  pub fn default_pin_options(mut self, options: PinOptions) -> PinataApiBuilder {
+    self.default_pin_options = Some(options);
+    self
+  }
+
+  /// Set the storage price per GB, where a GB is 1024^3 bytes, used by `cost_breakdown` to compute
+  /// the cost of pinned content. There is no default price, it depends on your plan.
+  pub fn price_per_gb(mut self, price: f64) -> PinataApiBuilder {
+    self.price_per_gb = Some(price);
+    self
+  }
+
+  /// Add a header sent with every request to the Pinata API, e.g. a tenant id required by an
+  /// API gateway in front of Pinata. Headers are not sent to the IPFS gateway used by `fetch()`.
+  ///
+  /// Headers with the same name as the authentication headers are ignored, so they can't be replaced.
+  /// An invalid header name or value fails [build](#method.build).
+  pub fn default_header<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> PinataApiBuilder {
+    self.default_headers.push((name.into(), value.into()));
+    self
+  }
+
+  /// Send requests with `transport` instead of reqwest, e.g. a mock answering with canned responses.
+  ///
+  /// The timeouts, proxies, connection pool, certificate and redirect settings of this builder only apply to the
+  /// default reqwest transport.
+  pub fn transport<T: HttpTransport + 'static>(mut self, transport: T) -> PinataApiBuilder {
+    self.transport = Some(Box::new(transport));
+    self
+  }
+
+  /// Creates the PinataApi. An error is returned if no credentials were provided or they are blank.
+  pub fn build(self) -> Result<PinataApi, Error> {
+    let mut auth_headers = match self.credentials {
+      Some(Credentials::ApiKeys(api_key, secret_api_key)) => {
+        utils::validate_keys(&api_key, &secret_api_key)?;
+        utils::api_key_headers(&api_key, &secret_api_key)?
+      },
+      Some(Credentials::Jwt(jwt)) => {
+        utils::validate_jwt(&jwt)?;
+        utils::jwt_headers(&jwt)?
+      },
+      None => Err(ApiError::InvalidApiKey())?,
+    };
+
+    let mut default_headers = HeaderMap::new();
+    for (name, value) in self.default_headers {
+      let name = HeaderName::from_bytes(name.as_bytes())?;
+      if !auth_headers.contains_key(&name) {
+        default_headers.append(name, value.parse()?);
+      }
+    }
+    auth_headers.extend(default_headers);
+
+    let base_url = self.base_url.as_deref().unwrap_or(utils::BASE_URL);
+    #[cfg_attr(target_arch = "wasm32", allow(unused_mut))]
+    let mut client_builder = ClientBuilder::new();
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+      client_builder = client_builder.user_agent(self.user_agent.as_deref().unwrap_or(utils::USER_AGENT));
+      if let Some(timeout) = self.timeout {
+        client_builder = client_builder.timeout(timeout);
+      }
+      if let Some(timeout) = self.connect_timeout {
+        client_builder = client_builder.connect_timeout(timeout);
+      }
+      for proxy in self.proxies {
+        client_builder = client_builder.proxy(proxy);
+      }
+      if let Some(max) = self.pool_max_idle_per_host {
+        client_builder = client_builder.pool_max_idle_per_host(max);
+      }
+      if let Some(timeout) = self.pool_idle_timeout {
+        client_builder = client_builder.pool_idle_timeout(timeout);
+      }
+      if self.danger_accept_invalid_certs {
+        client_builder = client_builder.danger_accept_invalid_certs(true);
+      }
+      client_builder = client_builder.redirect(self.redirect.unwrap_or_else(|| default_redirect_policy(base_url)));
+      #[cfg(feature = "gzip")]
+      {
+        client_builder = client_builder.gzip(self.gzip.unwrap_or(true));
+      }
+      #[cfg(feature = "brotli")]
+      {
+        client_builder = client_builder.brotli(self.brotli.unwrap_or(true));
+      }
+      #[cfg(feature = "deflate")]
+      {
+        client_builder = client_builder.deflate(self.deflate.unwrap_or(true));
+      }
+    }
+
+    let mut api = PinataApi::with_auth_headers(client_builder.build()?, auth_headers, base_url)?;
+    api.retry = self.retry;
+    api.default_pin_options = self.default_pin_options;
+    api.price_per_gb = self.price_per_gb;
+    if let Some(transport) = self.transport {
+      api.transport = transport;
+    }
+    if let Some(gateway_url) = self.gateway_url {
+      api.gateway_url = utils::normalize_base_url(&gateway_url);
+    }
+    Ok(api)
+  }
+}
+
+/// Follows at most `MAX_REDIRECTS` redirects, and none leading from the Pinata API at `base_url` to
+/// another origin, which would receive the authentication headers.
+#[cfg(not(target_arch = "wasm32"))]
+fn default_redirect_policy(base_url: &str) -> Policy {
+  let api_origin = Url::parse(base_url).ok().map(|url| url.origin());
+
+  Policy::custom(move |attempt| {
+    if attempt.previous().len() > MAX_REDIRECTS {
+      return attempt.error("too many redirects");
+    }
+    let from_api = attempt.previous().first().map(Url::origin) == api_origin;
+    if from_api && Some(attempt.url().origin()) != api_origin {
+      let target = attempt.url().origin().ascii_serialization();
+      return attempt.error(format!("refusing to follow a redirect from the Pinata API to {}", target));
+    }
+    attempt.follow()
+  })
+}
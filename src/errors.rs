@@ -1,43 +1,439 @@
-use failure::Fail;
+use std::fmt;
+use std::path::{Path, PathBuf};
 pub use failure::Error;
+use crate::api::data::{JobStatus, Region};
+
+/// Error reason Pinata returns when unpinning content the user hasn't pinned
+const NOT_PINNED_REASON: &str = "CURRENT_USER_HAS_NOT_PINNED_CID";
 
 /// All possible error returned from this SDK defined as variants of this enum.
 /// 
-/// This also derives the failure::Fail trait, so it should be easier to handle and extend
+/// This implements `std::error::Error`, with the underlying error available from `source()`,
+/// and so also the failure::Fail trait, so it should be easier to handle and extend
 /// in clients that also support this failure crate.
-#[derive(Debug, Fail)]
+#[derive(Debug)]
 pub enum ApiError {
   /// Thrown when api_key passed to the [PinataApi](struct.PinataApi.html) is blank.
-  #[fail(display = "Invalid api_key")]
   InvalidApiKey(),
   /// Throw when secret_api_key passed to the `PinataApi` is blank.
-  #[fail(display = "Invalid secret_api_key")]
   InvalidSecretApiKey(),
+  /// Thrown when the requested item could not be found on Pinata
+  NotFound(String),
+  /// Thrown when the environment variables needed to create a `PinataApi` are not set.
+  /// Contains the names of the missing variables.
+  MissingEnv(String),
+  /// Thrown when more than one file would be uploaded with the same path in a pin_file upload.
+  DuplicatePath {
+    /// The path within the upload that is duplicated
+    path: String,
+  },
+  /// Thrown when a directory passed to `PinByFile` has no files to upload, e.g. it only has
+  /// empty subdirectories. Contains the path of the directory.
+  EmptyDirectory(String),
+  /// Thrown when a file is larger than the maximum file size set with `PinByFile::max_file_size()`.
+  FileTooLarge {
+    /// Path of the file on disk, or its name for content in memory
+    path: String,
+    /// Size of the file in bytes
+    size: u64,
+    /// Maximum file size in bytes
+    limit: u64,
+  },
+  /// Thrown when following symlinks while pinning a directory leads back to one of its parent directories.
+  SymlinkLoop {
+    /// Path of the symlink pointing back to a parent directory
+    path: String,
+  },
+  /// Thrown when an entry of a directory passed to `PinByFile` can't be read while walking the directory.
+  DirectoryTraversal {
+    /// Path of the entry that couldn't be read
+    path: PathBuf,
+    /// The underlying io error
+    source: std::io::Error,
+  },
+  /// Thrown when a path passed to `PinByFile`, or a file found in a directory, can't be uploaded
+  /// because its name isn't valid UTF-8 or it has no file name, e.g. `..`. Also thrown by
+  /// `PinByFile::from_entries` for entry paths with a `..` segment.
+  InvalidPath(PathBuf),
+  /// Thrown by `cost_breakdown` when no price per GB was set with `PinataApiBuilder::price_per_gb`.
+  PriceNotConfigured(),
+  /// Thrown when a hash passed to the SDK is obviously not a valid IPFS CID.
+  InvalidCid(String),
+  /// Thrown when a host node is obviously not a multiaddr with a peer id, e.g. `/ip4/1.2.3.4/tcp/4001/p2p/Qm...`.
+  InvalidMultiaddr(String),
+  /// Thrown when a CID version other than 0 or 1 is set on a pin request.
+  InvalidCidVersion(u8),
+  /// Thrown when a [CidConfig](struct.CidConfig.html) can't be used to compute a CID, e.g. a CIDv0
+  /// with a hash function other than sha2-256.
+  InvalidCidConfig(String),
+  /// Thrown when a pin policy asks for more replications in a region than Pinata allows.
+  InvalidReplicationCount {
+    /// The region of the invalid replication count
+    region: Region,
+    /// Replication count asked for
+    requested: u8,
+    /// Maximum replication count of the region
+    max: u8,
+  },
+  /// Thrown when pin metadata has more keyvalues than the 10 Pinata allows.
+  TooManyKeyValues {
+    /// Number of keyvalues in the metadata
+    count: usize,
+  },
+  /// Thrown when the name in pin metadata is longer than Pinata allows.
+  NameTooLong {
+    /// Number of characters in the name
+    len: usize,
+    /// Maximum number of characters Pinata allows
+    max: usize,
+  },
+  /// Thrown when a raw JSON string passed to `PinByJson::from_raw()` isn't valid JSON, or the
+  /// content of a `PinByJson` can't be serialized to JSON. Contains the serde error.
+  InvalidJson(String),
+  /// Thrown when a JSON metadata value is not a string, number or boolean.
+  InvalidMetadataValue {
+    /// Key of the invalid value
+    key: String,
+  },
+  /// Thrown when `unpin_by_filter` is called with a filter that doesn't constrain which pins
+  /// match, which would unpin everything on the account.
+  EmptyFilter(),
+  /// Thrown when the jwt passed to the `PinataApi` is blank.
+  InvalidJwt(),
+  /// Thrown when a request takes longer than the timeout configured with
+  /// [PinataApiBuilder](struct.PinataApiBuilder.html).
+  Timeout(),
+  /// Thrown when an upload is cancelled with the token set with
+  /// [PinByFile::cancellation_token](struct.PinByFile.html#method.cancellation_token).
+  Cancelled(),
+  /// Thrown when a request still fails after all the retries configured with
+  /// [PinataApiBuilder](struct.PinataApiBuilder.html) were made.
+  RetriesExhausted {
+    /// Number of times the request was sent
+    attempts: u32,
+    /// Error returned for the last attempt
+    error: Box<ApiError>,
+  },
+  /// Thrown when `pin_file_verified` is called with a PinByFile that doesn't upload exactly one file.
+  NotSingleFile {
+    /// Number of files the PinByFile uploads
+    count: usize,
+  },
+  /// Thrown when the content fetched back by `pin_file_verified` differs from the content uploaded.
+  IntegrityMismatch {
+    /// CID returned by Pinata for the upload
+    cid: String,
+    /// Hex encoded sha2-256 digest of the uploaded content
+    expected: String,
+    /// Hex encoded sha2-256 digest of the content fetched from the gateway
+    actual: String,
+  },
+  /// Thrown when cancelling a pin by hash job that already ended without pinning the content.
+  PinJobFinished {
+    /// Final status of the pin job
+    status: JobStatus,
+  },
+  /// Thrown when cancelling a pin by hash job whose CID is already pinned on the account some
+  /// other way, unpinning it would remove that pin too.
+  AlreadyPinned {
+    /// CID of the pin by hash job
+    cid: String,
+  },
+  /// Thrown when a pin by hash job ends without pinning the content.
+  PinFailed {
+    /// Final status of the pin job
+    status: JobStatus,
+  },
+  /// Thrown when Pinata responds with an error status
+  Http {
+    /// HTTP status code of the response
+    status: u16,
+    /// Error message returned by Pinata
+    message: String,
+    /// Error reason code returned by Pinata (e.g. `KEY_REVOKED`), when available
+    reason: Option<String>,
+  },
+  /// Thrown by `check_connection` when Pinata rejects the credentials with a 401 or 403
+  Unauthorized {
+    /// HTTP status code of the response
+    status: u16,
+    /// Error message returned by Pinata
+    message: String,
+  },
+  /// Thrown by `check_connection` when Pinata can't be reached, e.g. the connection failed or
+  /// timed out
+  Network(Box<ApiError>),
+  /// Thrown by `check_connection` when Pinata responds with a 5xx status
+  ServiceUnavailable {
+    /// HTTP status code of the response
+    status: u16,
+    /// Error message or raw body returned by Pinata
+    message: String,
+  },
+  /// Thrown when Pinata responds with a body that is not the expected JSON, e.g. an HTML error
+  /// page returned by a proxy in front of Pinata.
+  UnexpectedResponse {
+    /// HTTP status code of the response
+    status: u16,
+    /// Raw response body, truncated to its first 1024 bytes
+    body: String,
+  },
+  /// Thrown when Pinata responds with JSON that doesn't have the shape the SDK expects, e.g. a
+  /// field the SDK requires is null.
+  Deserialize {
+    /// Name of the type the response was deserialized into
+    target: &'static str,
+    /// Raw response body, truncated to its first 1024 bytes
+    body: String,
+    /// The underlying serde error, locating the offending value
+    source: serde_json::Error,
+  },
+  /// Thrown when a value returned by Pinata can't be parsed into the expected type
+  Parse(String),
+  /// Thrown when a request can't be sent or its response can't be read, e.g. the connection failed
+  Request(reqwest::Error),
+  /// Thrown when reading a file or directory to upload fails
+  Io {
+    /// Path of the file or directory, when known
+    path: Option<String>,
+    /// The underlying io error
+    source: std::io::Error,
+  },
+  /// Thrown when a glob passed to `PinByFile::include_globs` or `PinByFile::exclude_globs` is invalid
+  #[cfg(not(target_arch = "wasm32"))]
+  InvalidGlob(globset::Error),
+  /// Thrown when a path isn't inside the directory it is made relative to
+  StripPrefix(std::path::StripPrefixError),
+  /// Thrown when a value can't be serialized to JSON, e.g. the metadata keyvalues of a pin
+  /// exported to CSV
+  Serialize(serde_json::Error),
+  /// Thrown when pins are added or removed on every walk of the pin list, so a consistent list
+  /// couldn't be read
+  PinListChanging {
+    /// Number of times the pin list was walked
+    walks: usize,
+  },
   /// A generic error with message on a possible failure while interacting with the api
-  #[fail(display = "Error: {}", _0)]
   GenericError(String),
 }
 
+impl ApiError {
+  /// Io error that happened while reading the file or directory at `path`
+  #[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+  pub(crate) fn io(path: &Path, source: std::io::Error) -> ApiError {
+    ApiError::Io { path: Some(path.display().to_string()), source }
+  }
+
+  /// Whether Pinata rejected an unpin because the content isn't pinned by the user
+  pub(crate) fn is_not_pinned(&self) -> bool {
+    match self {
+      ApiError::Http { status: 404, .. } => true,
+      ApiError::Http { reason: Some(reason), .. } => reason == NOT_PINNED_REASON,
+      _ => false,
+    }
+  }
+
+  /// HTTP status of the response the error was raised for, or `None` for errors that happened
+  /// before a response was received, e.g. an invalid key or a file that can't be read.
+  ///
+  /// For `RetriesExhausted` this is the status of the last attempt.
+  pub fn status_code(&self) -> Option<u16> {
+    match self {
+      ApiError::Http { status, .. }
+      | ApiError::UnexpectedResponse { status, .. }
+      | ApiError::Unauthorized { status, .. }
+      | ApiError::ServiceUnavailable { status, .. } => Some(*status),
+      ApiError::RetriesExhausted { error, .. } => error.status_code(),
+      ApiError::Request(err) => err.status().map(|status| status.as_u16()),
+      _ => None,
+    }
+  }
+
+  /// Classifies an error raised while testing the connection to Pinata, telling bad credentials
+  /// apart from network failures and outages of Pinata.
+  pub(crate) fn into_connection_error(self) -> ApiError {
+    let message = self.response_message();
+    match self.status_code() {
+      Some(status @ (401 | 403)) => ApiError::Unauthorized { status, message },
+      Some(status @ 500..=599) => ApiError::ServiceUnavailable { status, message },
+      None if self.is_network() => ApiError::Network(Box::new(self)),
+      _ => self,
+    }
+  }
+
+  /// Error message or raw body of the response the error was raised for
+  fn response_message(&self) -> String {
+    match self {
+      ApiError::Http { message, .. } => message.clone(),
+      ApiError::UnexpectedResponse { body, .. } => body.clone(),
+      ApiError::RetriesExhausted { error, .. } => error.response_message(),
+      _ => String::new(),
+    }
+  }
+
+  /// Whether the request failed before Pinata responded, e.g. the connection failed or timed out
+  fn is_network(&self) -> bool {
+    match self {
+      ApiError::Request(_) | ApiError::Timeout() => true,
+      ApiError::RetriesExhausted { error, .. } => error.is_network(),
+      _ => false,
+    }
+  }
+}
+
+impl fmt::Display for ApiError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ApiError::InvalidApiKey() => write!(f, "Invalid api_key: the api key is blank"),
+      ApiError::InvalidSecretApiKey() => write!(f, "Invalid secret_api_key: the secret api key is blank"),
+      ApiError::NotFound(what) => write!(f, "Not found: {}", what),
+      ApiError::MissingEnv(vars) => write!(f, "Missing environment variables: {}", vars),
+      ApiError::DuplicatePath { path } => write!(f, "Duplicate path in upload: {}", path),
+      ApiError::EmptyDirectory(path) => write!(f, "No files to upload in directory: {}", path),
+      ApiError::FileTooLarge { path, size, limit } => {
+        write!(f, "File {} is {} bytes, over the limit of {} bytes", path, size, limit)
+      },
+      ApiError::SymlinkLoop { path } => write!(f, "Symlink loop detected at: {}", path),
+      ApiError::DirectoryTraversal { path, source } => {
+        write!(f, "Failed to read directory entry {}: {}", path.display(), source)
+      },
+      ApiError::InvalidPath(path) => write!(f, "Invalid path {:?}: it must have a valid UTF-8 file name and no `..` segment", path),
+      ApiError::PriceNotConfigured() => {
+        write!(f, "No price per GB is configured, set it with PinataApiBuilder::price_per_gb")
+      },
+      ApiError::InvalidCid(cid) => write!(f, "Invalid CID: {:?}", cid),
+      ApiError::InvalidMultiaddr(addr) => write!(f, "Invalid host node multiaddr: {:?}", addr),
+      ApiError::InvalidCidVersion(version) => write!(f, "Invalid CID version {}: must be 0 or 1", version),
+      ApiError::InvalidCidConfig(message) => write!(f, "Invalid CID config: {}", message),
+      ApiError::InvalidReplicationCount { region, requested, max } => {
+        write!(f, "Invalid replication count {} for region {}: at most {} allowed", requested, region.code(), max)
+      },
+      ApiError::TooManyKeyValues { count } => {
+        write!(f, "Too many metadata keyvalues: {} (Pinata allows at most 10)", count)
+      },
+      ApiError::NameTooLong { len, max } => {
+        write!(f, "Pin name is {} characters long, Pinata allows at most {}", len, max)
+      },
+      ApiError::InvalidJson(message) => write!(f, "Invalid JSON: {}", message),
+      ApiError::InvalidMetadataValue { key } => {
+        write!(f, "Invalid metadata value for {:?}: keyvalues must be strings, numbers or booleans", key)
+      },
+      ApiError::EmptyFilter() => write!(f, "Refusing to unpin with an empty filter: it matches every pin"),
+      ApiError::InvalidJwt() => write!(f, "Invalid jwt: the jwt is blank"),
+      ApiError::Timeout() => write!(f, "Request timed out"),
+      ApiError::Cancelled() => write!(f, "Upload cancelled"),
+      ApiError::RetriesExhausted { attempts, error } => {
+        write!(f, "Request failed after {} attempts: {}", attempts, error)
+      },
+      ApiError::NotSingleFile { count } => write!(f, "Expected a single file to upload, got {}", count),
+      ApiError::IntegrityMismatch { cid, expected, actual } => {
+        write!(f, "Content fetched for {} has sha2-256 digest {}, expected {}", cid, actual, expected)
+      },
+      ApiError::PinJobFinished { status } => {
+        write!(f, "Pin job already finished with status {:?}, it can't be cancelled", status)
+      },
+      ApiError::AlreadyPinned { cid } => {
+        write!(f, "{} is already pinned on the account, cancelling its pin job would unpin it", cid)
+      },
+      ApiError::PinFailed { status } => write!(f, "Pin job failed with status {:?}", status),
+      ApiError::Http { status, message, reason: Some(reason) } => {
+        write!(f, "Pinata responded with status {} ({}): {}", status, reason, message)
+      },
+      ApiError::Http { status, message, reason: None } => {
+        write!(f, "Pinata responded with status {}: {}", status, message)
+      },
+      ApiError::Unauthorized { status, message } => {
+        write!(f, "Pinata rejected the credentials with status {}: {}", status, message)
+      },
+      ApiError::Network(error) => write!(f, "Pinata can't be reached: {}", error),
+      ApiError::ServiceUnavailable { status, message } => {
+        write!(f, "Pinata is unavailable, it responded with status {}: {}", status, message)
+      },
+      ApiError::UnexpectedResponse { status, body } => {
+        write!(f, "Unexpected response with status {}: {}", status, body)
+      },
+      ApiError::Deserialize { target, body, source } => {
+        write!(f, "Failed to deserialize response into {}: {}, body: {}", target, source, body)
+      },
+      ApiError::Parse(message) => write!(f, "Parse error: {}", message),
+      ApiError::Request(err) => write!(f, "Request failed: {}", err),
+      ApiError::Io { path: Some(path), source } => write!(f, "Failed to read {}: {}", path, source),
+      ApiError::Io { path: None, source } => write!(f, "Io error: {}", source),
+      #[cfg(not(target_arch = "wasm32"))]
+      ApiError::InvalidGlob(err) => write!(f, "Invalid glob: {}", err),
+      ApiError::StripPrefix(err) => write!(f, "Invalid relative path: {}", err),
+      ApiError::Serialize(err) => write!(f, "Failed to serialize to JSON: {}", err),
+      ApiError::PinListChanging { walks } => {
+        write!(f, "Pin list kept changing while it was read {} times", walks)
+      },
+      ApiError::GenericError(message) => write!(f, "Error: {}", message),
+    }
+  }
+}
+
+impl std::error::Error for ApiError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      ApiError::RetriesExhausted { error, .. } => Some(error.as_ref()),
+      ApiError::Network(error) => Some(error.as_ref()),
+      ApiError::Request(err) => Some(err),
+      ApiError::Deserialize { source, .. } => Some(source),
+      ApiError::Io { source, .. } => Some(source),
+      ApiError::DirectoryTraversal { source, .. } => Some(source),
+      #[cfg(not(target_arch = "wasm32"))]
+      ApiError::InvalidGlob(err) => Some(err),
+      ApiError::StripPrefix(err) => Some(err),
+      ApiError::Serialize(err) => Some(err),
+      _ => None,
+    }
+  }
+}
+
 impl From<reqwest::Error> for ApiError {
   fn from(req_err: reqwest::Error) -> ApiError {
-    ApiError::GenericError(format!("{}", req_err))
+    if req_err.is_timeout() {
+      ApiError::Timeout()
+    } else {
+      ApiError::Request(req_err)
+    }
   }
 }
 
 impl From<std::io::Error> for ApiError {
   fn from(io_err: std::io::Error) -> ApiError {
-    ApiError::GenericError(format!("{}", io_err))
+    ApiError::Io { path: None, source: io_err }
   }
 }
 
+#[cfg(not(target_arch = "wasm32"))]
 impl From<walkdir::Error> for ApiError {
   fn from(io_err: walkdir::Error) -> ApiError {
-    ApiError::GenericError(format!("{}", io_err))
+    match (io_err.loop_ancestor(), io_err.path()) {
+      (Some(_), Some(path)) => ApiError::SymlinkLoop { path: path.display().to_string() },
+      (_, path) => ApiError::DirectoryTraversal {
+        path: path.map(Path::to_path_buf).unwrap_or_default(),
+        source: io_err.into(),
+      },
+    }
+  }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl From<globset::Error> for ApiError {
+  fn from(glob_err: globset::Error) -> ApiError {
+    ApiError::InvalidGlob(glob_err)
   }
 }
 
 impl From<std::path::StripPrefixError> for ApiError {
-  fn from(io_err: std::path::StripPrefixError) -> ApiError {
-    ApiError::GenericError(format!("{}", io_err))
+  fn from(prefix_err: std::path::StripPrefixError) -> ApiError {
+    ApiError::StripPrefix(prefix_err)
+  }
+}
+
+#[cfg(feature = "csv")]
+impl From<csv::Error> for ApiError {
+  fn from(csv_err: csv::Error) -> ApiError {
+    ApiError::Io { path: None, source: csv_err.into() }
   }
-}
\ No newline at end of file
+}
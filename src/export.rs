@@ -0,0 +1,60 @@
+use std::io::Write;
+use csv::Writer;
+use crate::PinRecord;
+use crate::errors::ApiError;
+
+/// Columns written by `PinataApi::export_pins_csv()`
+pub(crate) const CSV_HEADERS: [&str; 6] = [
+  "ipfs_pin_hash", "name", "size", "date_pinned", "date_unpinned", "keyvalues",
+];
+
+/// Writes a CSV row for each pin. Metadata keyvalues are written as a single JSON column
+pub(crate) fn write_pin_rows<W: Write>(writer: &mut Writer<W>, pins: &[PinRecord]) -> Result<(), ApiError> {
+  for pin in pins {
+    let keyvalues = match &pin.metadata.keyvalues {
+      Some(keyvalues) => serde_json::to_string(keyvalues)
+        .map_err(ApiError::Serialize)?,
+      None => String::new(),
+    };
+
+    writer.write_record([
+      pin.ipfs_pin_hash.as_str(),
+      pin.metadata.name.as_deref().unwrap_or(""),
+      &pin.size.to_string(),
+      pin.date_pinned.as_deref().unwrap_or(""),
+      pin.date_unpinned.as_deref().unwrap_or(""),
+      &keyvalues,
+    ])?;
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use csv::Writer;
+  use crate::PinRecord;
+  use super::{CSV_HEADERS, write_pin_rows};
+
+  #[test]
+  fn test_pin_rows_are_written_as_csv() {
+    let pin: PinRecord = serde_json::from_str(r#"{
+      "id": "cc7f924f-7246-42ce-a1c7-f0067ac02144",
+      "ipfs_pin_hash": "Qme5npQ51psDHssScnXqeKoUvA7UizxhRCfZi6ewMiiHcn",
+      "size": 36,
+      "user_id": "6176135e-fd99-4af9-a27c-23dd9d8e0461",
+      "date_pinned": "2020-04-19T15:07:36.700Z",
+      "date_unpinned": null,
+      "metadata": { "name": "report, final", "keyvalues": { "owner": "finance" } },
+      "regions": []
+    }"#).unwrap();
+
+    let mut writer = Writer::from_writer(vec![]);
+    writer.write_record(CSV_HEADERS).unwrap();
+    write_pin_rows(&mut writer, &[pin]).unwrap();
+    let csv = String::from_utf8(writer.into_inner().unwrap()).unwrap();
+
+    assert_eq!(csv, "ipfs_pin_hash,name,size,date_pinned,date_unpinned,keyvalues\n\
+      Qme5npQ51psDHssScnXqeKoUvA7UizxhRCfZi6ewMiiHcn,\"report, final\",36,2020-04-19T15:07:36.700Z,,\"{\"\"owner\"\":\"\"finance\"\"}\"\n");
+  }
+}
@@ -15,6 +15,11 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! If you authenticate with a JWT, use `PinataApi::new_with_jwt("jwt")` instead.
+//!
+//! The credentials can also be read from the `PINATA_JWT` or the `PINATA_API_KEY` and
+//! `PINATA_SECRET_API_KEY` environment variables using `PinataApi::from_env()`.
 //! 
 //! ## Usage
 //! 
@@ -69,75 +74,264 @@
 //! You can unpin using the `PinataApi::unpin()` function by passing in the CID hash of the already
 //! pinned content.
 //! 
+//! ## Response compression
+//!
+//! Responses are requested gzip compressed through the default `gzip` feature, which enables
+//! reqwest's `gzip` feature. The `brotli` and `deflate` features enable the other encodings the same
+//! way. Each encoding can be turned off with `PinataApiBuilder::gzip()`, `PinataApiBuilder::brotli()`
+//! and `PinataApiBuilder::deflate()`.
+//!
+//! ## Tracing
+//!
+//! The `tracing` feature emits a [tracing](https://docs.rs/tracing) span for each call to a
+//! `PinataApi` function, named after the function and carrying the CID or job id it acts on, with
+//! the error recorded when the call fails. Each HTTP request made by the call gets a nested
+//! `pinata_request` span with its method, endpoint, HTTP status and `elapsed_ms`. Without the
+//! feature no spans are created.
+//!
+//! ## WebAssembly
+//!
+//! The crate can be compiled for `wasm32-unknown-unknown`, where requests are sent with reqwest's
+//! browser backend. Everything that needs the filesystem or a tokio runtime is unavailable on wasm:
+//!
+//! - `PinByFile::new()` and the options for directories: `PinByFile::follow_symlinks()`,
+//!   `PinByFile::read_concurrency()`, `PinByFile::include_globs()` and `PinByFile::exclude_globs()`. Content in
+//!   memory can still be pinned with `pin_file()` using `PinByFile::from_bytes()`.
+//! - `PinByFile::cancellation_token()`, as uploads are cancelled with a tokio `CancellationToken`
+//! - `PinataApi::pin_file_with_progress()` and `PinataApi::pin_stream()`
+//! - `PinataApiBuilder::timeout()`, `PinataApiBuilder::connect_timeout()`, `PinataApiBuilder::proxy()` and
+//!   `PinataApiBuilder::user_agent()`, as the browser sets the `User-Agent` itself
+//! - `PinataApiBuilder::pool_max_idle_per_host()` and `PinataApiBuilder::pool_idle_timeout()`, as the browser manages connections
+//! - `PinataApiBuilder::danger_accept_invalid_certs()`, as the browser validates certificates
+//! - `PinataApiBuilder::redirect()`, as the browser follows redirects
+//! - `PinataApiBuilder::gzip()`, `PinataApiBuilder::brotli()` and `PinataApiBuilder::deflate()`, as the browser handles compression
+//! - the `blocking` module
+//!
 
 #[cfg_attr(test, macro_use)]
 extern crate log;
 extern crate derive_builder;
 
-use std::fs;
-use std::path::Path;
-use reqwest::{Client, ClientBuilder, header::HeaderMap, multipart::{Form, Part}, Response};
-use walkdir::WalkDir;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::Mutex;
+use bytes::Bytes;
+use std::time::Duration;
+use futures::future::{self, Either};
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::io::AsyncRead;
+use reqwest::{Client, header::{CONTENT_TYPE, HeaderMap}, Method, multipart::Form, Request, RequestBuilder};
 use serde::{Serialize};
 use serde::de::DeserializeOwned;
+use serde_json::error::Category;
 use errors::Error;
-use utils::api_url;
+use utils::{Credentials, RetryConfig};
 use api::internal::*;
+use api::groups::{GroupCids, GroupName};
+use api::gateways::{GatewayAccess, GatewayList};
+use api::keys::{ApiKeyPage, ApiKeyPageQuery, RevokeApiKey};
+use pagination::{PinJobsPager, PinListPager};
+use upload::{UploadFile, UploadProgress};
+use transport::ReqwestTransport;
+
+/// Number of times `get_all_pins` walks through the pin list before giving up on it changing
+const MAX_PIN_LIST_WALKS: usize = 3;
+
+/// Number of requests `pin_json_many`, `unpin_many` and `change_hash_metadata_many` run at the same
+/// time by default
+const DEFAULT_CONCURRENCY: usize = 8;
+
+/// Number of requests to run at the same time for a `concurrency` argument, at least 1
+fn concurrency_limit(concurrency: Option<usize>) -> usize {
+  concurrency.unwrap_or(DEFAULT_CONCURRENCY).max(1)
+}
 
 pub use api::data::*;
 pub use api::metadata::*;
+pub use api::groups::Group;
+pub use api::gateways::{CustomDomain, Gateway};
+pub use api::keys::{
+  ApiKeyInfo, ApiKeyScopes, DataScopes, EndpointScopes, GenerateApiKeyRequest, GeneratedApiKey, PinningScopes,
+};
 pub use errors::ApiError;
+pub use builder::PinataApiBuilder;
+pub use transport::{HttpTransport, TransportFuture, TransportResponse};
+pub use unixfs::{CidConfig, CidHash};
+#[cfg(not(target_arch = "wasm32"))]
+pub use tokio_util::sync::CancellationToken;
+pub use utils::{cid_v0_to_v1, cid_v1_to_v0, compute_cid};
 
 mod api;
+mod builder;
 mod utils;
 mod errors;
+mod upload;
+mod pagination;
+mod reports;
+mod transport;
+mod unixfs;
+#[cfg(feature = "csv")]
+mod export;
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+pub mod blocking;
 
 /// API struct. Exposes functions to interact with the Pinata API
 pub struct PinataApi {
+  /// Client used to build the requests, which are sent with the `transport`
   client: Client,
+  transport: Box<dyn HttpTransport>,
+  base_url: String,
+  /// Authentication headers, and the default headers set with the builder, sent to the Pinata API
+  auth_headers: HeaderMap,
+  retry: RetryConfig,
+  gateway_url: String,
+  default_pin_options: Option<PinOptions>,
+  price_per_gb: Option<f64>,
+  last_rate_limit: Mutex<Option<RateLimitInfo>>,
 }
 
 impl PinataApi {
   /// Creates a new instance of PinataApi using the provided keys.
   /// This function panics if api_key or secret_api_key's are empty/blank
   pub fn new<S: Into<String>>(api_key: S, secret_api_key: S) -> Result<PinataApi, Error> {
+    PinataApi::builder()
+      .api_keys(api_key, secret_api_key)
+      .build()
+  }
+
+  /// Creates a new instance of PinataApi using the provided keys, sending requests to `base_url`
+  /// instead of `https://api.pinata.cloud`.
+  ///
+  /// This is useful to point the api at a proxy, a dedicated endpoint or a mock server.
+  pub fn new_with_base_url<S, B>(api_key: S, secret_api_key: S, base_url: B) -> Result<PinataApi, Error>
+    where S: Into<String>, B: Into<String>
+  {
+    PinataApi::builder()
+      .api_keys(api_key, secret_api_key)
+      .base_url(base_url)
+      .build()
+  }
+
+  /// Creates a new instance of PinataApi that sends requests with the provided `client`.
+  ///
+  /// Use this to reuse a `reqwest::Client` you already configured (connection pool, DNS, TLS, ...).
+  /// The Pinata auth headers are attached to every request sent, so the client does not need to have
+  /// them as default headers.
+  ///
+  /// Note that no timeouts are set by the SDK on the provided client, callers are responsible for
+  /// configuring their own timeouts.
+  pub fn from_client<S: Into<String>>(client: Client, api_key: S, secret_api_key: S) -> Result<PinataApi, Error> {
     let owned_key = api_key.into();
     let owned_secret = secret_api_key.into();
 
     utils::validate_keys(&owned_key, &owned_secret)?;
 
-    let mut default_headers = HeaderMap::new();
-    default_headers.insert("pinata_api_key", (&owned_key).parse().unwrap());
-    default_headers.insert("pinata_secret_api_key", (&owned_secret).parse().unwrap());
+    PinataApi::with_auth_headers(client, utils::api_key_headers(&owned_key, &owned_secret)?, utils::BASE_URL)
+  }
+
+  /// Creates a new instance of PinataApi authenticating with a JWT.
+  ///
+  /// The JWT is sent as a bearer token in the `Authorization` header instead of the
+  /// api key headers. An error is returned if the jwt is empty/blank.
+  pub fn new_with_jwt<S: Into<String>>(jwt: S) -> Result<PinataApi, Error> {
+    PinataApi::builder()
+      .jwt(jwt)
+      .build()
+  }
+
+  /// Creates a new instance of PinataApi authenticating with a JWT, sending requests to `base_url`
+  /// instead of `https://api.pinata.cloud`.
+  pub fn new_with_jwt_and_base_url<S, B>(jwt: S, base_url: B) -> Result<PinataApi, Error>
+    where S: Into<String>, B: Into<String>
+  {
+    PinataApi::builder()
+      .jwt(jwt)
+      .base_url(base_url)
+      .build()
+  }
+
+  /// Creates a new instance of PinataApi using credentials read from the environment.
+  ///
+  /// If the `PINATA_JWT` variable is set, it is used to authenticate. Otherwise the api key is
+  /// read from the `PINATA_API_KEY` variable and the secret api key from `PINATA_SECRET_API_KEY`.
+  /// If none of them is set, `ApiError::MissingEnv` is returned listing the missing variables.
+  pub fn from_env() -> Result<PinataApi, Error> {
+    match utils::credentials_from_env(|name| std::env::var(name).ok())? {
+      Credentials::Jwt(jwt) => PinataApi::new_with_jwt(jwt),
+      Credentials::ApiKeys(api_key, secret_api_key) => PinataApi::new(api_key, secret_api_key),
+    }
+  }
 
-    let client = ClientBuilder::new()
-      .default_headers(default_headers)
-      .build()?;
+  /// Returns a [PinataApiBuilder](struct.PinataApiBuilder.html) to configure a new PinataApi
+  pub fn builder() -> PinataApiBuilder {
+    PinataApiBuilder::default()
+  }
 
+  fn with_auth_headers(client: Client, auth_headers: HeaderMap, base_url: &str) -> Result<PinataApi, Error> {
     Ok(PinataApi {
+      transport: Box::new(ReqwestTransport::new(client.clone())),
       client,
+      base_url: utils::normalize_base_url(base_url),
+      auth_headers,
+      retry: RetryConfig::default(),
+      gateway_url: utils::GATEWAY_URL.to_string(),
+      default_pin_options: None,
+      price_per_gb: None,
+      last_rate_limit: Mutex::new(None),
     })
   }
 
   /// Test if your credentials are corrects. It returns an error if credentials are not correct
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
   pub async fn test_authentication(&self) -> Result<(), ApiError> {
-    let response = self.client.get(&api_url("/data/testAuthentication"))
-      .send()
-      .await?;
+    let request = self.build_request(Method::GET, "/data/testAuthentication");
+    let response = self.send(request).await?;
 
     self.parse_ok_result(response).await
   }
 
+  /// Test if Pinata can be reached with your credentials. Unlike `test_authentication`, the error
+  /// tells why the check failed:
+  /// - `ApiError::Unauthorized` when Pinata rejects the credentials with a 401 or 403
+  /// - `ApiError::Network` when Pinata can't be reached, e.g. the connection failed or timed out
+  /// - `ApiError::ServiceUnavailable` when Pinata responds with a 5xx status
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+  pub async fn check_connection(&self) -> Result<(), ApiError> {
+    self.test_authentication().await.map_err(ApiError::into_connection_error)
+  }
+
   /// Change the pin policy for an individual piece of content.
   ///
   /// Changes made via this function only affect the content for the hash passed in. They do not affect a user's account level pin policy.
   ///
   /// To read more about pin policies, please check out the [Regions and Replications](https://pinata.cloud/documentation#RegionsAndReplications) documentation
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err, fields(cid = %policy.ipfs_pin_hash)))]
   pub async fn set_hash_pin_policy(&self, policy: HashPinPolicy) -> Result<(), ApiError> {
-    let response = self.client.put(&api_url("/pinning/hashPinPolicy"))
-      .json(&policy)
-      .send()
-      .await?;
+    utils::validate_cid(&policy.ipfs_pin_hash)?;
+    policy.new_pin_policy.validate()?;
+    let request = self.build_request(Method::PUT, "/pinning/hashPinPolicy")
+      .json(&policy);
+    let response = self.send(request).await?;
+
+    self.parse_ok_result(response).await
+  }
+
+  /// Change the account level pin policy, used by default for all content pinned afterwards.
+  ///
+  /// If `migrate_existing` is true, content already pinned is also moved to the new policy. Content with
+  /// its own hash pin policy keeps that policy.
+  ///
+  /// To read more about pin policies, please check out the [Regions and Replications](https://pinata.cloud/documentation#RegionsAndReplications) documentation
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+  pub async fn set_user_pin_policy(&self, policy: PinPolicy, migrate_existing: bool) -> Result<(), ApiError> {
+    policy.validate()?;
+    let request = self.build_request(Method::PUT, "/pinning/userPinPolicy")
+      .json(&UserPinPolicy {
+        new_pin_policy: policy,
+        migrate_previous_pins: migrate_existing,
+      });
+    let response = self.send(request).await?;
 
     self.parse_ok_result(response).await
   }
@@ -146,150 +340,1048 @@ impl PinataApi {
   /// 
   /// Content added through this function is pinned in the background. Fpr this operation to succeed, the 
   /// content for the hash provided must already be pinned by another node on the IPFS network.
-  pub async fn pin_by_hash(&self, hash: PinByHash) -> Result<PinByHashResult, ApiError> {
-    let response = self.client.post(&api_url("/pinning/pinByHash"))
-      .json(&hash)
-      .send()
-      .await?;
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err, fields(cid = %hash.hash_to_pin)))]
+  pub async fn pin_by_hash(&self, mut hash: PinByHash) -> Result<PinByHashResult, ApiError> {
+    utils::validate_cid(&hash.hash_to_pin)?;
+    validate_metadata(hash.pinata_metadata.as_ref())?;
+    hash.pinata_option = self.pin_options_or_default(hash.pinata_option)?;
+    let request = self.build_request(Method::POST, "/pinning/pinByHash")
+      .json(&hash);
+    let response = self.send(request).await?;
 
     self.parse_result(response).await
   }
 
   /// Retrieve a list of all the pins that are currently in the pin queue for your user
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
   pub async fn get_pin_jobs(&self, filters: PinJobsFilter) -> Result<PinJobs, ApiError> {
-    let response = self.client.get(&api_url("/pinning/pinJobs"))
-      .query(&filters)
-      .send()
-      .await?;
+    let request = self.build_request(Method::GET, "/pinning/pinJobs")
+      .query(&filters);
+    let response = self.send(request).await?;
 
     self.parse_result(response).await
   }
 
+  /// Get the pin job with id `job_id`, e.g. the id of a [PinByHashResult](struct.PinByHashResult.html).
+  ///
+  /// Pinata has no endpoint for a single pin job, so the pin queue is paged through until the job
+  /// is found. If no job matches `ApiError::NotFound` is returned.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err, fields(job_id)))]
+  pub async fn get_pin_job(&self, job_id: &str) -> Result<PinJob, ApiError> {
+    let mut pager = PinJobsPager::new(PinJobsFilter::default());
+
+    while let Some(jobs) = pager.next_page(self).await? {
+      if let Some(job) = jobs.into_iter().find(|job| job.id == job_id) {
+        return Ok(job);
+      }
+    }
+
+    Err(ApiError::NotFound(job_id.to_string()))
+  }
+
+  /// Stream all the jobs in the pin queue matching `filters`, fetching the pages as they are read.
+  ///
+  /// The filter's sort, status and hash apply to every page. Its limit sets the page size, the
+  /// maximum allowed by Pinata when not set, and its offset the first job streamed. The stream ends
+  /// after the first error.
+  pub fn get_pin_jobs_stream(&self, filters: PinJobsFilter) -> impl Stream<Item = Result<PinJob, ApiError>> + '_ {
+    stream::try_unfold(PinJobsPager::new(filters), move |mut pager| async move {
+      let page = pager.next_page(self).await?;
+      Ok::<_, ApiError>(page.map(|jobs| (stream::iter(jobs.into_iter().map(Ok)), pager)))
+    })
+    .try_flatten()
+  }
+
+  /// Wait for the pin by hash job with id `job_id` to finish, checking its status every `poll_interval`.
+  ///
+  /// Pinata removes jobs from the pin queue once the content is pinned, so the job is done when it
+  /// is no longer found in the queue. The last state of the job seen in the queue is returned then.
+  /// If the job ends with a failed status, e.g. `Expired` or `InvalidObject`, `ApiError::PinFailed`
+  /// is returned with that status, and if the job is still pending after `timeout`
+  /// `ApiError::Timeout` is returned.
+  ///
+  /// As a job that is not in the queue on the first check can't be told apart from an unknown job,
+  /// `ApiError::NotFound` is returned in that case.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err, fields(job_id)))]
+  pub async fn wait_for_pin(&self, job_id: &str, poll_interval: Duration, timeout: Duration) -> Result<PinJob, ApiError> {
+    let poll = Box::pin(self.poll_pin_job(job_id, poll_interval));
+    let timer = Box::pin(utils::sleep(timeout));
+
+    match future::select(poll, timer).await {
+      Either::Left((result, _)) => result,
+      Either::Right(_) => Err(ApiError::Timeout()),
+    }
+  }
+
+  /// Cancel the pin by hash job with id `job_id`, so Pinata stops searching for its content.
+  ///
+  /// Pinata has no endpoint to cancel a job, a pending job is cancelled by unpinning its hash.
+  /// Jobs are removed from the pin queue once their content is pinned, so cancelling a job that
+  /// already completed returns `ApiError::NotFound`, the content stays pinned and can be removed
+  /// with [unpin](#method.unpin). A job that ended without pinning its content, e.g. `Expired`,
+  /// returns `ApiError::PinJobFinished` with its status.
+  ///
+  /// As the hash is unpinned, the pin list is checked first: when the account already pinned the
+  /// same CID some other way, e.g. with `pin_file`, `ApiError::AlreadyPinned` is returned and
+  /// nothing is unpinned. A job finishing between reading its status and unpinning still has the
+  /// content it just pinned unpinned, and `Ok(())` is returned.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err, fields(job_id)))]
+  pub async fn cancel_pin_job(&self, job_id: &str) -> Result<(), ApiError> {
+    let job = self.get_pin_job(job_id).await?;
+    if !job.status.is_pending() {
+      return Err(ApiError::PinJobFinished { status: job.status });
+    }
+
+    match self.find_pinned_hash(&job.ipfs_pin_hash).await {
+      Ok(_) => return Err(ApiError::AlreadyPinned { cid: job.ipfs_pin_hash }),
+      Err(ApiError::NotFound(_)) => {},
+      Err(err) => return Err(err),
+    }
+
+    self.unpin(&job.ipfs_pin_hash).await
+  }
+
   /// Pin any JSON serializable object to Pinata IPFS nodes.
-  pub async fn pin_json<S>(&self, pin_data: PinByJson<S>) -> Result<PinnedObject, ApiError> 
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+  pub async fn pin_json<S>(&self, mut pin_data: PinByJson<S>) -> Result<PinnedObject, ApiError> 
     where S: Serialize
   {
-    let response = self.client.post(&api_url("/pinning/pinJSONToIPFS"))
-      .json(&pin_data)
-      .send()
-      .await?;
+    validate_metadata(pin_data.pinata_metadata.as_ref())?;
+    pin_data.pinata_option = self.pin_options_or_default(pin_data.pinata_option)?;
+    let request = self.build_request(Method::POST, "/pinning/pinJSONToIPFS")
+      .header(CONTENT_TYPE, "application/json")
+      .body(pin_data.to_body()?);
+    let response = self.send(request).await?;
 
     self.parse_result(response).await
   }
 
+  /// Pin a JSON serializable object named `name`, with `keyvalues` metadata, into the group with id
+  /// `group_id` and with CID version `cid_version`, in one call.
+  ///
+  /// This builds the [PinByJson](struct.PinByJson.html) with `set_metadata_with_name()`,
+  /// `set_options()` and `set_cid_version()`, build one yourself to set other options. As with
+  /// `set_options()`, the default pin options of the PinataApi are not used.
+  /// Returns `ApiError::InvalidCidVersion` if `cid_version` isn't 0 or 1.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err, fields(group_id = %group_id)))]
+  pub async fn pin_json_full<S>(
+    &self,
+    content: S,
+    name: &str,
+    keyvalues: MetadataKeyValues,
+    group_id: &str,
+    cid_version: u8,
+  ) -> Result<PinnedObject, ApiError>
+    where S: Serialize
+  {
+    let options = PinOptions { group_id: Some(group_id.to_string()), ..PinOptions::default() };
+    let pin_data = PinByJson::new(content)
+      .set_metadata_with_name(name, keyvalues)
+      .set_options(options)
+      .set_cid_version(cid_version)?;
+
+    self.pin_json(pin_data).await
+  }
+
+  /// Pin a JSON object only if it isn't pinned on the account yet, e.g. to make a pipeline
+  /// idempotent without uploading the same content again.
+  ///
+  /// The CID the content would be pinned as is computed locally and looked up in the pin list
+  /// first. The content is only uploaded when no pin has that CID. The computed CID only matches
+  /// the one Pinata gives the content when Pinata stores it the same way: serialized as compact
+  /// JSON, chunked with the default IPFS settings and with the CID version of the pin options.
+  /// When they differ the content is always uploaded, as with `pin_json()`.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+  pub async fn pin_json_if_absent<S>(&self, pin_data: PinByJson<S>) -> Result<PinOutcome, ApiError>
+    where S: Serialize
+  {
+    let options = self.pin_options_or_default(pin_data.pinata_option.clone())?;
+    let cid_version = options.and_then(|options| options.cid_version).unwrap_or(0);
+    let cid = utils::compute_cid(&pin_data.content_bytes()?, &CidConfig::default().cid_version(cid_version))?;
+
+    match self.find_pinned_hash(&cid).await {
+      Ok(_) => Ok(PinOutcome::AlreadyPinned(cid)),
+      Err(ApiError::NotFound(_)) => self.pin_json(pin_data).await.map(PinOutcome::NewlyPinned),
+      Err(err) => Err(err),
+    }
+  }
+
+  /// Pin several JSON objects, returning the result of pinning each of them in the same order
+  /// as `items`.
+  ///
+  /// At most `concurrency` pin requests run at the same time, 8 when `None` is passed. Pinata rate
+  /// limits requests, so keep `concurrency` low enough to stay within your plan's limit, see
+  /// [last_rate_limit](#method.last_rate_limit). A failure to pin one object does not stop the
+  /// others from being pinned.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+  pub async fn pin_json_many<S>(
+    &self,
+    items: Vec<PinByJson<S>>,
+    concurrency: Option<usize>,
+  ) -> Vec<Result<PinnedObject, ApiError>>
+    where S: Serialize
+  {
+    let mut results: Vec<(usize, Result<PinnedObject, ApiError>)> = stream::iter(items.into_iter().enumerate())
+      .map(|(index, item)| async move {
+        (index, self.pin_json(item).await)
+      })
+      .buffer_unordered(concurrency_limit(concurrency))
+      .collect()
+      .await;
+
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+  }
+
   /// Pin any file or folder to Pinata's IPFS nodes.
   /// 
   /// To upload a file use `PinByFile::new("file_path")`. If file_path is a directory, all the content
   /// of the directory will be uploaded to IPFS and the hash of the parent directory is returned.
+  /// Content already in memory can be uploaded with `PinByFile::from_bytes("file_name", bytes)`.
   ///
   /// If the file cannot be read or directory cannot be read an error will be returned.
+  ///
+  /// Files are streamed from disk while uploading, so large files are not loaded into memory.
+  ///
+  /// Files that end up with the same path in the upload are rejected with `ApiError::DuplicatePath`,
+  /// see [PinByFile::set_duplicate_path_policy](struct.PinByFile.html#method.set_duplicate_path_policy)
+  /// to change this.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
   pub async fn pin_file(&self, pin_data: PinByFile) -> Result<PinnedObject, ApiError> {
-    let mut form = Form::new();
-
-    for file_data in pin_data.files {
-      let base_path = Path::new(&file_data.file_path);
-      if base_path.is_dir() {
-        // recursively read the directory
-        for entry_result in WalkDir::new(base_path) {
-          let entry = entry_result?;
-          let path = entry.path();
-
-          // not interested in reading directory
-          if path.is_dir() { continue }
-
-          let path_name = path.strip_prefix(base_path)?;
-          let part_file_name = format!(
-            "{}/{}", 
-            base_path.file_name().unwrap().to_str().unwrap(),
-            path_name.to_str().unwrap()
-          );
-          
-          let part = Part::bytes(fs::read(path)?)
-            .file_name(part_file_name);
-          form = form.part("file", part);
-        }
+    let cancellation = pin_data.upload_options.cancellation.clone();
+    upload::until_cancelled(cancellation, async move {
+      validate_metadata(pin_data.pinata_metadata.as_ref())?;
+      let options = self.pin_options_or_default(pin_data.pinata_option)?;
+      let files = upload::prepare_files(pin_data.files, &pin_data.upload_options).await?;
+      let field_name = pin_data.upload_options.field_name();
+      self.upload_files(files, field_name, pin_data.pinata_metadata, options, None).await
+    }).await
+  }
+
+  /// Pin a single file like [pin_file](#method.pin_file), then fetch it back through the gateway to
+  /// verify the pinned content is the content uploaded.
+  ///
+  /// The file is read into memory and its sha2-256 digest compared with the digest of the content
+  /// returned by [fetch](#method.fetch). This costs an extra download of the whole file after the
+  /// upload, so only use it where integrity matters more than speed. `ApiError::IntegrityMismatch`
+  /// is returned if the digests differ, and `ApiError::NotSingleFile` if `pin_data` doesn't upload
+  /// exactly one file, e.g. it is a directory. When the file is wrapped with a directory, through
+  /// its options or the default pin options, the file is fetched from inside that directory.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+  pub async fn pin_file_verified(&self, pin_data: PinByFile) -> Result<PinnedObject, ApiError> {
+    let cancellation = pin_data.upload_options.cancellation.clone();
+    upload::until_cancelled(cancellation, async move {
+      validate_metadata(pin_data.pinata_metadata.as_ref())?;
+      let options = self.pin_options_or_default(pin_data.pinata_option)?;
+      let mut files = upload::prepare_files(pin_data.files, &pin_data.upload_options).await?;
+      if files.len() != 1 {
+        return Err(ApiError::NotSingleFile { count: files.len() });
+      }
+
+      let file = files.remove(0).read_into_memory().await?;
+      let expected = utils::sha256_hex(file.bytes().unwrap_or_default());
+      let wrapped = options.as_ref().and_then(|options| options.wrap_with_directory).unwrap_or(false);
+      let part_name = file.part_name.clone();
+      let field_name = pin_data.upload_options.field_name();
+      let pinned = self.upload_files(vec![file], field_name, pin_data.pinata_metadata, options, None).await?;
+
+      let url = if wrapped {
+        self.gateway_url_with_path(&pinned.ipfs_hash, &part_name)
       } else {
-        let file_name = base_path.file_name().unwrap().to_str().unwrap();
-        let part = Part::bytes(fs::read(base_path)?);
-        form = form.part("file", part.file_name(String::from(file_name)));
+        self.gateway_url(&pinned.ipfs_hash)
+      };
+      let actual = utils::sha256_hex(&self.fetch_url(&url).await?);
+      if actual != expected {
+        return Err(ApiError::IntegrityMismatch { cid: pinned.ipfs_hash, expected, actual });
       }
-    }
-    
-    if let Some(metadata) = pin_data.pinata_metadata {
-      form = form.text("pinataMetadata", serde_json::to_string(&metadata).unwrap());
-    }
-    
-    if let Some(option) = pin_data.pinata_option {
-      form = form.text("pinataOptions", serde_json::to_string(&option).unwrap());
-    }
-    
-    let response = self.client.post(&api_url("/pinning/pinFileToIPFS"))
-      .multipart(form)
-      .send()
-      .await?;
+      Ok(pinned)
+    }).await
+  }
 
-    self.parse_result(response).await
+  /// Pin a directory like [pin_file](#method.pin_file), also returning the path and size of every
+  /// file uploaded.
+  ///
+  /// Pinata only returns the CID of the parent directory, the file listing is gathered while walking
+  /// the directory and can be used to verify what was pinned.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+  pub async fn pin_directory_detailed(&self, pin_data: PinByFile) -> Result<DirectoryPinResult, ApiError> {
+    let cancellation = pin_data.upload_options.cancellation.clone();
+    upload::until_cancelled(cancellation, async move {
+      validate_metadata(pin_data.pinata_metadata.as_ref())?;
+      let options = self.pin_options_or_default(pin_data.pinata_option)?;
+      let files = upload::prepare_files(pin_data.files, &pin_data.upload_options).await?;
+      let listing = files.iter()
+        .map(|file| (upload::path_in_upload(&file.part_name).to_string(), file.size))
+        .collect();
+      let field_name = pin_data.upload_options.field_name();
+      let pinned = self.upload_files(files, field_name, pin_data.pinata_metadata, options, None).await?;
+
+      Ok(DirectoryPinResult { pinned, files: listing })
+    }).await
+  }
+
+  /// Pin any file or folder to Pinata's IPFS nodes, reporting the upload progress.
+  ///
+  /// Works like [pin_file](#method.pin_file), but `progress` is called with the number of bytes uploaded
+  /// so far and the total number of bytes to upload as the file contents are sent. For directories the total
+  /// is the sum of the size of all the files in the directory.
+  #[cfg(not(target_arch = "wasm32"))]
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+  pub async fn pin_file_with_progress<F>(&self, pin_data: PinByFile, progress: F) -> Result<PinnedObject, ApiError>
+    where F: FnMut(u64, u64) + Send + 'static
+  {
+    let cancellation = pin_data.upload_options.cancellation.clone();
+    upload::until_cancelled(cancellation, async move {
+      validate_metadata(pin_data.pinata_metadata.as_ref())?;
+      let options = self.pin_options_or_default(pin_data.pinata_option)?;
+      let files = upload::prepare_files(pin_data.files, &pin_data.upload_options).await?;
+      let total = files.iter().map(|file| file.size).sum();
+      let progress = UploadProgress::new(total, progress);
+      let field_name = pin_data.upload_options.field_name();
+      self.upload_files(files, field_name, pin_data.pinata_metadata, options, Some(progress)).await
+    }).await
+  }
+
+  /// Pin content read from `reader` as a file named `file_name`, without buffering it in memory.
+  ///
+  /// Useful when the content has no path on disk, e.g. it comes from a network socket or a
+  /// decompression pipeline. The metadata and options are sent the same way as with
+  /// [pin_file](#method.pin_file).
+  #[cfg(not(target_arch = "wasm32"))]
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+  pub async fn pin_stream<R>(
+    &self,
+    file_name: &str,
+    reader: R,
+    metadata: Option<PinMetadata>,
+    options: Option<PinOptions>,
+  ) -> Result<PinnedObject, ApiError>
+    where R: AsyncRead + Send + 'static
+  {
+    validate_metadata(metadata.as_ref())?;
+    let options = self.pin_options_or_default(options)?;
+    let file = UploadFile::from_reader(file_name.to_string(), reader);
+    self.upload_files(vec![file], upload::FILE_FIELD_NAME, metadata, options, None).await
   }
 
   /// Unpin content previously uploaded to the Pinata's IPFS nodes.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err, fields(cid = %hash)))]
   pub async fn unpin(&self, hash: &str) -> Result<(), ApiError> {
-    let response = self.client.delete(&api_url(&format!("/pinning/unpin/{}", hash)))
-      .send()
-      .await?;
+    utils::validate_cid(hash)?;
+    let request = self.build_request(Method::DELETE, &format!("/pinning/unpin/{}", hash));
+    let response = self.send(request).await?;
 
     self.parse_ok_result(response).await
   }
 
+  /// Unpin the content for `hash` like [unpin](#method.unpin), returning whether it was pinned.
+  ///
+  /// Content that isn't pinned resolves to `Ok(false)` instead of an error, so cleanup code can
+  /// unpin the same hash more than once. Other failures are still returned as errors.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err, fields(cid = %hash)))]
+  pub async fn try_unpin(&self, hash: &str) -> Result<bool, ApiError> {
+    match self.unpin(hash).await {
+      Ok(()) => Ok(true),
+      Err(err) if err.is_not_pinned() => Ok(false),
+      Err(err) => Err(err),
+    }
+  }
+
+  /// Unpin several hashes, returning the result of unpinning each of them in the same order as
+  /// `hashes`.
+  ///
+  /// At most `concurrency` unpin requests run at the same time, 8 when `None` is passed.
+  /// A failure to unpin one hash does not stop the others from being unpinned.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+  pub async fn unpin_many(
+    &self,
+    hashes: &[String],
+    concurrency: Option<usize>,
+  ) -> Vec<(String, Result<(), ApiError>)> {
+    let mut results: Vec<_> = stream::iter(hashes.iter().cloned().enumerate())
+      .map(|(index, hash)| async move {
+        let result = self.unpin(&hash).await;
+        (index, (hash, result))
+      })
+      .buffer_unordered(concurrency_limit(concurrency))
+      .collect()
+      .await;
+
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+  }
+
+  /// Unpin all the content currently pinned that matches `filters`, e.g. every pin tagged `env=staging`.
+  ///
+  /// The matching hashes are read from the pin list first, then unpinned with up to 8 requests at
+  /// the same time. A failure to unpin one hash does not stop the others, the failures are listed in
+  /// the returned summary.
+  ///
+  /// As a safety check against unpinning the whole account, the filter must constrain which pins
+  /// match with at least one of the hash, date, size or metadata filters, otherwise
+  /// `ApiError::EmptyFilter` is returned and nothing is unpinned. The filter's status is ignored.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+  pub async fn unpin_by_filter(&self, mut filters: PinListFilter) -> Result<UnpinSummary, ApiError> {
+    if !filters.has_constraints() {
+      return Err(ApiError::EmptyFilter());
+    }
+
+    filters.status = Some(PinStatus::Pinned);
+    let hashes: Vec<String> = self.get_all_pins(filters).await?
+      .into_iter()
+      .map(|pin| pin.ipfs_pin_hash)
+      .collect::<HashSet<_>>()
+      .into_iter()
+      .collect();
+
+    let mut summary = UnpinSummary { unpinned: 0, failed: vec![] };
+    for (hash, result) in self.unpin_many(&hashes, None).await {
+      match result {
+        Ok(()) => summary.unpinned += 1,
+        Err(err) => summary.failed.push((hash, err)),
+      }
+    }
+
+    Ok(summary)
+  }
+
   /// Change name and custom key values associated for a piece of content stored on Pinata.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err, fields(cid = %change.ipfs_pin_hash)))]
   pub async fn change_hash_metadata(&self, change: ChangePinMetadata) -> Result<(), ApiError> {
-    let response = self.client.put(&api_url("/pinning/hashMetadata"))
-      .json(&change)
-      .send()
-      .await?;
+    change.metadata.validate()?;
+    let request = self.build_request(Method::PUT, "/pinning/hashMetadata")
+      .json(&change);
+    let response = self.send(request).await?;
 
     self.parse_ok_result(response).await
   }
 
+  /// Change the metadata of several pins, returning the result of each change with the hash it
+  /// was made to in the same order as `changes`, e.g. to bump a `version` keyvalue across a batch
+  /// of pins.
+  ///
+  /// At most `concurrency` requests run at the same time, 8 when `None` is passed.
+  /// A failed change does not stop the others from being made.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all))]
+  pub async fn change_hash_metadata_many(
+    &self,
+    changes: Vec<ChangePinMetadata>,
+    concurrency: Option<usize>,
+  ) -> Vec<(String, Result<(), ApiError>)> {
+    let mut results: Vec<_> = stream::iter(changes.into_iter().enumerate())
+      .map(|(index, change)| async move {
+        let ipfs_pin_hash = change.ipfs_pin_hash.clone();
+        (index, (ipfs_pin_hash, self.change_hash_metadata(change).await))
+      })
+      .buffer_unordered(concurrency_limit(concurrency))
+      .collect()
+      .await;
+
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+  }
+
+  /// Get the name and keyvalues metadata of a piece of content pinned on Pinata.
+  ///
+  /// If the hash is not currently pinned `ApiError::NotFound` is returned.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err, fields(cid = %ipfs_pin_hash)))]
+  pub async fn get_hash_metadata(&self, ipfs_pin_hash: &str) -> Result<PinMetadata, ApiError> {
+    let pin = self.find_pinned_hash(ipfs_pin_hash).await?;
+    Ok(pin.metadata.into())
+  }
+
+  /// Change the metadata of a piece of content only if it differs from its current metadata.
+  ///
+  /// The current metadata is fetched and compared with `desired`. If something differs, only the
+  /// differences are sent, with keyvalues missing in `desired` being deleted. A `None` name in `desired`
+  /// keeps the current name. Returns whether the metadata was changed.
+  ///
+  /// If the hash is not currently pinned `ApiError::NotFound` is returned.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+  pub async fn set_metadata_if_changed<S>(&self, ipfs_pin_hash: S, desired: PinMetadata) -> Result<bool, ApiError>
+    where S: Into<String>
+  {
+    let ipfs_pin_hash = ipfs_pin_hash.into();
+    let pin = self.find_pinned_hash(&ipfs_pin_hash).await?;
+
+    match pin.metadata.changes_to(desired) {
+      Some(metadata) => {
+        self.change_hash_metadata(ChangePinMetadata { ipfs_pin_hash, metadata }).await?;
+        Ok(true)
+      },
+      None => Ok(false),
+    }
+  }
+
   /// This endpoint returns the total combined size for all content that you've pinned through Pinata
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
   pub async fn get_total_user_pinned_data(&self) ->  Result<TotalPinnedData, ApiError> {
-    let response = self.client.get(&api_url("/data/userPinnedDataTotal"))
-      .send()
-      .await?;
+    let request = self.build_request(Method::GET, "/data/userPinnedDataTotal");
+    let response = self.send(request).await?;
 
     self.parse_result(response).await
   }
 
+  /// Get the number of pins and the storage used by your account, to check how close it is to the
+  /// plan's limit before pinning more content.
+  ///
+  /// The usage is read from [get_total_user_pinned_data](#method.get_total_user_pinned_data). Pinata
+  /// doesn't return the plan's storage limit, so `storage_limit` and `remaining` are `None`.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+  pub async fn get_account_usage(&self) -> Result<AccountUsage, ApiError> {
+    AccountUsage::from_total(&self.get_total_user_pinned_data().await?)
+  }
+
   /// This returns data on what content the sender has pinned to IPFS from pinata
   /// 
   /// The purpose of this endpoint is to provide insight into what is being pinned, and how
   /// long it has been pinned. The results of this call can be filtered using [PinListFilter](struct.PinListFilter.html).
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
   pub async fn get_pin_list(&self, filters: PinListFilter) -> Result<PinList, ApiError> {
-    let response = self.client.get(&api_url("/data/pinList"))
-      .query(&filters)
-      .send()
-      .await?;
+    let request = self.build_request(Method::GET, "/data/pinList")
+      .query(&filters);
+    let response = self.send(request).await?;
 
     self.parse_result(response).await
   }
 
-  async fn parse_result<R>(&self, response: Response) -> Result<R, ApiError> 
+  /// Fetch all the pins matching `filters`, reading the pin list page by page.
+  ///
+  /// Pages are read starting from the filter's page offset, using the filter's page limit or
+  /// the maximum page size allowed by Pinata, until all `count` matching records are read.
+  ///
+  /// When pins are added or removed while the pages are read, records shift between pages. Records
+  /// are never returned twice, and if the number of matching records changed during the walk the
+  /// pin list is read again to pick up the records that shifted into pages already read.
+  /// `ApiError::PinListChanging` is returned if the pin list keeps changing after a few walks.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+  pub async fn get_all_pins(&self, filters: PinListFilter) -> Result<Vec<PinRecord>, ApiError> {
+    let mut seen = HashSet::new();
+    let mut all_pins = vec![];
+
+    for _ in 0..MAX_PIN_LIST_WALKS {
+      let mut pager = PinListPager::new(filters.clone());
+      while let Some(pins) = pager.next_page(self).await? {
+        for pin in pins {
+          if seen.insert(pin.id.clone()) {
+            all_pins.push(pin);
+          }
+        }
+      }
+
+      if !pager.count_changed() {
+        return Ok(all_pins);
+      }
+    }
+
+    Err(ApiError::PinListChanging { walks: MAX_PIN_LIST_WALKS })
+  }
+
+  /// Compute the storage cost of your pinned content, broken down by the `by` key.
+  ///
+  /// All currently pinned content is fetched page by page and the sizes are added up per value
+  /// of the breakdown key. The cost of each bucket is computed using the rate set with
+  /// [PinataApiBuilder::price_per_gb](struct.PinataApiBuilder.html#method.price_per_gb), without it
+  /// `ApiError::PriceNotConfigured` is returned. Pins without a value for the key, or not in any
+  /// group when breaking down by group, are added to the `""` bucket.
+  ///
+  /// Breaking down by group also lists the groups and the content pinned in each of them first.
+  /// Content added to several groups is only counted once, in the first of its groups in the order
+  /// returned by [list_groups](#method.list_groups).
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+  pub async fn cost_breakdown(&self, by: BreakdownKey) -> Result<HashMap<String, Cost>, ApiError> {
+    let price_per_gb = self.price_per_gb.ok_or(ApiError::PriceNotConfigured())?;
+    let pin_groups = match by {
+      BreakdownKey::Group => self.pin_groups().await?,
+      BreakdownKey::MetadataKey(_) => HashMap::new(),
+    };
+    let mut filters = PinListFilter::default();
+    filters.status = Some(PinStatus::Pinned);
+
+    let mut breakdown = HashMap::new();
+    let mut pager = PinListPager::new(filters);
+    while let Some(pins) = pager.next_page(self).await? {
+      reports::add_to_cost_breakdown(&mut breakdown, &pins, &by, &pin_groups, price_per_gb);
+    }
+
+    Ok(breakdown)
+  }
+
+  /// Maps the hash of each pin added to a group to the id of the group, the first group listed for
+  /// hashes in several groups
+  async fn pin_groups(&self) -> Result<HashMap<String, String>, ApiError> {
+    let mut pin_groups = HashMap::new();
+    for group in self.list_groups().await? {
+      let mut filters = PinListFilter::default();
+      filters.status = Some(PinStatus::Pinned);
+      filters.group_id = Some(group.id.clone());
+
+      let mut pager = PinListPager::new(filters);
+      while let Some(pins) = pager.next_page(self).await? {
+        for pin in pins {
+          pin_groups.entry(pin.ipfs_pin_hash).or_insert_with(|| group.id.clone());
+        }
+      }
+    }
+
+    Ok(pin_groups)
+  }
+
+  /// Summarize the size of your pinned content, counting the pins and adding up their sizes in
+  /// buckets of under 1MB, 1 to 10MB, 10 to 100MB and 100MB or more.
+  ///
+  /// All currently pinned content is fetched with [get_all_pins](#method.get_all_pins).
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+  pub async fn pinned_data_summary(&self) -> Result<PinnedDataSummary, ApiError> {
+    let mut filters = PinListFilter::default();
+    filters.status = Some(PinStatus::Pinned);
+
+    let mut summary = PinnedDataSummary::default();
+    reports::add_to_size_summary(&mut summary, &self.get_all_pins(filters).await?);
+    Ok(summary)
+  }
+
+  /// Export all the pins on your account as CSV to the provided writer.
+  ///
+  /// The pin list is fetched page by page and each page is written out as it is received.
+  /// The columns written are `ipfs_pin_hash`, `name`, `size`, `date_pinned`, `date_unpinned`
+  /// and `keyvalues`, where `keyvalues` holds the pin metadata keyvalues as a JSON object.
+  ///
+  /// Returns the number of pin rows written. This function is only available with the `csv` feature.
+  #[cfg(feature = "csv")]
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+  pub async fn export_pins_csv<W>(&self, writer: W) -> Result<u64, ApiError>
+    where W: std::io::Write
+  {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    csv_writer.write_record(export::CSV_HEADERS)?;
+
+    let mut pager = PinListPager::new(PinListFilter::default());
+    let mut written = 0;
+    while let Some(pins) = pager.next_page(self).await? {
+      export::write_pin_rows(&mut csv_writer, &pins)?;
+      written += pins.len() as u64;
+    }
+
+    csv_writer.flush()?;
+    Ok(written)
+  }
+
+  /// Create a new group named `name` to organize pinned content.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+  pub async fn create_group<S: Into<String>>(&self, name: S) -> Result<Group, ApiError> {
+    let request = self.build_request(Method::POST, "/groups")
+      .json(&GroupName { name: name.into() });
+    let response = self.send(request).await?;
+
+    self.parse_result(response).await
+  }
+
+  /// List the groups of the authenticated user.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+  pub async fn list_groups(&self) -> Result<Vec<Group>, ApiError> {
+    let request = self.build_request(Method::GET, "/groups");
+    let response = self.send(request).await?;
+
+    self.parse_result(response).await
+  }
+
+  /// Get the group with id `group_id`.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err, fields(group_id)))]
+  pub async fn get_group(&self, group_id: &str) -> Result<Group, ApiError> {
+    let request = self.build_request(Method::GET, &format!("/groups/{}", group_id));
+    let response = self.send(request).await?;
+
+    self.parse_result(response).await
+  }
+
+  /// Rename the group with id `group_id`.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err, fields(group_id)))]
+  pub async fn update_group<S: Into<String>>(&self, group_id: &str, name: S) -> Result<Group, ApiError> {
+    let request = self.build_request(Method::PUT, &format!("/groups/{}", group_id))
+      .json(&GroupName { name: name.into() });
+    let response = self.send(request).await?;
+
+    self.parse_result(response).await
+  }
+
+  /// Delete the group with id `group_id`. Content in the group stays pinned.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err, fields(group_id)))]
+  pub async fn delete_group(&self, group_id: &str) -> Result<(), ApiError> {
+    let request = self.build_request(Method::DELETE, &format!("/groups/{}", group_id));
+    let response = self.send(request).await?;
+
+    self.parse_ok_result(response).await
+  }
+
+  /// Add already pinned content to the group with id `group_id`.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err, fields(group_id)))]
+  pub async fn add_cids_to_group(&self, group_id: &str, cids: Vec<String>) -> Result<(), ApiError> {
+    let request = self.build_request(Method::PUT, &format!("/groups/{}/cids", group_id))
+      .json(&GroupCids { cids });
+    let response = self.send(request).await?;
+
+    self.parse_ok_result(response).await
+  }
+
+  /// Remove content from the group with id `group_id`. The content stays pinned.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err, fields(group_id)))]
+  pub async fn remove_cids_from_group(&self, group_id: &str, cids: Vec<String>) -> Result<(), ApiError> {
+    let request = self.build_request(Method::DELETE, &format!("/groups/{}/cids", group_id))
+      .json(&GroupCids { cids });
+    let response = self.send(request).await?;
+
+    self.parse_ok_result(response).await
+  }
+
+  /// Generate a new api key allowed to call the endpoints in the request's scopes, e.g. to hand
+  /// limited credentials to a CI job. Generating keys requires admin credentials.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+  pub async fn generate_api_key(&self, request: GenerateApiKeyRequest) -> Result<GeneratedApiKey, ApiError> {
+    let request = self.build_request(Method::POST, "/users/generateApiKey")
+      .json(&request);
+    let response = self.send(request).await?;
+
+    self.parse_result(response).await
+  }
+
+  /// List the api keys of the account, including revoked keys.
+  ///
+  /// Pinata returns the keys a page at a time, all the pages are read.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+  pub async fn list_api_keys(&self) -> Result<Vec<ApiKeyInfo>, ApiError> {
+    let mut keys = vec![];
+
+    loop {
+      let request = self.build_request(Method::GET, "/users/apiKeys")
+        .query(&ApiKeyPageQuery { offset: keys.len() as u64 });
+      let response = self.send(request).await?;
+      let page: ApiKeyPage = self.parse_result(response).await?;
+
+      let page_len = page.keys.len();
+      keys.extend(page.keys);
+      if page_len == 0 || keys.len() as u64 >= page.count {
+        return Ok(keys);
+      }
+    }
+  }
+
+  /// Revoke the api key `api_key`. Requests made with it are rejected afterwards.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+  pub async fn revoke_api_key(&self, api_key: &str) -> Result<(), ApiError> {
+    let request = self.build_request(Method::PUT, "/users/revokeApiKey")
+      .json(&RevokeApiKey { api_key: api_key.to_string() });
+    let response = self.send(request).await?;
+
+    self.parse_ok_result(response).await
+  }
+
+  /// List the dedicated gateways of the account, with their access restriction and custom domains.
+  ///
+  /// Use [PinataApiBuilder::gateway_url](struct.PinataApiBuilder.html#method.gateway_url) to fetch
+  /// content through one of them.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+  pub async fn list_gateways(&self) -> Result<Vec<Gateway>, ApiError> {
+    let request = self.build_request(Method::GET, "/v3/ingress/gateways");
+    let response = self.send(request).await?;
+    let gateways: GatewayList = self.parse_result(response).await?;
+
+    Ok(gateways.rows)
+  }
+
+  /// Change who can access the dedicated gateway `domain`. A restricted gateway only serves
+  /// content pinned by the account, an unrestricted one serves any content on IPFS.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err, fields(domain)))]
+  pub async fn update_gateway_access(&self, domain: &str, restricted: bool) -> Result<(), ApiError> {
+    let request = self.build_request(Method::PUT, &format!("/v3/ingress/gateways/{}", domain))
+      .json(&GatewayAccess { restrict: restricted });
+    let response = self.send(request).await?;
+
+    self.parse_ok_result(response).await
+  }
+
+  /// Returns the url to retrieve the content for `cid` through the configured gateway.
+  ///
+  /// The gateway defaults to `https://gateway.pinata.cloud` and can be changed with
+  /// [PinataApiBuilder::gateway_url](struct.PinataApiBuilder.html#method.gateway_url).
+  pub fn gateway_url(&self, cid: &str) -> String {
+    format!("{}/ipfs/{}", self.gateway_url, cid)
+  }
+
+  /// Returns the url to retrieve the file at `path` inside the directory pinned as `cid`
+  /// through the configured gateway.
+  pub fn gateway_url_with_path(&self, cid: &str, path: &str) -> String {
+    format!("{}/{}", self.gateway_url(cid), path.trim_start_matches('/'))
+  }
+
+  /// Fetch the content for `cid` through the configured gateway.
+  ///
+  /// The Pinata auth headers are not sent to the gateway.
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err, fields(cid = %cid)))]
+  pub async fn fetch(&self, cid: &str) -> Result<Bytes, ApiError> {
+    self.fetch_url(&self.gateway_url(cid)).await
+  }
+
+  async fn fetch_url(&self, url: &str) -> Result<Bytes, ApiError> {
+    let request = self.client.get(url);
+    let response = self.send(request).await?;
+
+    if response.status.is_success() {
+      Ok(response.body)
+    } else {
+      Err(self.parse_error(response).await)
+    }
+  }
+
+  /// Send a request to any Pinata API endpoint, e.g. one this crate doesn't support yet.
+  ///
+  /// This is an advanced API: the request is sent to `path` on the base url with the auth headers
+  /// and retried like the other functions, `query` is added to the url and `body` is sent as JSON,
+  /// but neither is validated. A successful response is deserialized as `T`, use
+  /// `serde_json::Value` to get the raw JSON. Error responses fail like any other call.
+  ///
+  /// ```no_run
+  /// # use pinata_sdk::{ApiError, PinataApi};
+  /// # async fn run() -> Result<(), ApiError> {
+  /// # let api = PinataApi::new("api_key", "secret_api_key").unwrap();
+  /// let pins: serde_json::Value = api.request(
+  ///   reqwest::Method::GET,
+  ///   "/data/pinList",
+  ///   None,
+  ///   Some(serde_json::json!({ "status": "pinned", "pageLimit": 5 })),
+  /// ).await?;
+  /// # Ok(())
+  /// # }
+  /// ```
+  #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err, fields(path)))]
+  pub async fn request<T>(
+    &self,
+    method: Method,
+    path: &str,
+    body: Option<serde_json::Value>,
+    query: Option<serde_json::Value>,
+  ) -> Result<T, ApiError>
+    where T: DeserializeOwned
+  {
+    let mut request = self.build_request(method, path);
+    if let Some(query) = query {
+      request = request.query(&query);
+    }
+    if let Some(body) = body {
+      request = request.json(&body);
+    }
+    let response = self.send(request).await?;
+
+    self.parse_result(response).await
+  }
+
+  /// Returns the rate limit state reported by the most recent Pinata API response that had
+  /// rate limit headers, or `None` if no response had them yet.
+  ///
+  /// Responses from the gateway used by `fetch()` are not taken into account.
+  pub fn last_rate_limit(&self) -> Option<RateLimitInfo> {
+    self.last_rate_limit.lock().ok().and_then(|last| last.clone())
+  }
+
+  /// Options to pin with: the request's own options, or the default pin options if it has none.
+  /// The host nodes of the options are validated.
+  fn pin_options_or_default(&self, options: Option<PinOptions>) -> Result<Option<PinOptions>, ApiError> {
+    let options = options.or_else(|| self.default_pin_options.clone());
+    if let Some(options) = &options {
+      options.validate()?;
+    }
+    Ok(options)
+  }
+
+  fn api_url(&self, path: &str) -> String {
+    format!("{}{}", self.base_url, path)
+  }
+
+  /// Creates a request to the api `path` with the auth headers attached
+  fn build_request(&self, method: Method, path: &str) -> RequestBuilder {
+    self.client.request(method, self.api_url(path))
+      .headers(self.auth_headers.clone())
+  }
+
+  async fn upload_files(
+    &self,
+    files: Vec<UploadFile>,
+    field_name: &str,
+    metadata: Option<PinMetadata>,
+    options: Option<PinOptions>,
+    progress: Option<UploadProgress>,
+  ) -> Result<PinnedObject, ApiError> {
+    let metadata = metadata.map(|metadata| serde_json::to_string(&metadata).unwrap());
+    let options = options.map(|options| serde_json::to_string(&options).unwrap());
+
+    // the form is consumed by the request and its files can only be streamed once, so the form is
+    // rebuilt from a copy of the files for each retry, reopening the files on disk
+    let build = |files: Vec<UploadFile>| async {
+      if let Some(progress) = &progress {
+        progress.reset();
+      }
+
+      let mut form = Form::new();
+      for file in files {
+        form = form.part(field_name.to_string(), file.into_part(progress.as_ref()).await?);
+      }
+      if let Some(metadata) = &metadata {
+        form = form.text("pinataMetadata", metadata.clone());
+      }
+      if let Some(options) = &options {
+        form = form.text("pinataOptions", options.clone());
+      }
+
+      Ok(self.build_request(Method::POST, "/pinning/pinFileToIPFS").multipart(form).build()?)
+    };
+    let try_clone = |files: &Vec<UploadFile>| files.iter().map(UploadFile::try_clone).collect();
+    let response = self.send_retrying(files, build, try_clone).await?;
+
+    self.parse_result(response).await
+  }
+
+  /// Sends the request, retrying on 429 and 5xx responses as configured.
+  ///
+  /// Only GET, PUT and DELETE requests whose body can be cloned are retried.
+  async fn send(&self, request: RequestBuilder) -> Result<TransportResponse, ApiError> {
+    let request = request.build()?;
+    let idempotent = utils::is_idempotent(request.method());
+    let try_clone = |request: &Request| if idempotent { request.try_clone() } else { None };
+
+    self.send_retrying(request, |request| future::ready(Ok(request)), try_clone).await
+  }
+
+  /// Sends the request built from `attempt` by `build`, retrying on 429 and 5xx responses as configured.
+  ///
+  /// Before each attempt that can still be retried, `try_clone` copies it to build the retry from.
+  /// The request is not retried when `try_clone` returns `None`.
+  async fn send_retrying<T, B, F, C>(&self, mut attempt: T, build: B, try_clone: C) -> Result<TransportResponse, ApiError>
+    where B: Fn(T) -> F,
+          F: Future<Output = Result<Request, ApiError>>,
+          C: Fn(&T) -> Option<T>,
+  {
+    let mut retries = 0;
+
+    loop {
+      let next_attempt = if retries < self.retry.max_retries {
+        try_clone(&attempt)
+      } else {
+        None
+      };
+
+      let response = transport::execute(self.transport.as_ref(), build(attempt).await?).await?;
+      self.record_rate_limit(&response);
+      if !utils::is_retryable_status(response.status) {
+        return Ok(response);
+      }
+
+      match next_attempt {
+        Some(next_attempt) => {
+          utils::sleep(self.retry.retry_delay(retries, &response.headers)).await;
+          retries += 1;
+          attempt = next_attempt;
+        },
+        None if retries > 0 => {
+          let error = self.parse_error(response).await;
+          return Err(ApiError::RetriesExhausted { attempts: retries + 1, error: Box::new(error) });
+        },
+        None => return Ok(response),
+      }
+    }
+  }
+
+  fn record_rate_limit(&self, response: &TransportResponse) {
+    if !utils::is_under_base_url(&response.url, &self.base_url) {
+      return;
+    }
+
+    if let Some(info) = utils::rate_limit_info(&response.headers) {
+      if let Ok(mut last) = self.last_rate_limit.lock() {
+        *last = Some(info);
+      }
+    }
+  }
+
+  async fn poll_pin_job(&self, job_id: &str, poll_interval: Duration) -> Result<PinJob, ApiError> {
+    let mut last_seen: Option<PinJob> = None;
+
+    loop {
+      match self.get_pin_job(job_id).await {
+        Ok(job) if job.status.is_pending() => last_seen = Some(job),
+        Ok(job) => return Err(ApiError::PinFailed { status: job.status }),
+        Err(ApiError::NotFound(id)) => return last_seen.ok_or(ApiError::NotFound(id)),
+        Err(err) => return Err(err),
+      }
+
+      utils::sleep(poll_interval).await;
+    }
+  }
+
+  async fn find_pinned_hash(&self, ipfs_pin_hash: &str) -> Result<PinRecord, ApiError> {
+    let mut filters = PinListFilter::default();
+    filters.hash_contains = Some(ipfs_pin_hash.to_string());
+    filters.status = Some(PinStatus::Pinned);
+
+    let mut pager = PinListPager::new(filters);
+    while let Some(pins) = pager.next_page(self).await? {
+      if let Some(pin) = pins.into_iter().find(|pin| pin.ipfs_pin_hash == ipfs_pin_hash) {
+        return Ok(pin);
+      }
+    }
+
+    Err(ApiError::NotFound(format!("no pin found for hash {}", ipfs_pin_hash)))
+  }
+
+  async fn parse_result<R>(&self, response: TransportResponse) -> Result<R, ApiError> 
     where R: DeserializeOwned
   {
-    if response.status().is_success() {
-      let result = response.json::<R>().await?;
-      Ok(result)
+    if response.status.is_success() {
+      let status = response.status;
+      let body = response.text();
+      serde_json::from_str(&body).map_err(|err| match err.classify() {
+        // valid JSON with an unexpected shape, as opposed to a body that isn't JSON at all
+        Category::Data => ApiError::Deserialize {
+          target: std::any::type_name::<R>(),
+          body: utils::truncate_body(body),
+          source: err,
+        },
+        _ => ApiError::UnexpectedResponse {
+          status: status.as_u16(),
+          body: utils::truncate_body(body),
+        },
+      })
     } else {
-      let error = response.json::<PinataApiError>().await?;
-      Err(ApiError::GenericError(error.message()))
+      Err(self.parse_error(response).await)
     }
   }
 
-  async fn parse_ok_result(&self, response: Response) -> Result<(), ApiError> {
-    if response.status().is_success() {
+  async fn parse_ok_result(&self, response: TransportResponse) -> Result<(), ApiError> {
+    if response.status.is_success() {
       Ok(())
     } else {
-      let error = response.json::<PinataApiError>().await?;
-      Err(ApiError::GenericError(error.message()))
+      Err(self.parse_error(response).await)
+    }
+  }
+
+  async fn parse_error(&self, response: TransportResponse) -> ApiError {
+    let status = response.status;
+    let body = response.text();
+
+    match serde_json::from_str::<PinataApiError>(&body) {
+      Ok(error) => ApiError::Http {
+        status: status.as_u16(),
+        message: error.message(),
+        reason: error.reason(),
+      },
+      Err(_) if body.trim().is_empty() => ApiError::Http {
+        status: status.as_u16(),
+        message: status.canonical_reason().unwrap_or_default().to_string(),
+        reason: None,
+      },
+      Err(_) => ApiError::UnexpectedResponse {
+        status: status.as_u16(),
+        body: utils::truncate_body(body),
+      },
     }
   }
 }
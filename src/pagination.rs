@@ -0,0 +1,117 @@
+use crate::{PinataApi, PinJob, PinJobsFilter, PinListFilter, PinRecord};
+use crate::errors::ApiError;
+
+/// Maximum number of records Pinata returns per pin list page
+const MAX_PAGE_LIMIT: u16 = 1000;
+
+/// Maximum number of jobs Pinata returns per pin jobs page
+const MAX_PIN_JOBS_PAGE_LIMIT: u16 = 1000;
+
+/// Walks through all the pages of a pin list query, one page at a time.
+///
+/// The page limit and offset set on the filter are respected, if no page limit
+/// is set, the maximum page size allowed by Pinata is used.
+pub(crate) struct PinListPager {
+  filters: PinListFilter,
+  limit: u16,
+  offset: u64,
+  count: Option<u64>,
+  count_changed: bool,
+  done: bool,
+}
+
+impl PinListPager {
+  pub(crate) fn new(filters: PinListFilter) -> PinListPager {
+    let limit = filters.page_limit.unwrap_or(MAX_PAGE_LIMIT);
+    let offset = filters.page_offset.unwrap_or(0);
+
+    PinListPager {
+      filters,
+      limit,
+      offset,
+      count: None,
+      count_changed: false,
+      done: false,
+    }
+  }
+
+  /// Fetches the next page of results. Returns `None` once all pages have been read.
+  pub(crate) async fn next_page(&mut self, api: &PinataApi) -> Result<Option<Vec<PinRecord>>, ApiError> {
+    if self.done {
+      return Ok(None);
+    }
+
+    self.filters.page_limit = Some(self.limit);
+    self.filters.page_offset = Some(self.offset);
+    let page = api.get_pin_list(self.filters.clone()).await?;
+
+    if self.count.is_some() && self.count != Some(page.count) {
+      self.count_changed = true;
+    }
+    self.count = Some(page.count);
+
+    self.offset += page.rows.len() as u64;
+    if page.rows.is_empty() || self.offset >= page.count {
+      self.done = true;
+    }
+
+    if page.rows.is_empty() {
+      Ok(None)
+    } else {
+      Ok(Some(page.rows))
+    }
+  }
+
+  /// Whether the total number of matching records changed between the pages read so far,
+  /// meaning records may have shifted between pages while walking through them.
+  pub(crate) fn count_changed(&self) -> bool {
+    self.count_changed
+  }
+}
+
+/// Walks through all the pages of a pin jobs query, one page at a time.
+///
+/// The limit and offset set on the filter are respected, if no limit is set,
+/// the maximum page size allowed by Pinata is used.
+pub(crate) struct PinJobsPager {
+  filters: PinJobsFilter,
+  limit: u16,
+  offset: u64,
+  done: bool,
+}
+
+impl PinJobsPager {
+  pub(crate) fn new(filters: PinJobsFilter) -> PinJobsPager {
+    let limit = filters.limit.unwrap_or(MAX_PIN_JOBS_PAGE_LIMIT);
+    let offset = filters.offset.unwrap_or(0);
+
+    PinJobsPager {
+      filters,
+      limit,
+      offset,
+      done: false,
+    }
+  }
+
+  /// Fetches the next page of jobs. Returns `None` once all pages have been read.
+  pub(crate) async fn next_page(&mut self, api: &PinataApi) -> Result<Option<Vec<PinJob>>, ApiError> {
+    if self.done {
+      return Ok(None);
+    }
+
+    self.filters.limit = Some(self.limit);
+    self.filters.offset = Some(self.offset);
+    let page = api.get_pin_jobs(self.filters.clone()).await?;
+
+    self.offset += page.rows.len() as u64;
+    if page.rows.is_empty() || self.offset >= page.count {
+      self.done = true;
+    }
+
+    if page.rows.is_empty() {
+      Ok(None)
+    } else {
+      Ok(Some(page.rows))
+    }
+  }
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use crate::api::data::{BreakdownKey, Cost, PinRecord, PinnedDataSummary};
+use crate::api::metadata::MetadataValue;
+
+/// Bytes in a GB used when computing storage costs
+const BYTES_PER_GB: f64 = 1_073_741_824.0;
+
+/// Adds the pins to the cost breakdown, bucketed by the value of `by`. `pin_groups` maps the
+/// hashes of grouped pins to the id of their group, it's only used to break down by group.
+///
+/// Pins without a value for `by` are added to the `""` bucket.
+pub(crate) fn add_to_cost_breakdown(
+  breakdown: &mut HashMap<String, Cost>,
+  pins: &[PinRecord],
+  by: &BreakdownKey,
+  pin_groups: &HashMap<String, String>,
+  price_per_gb: f64,
+) {
+  for pin in pins {
+    let bucket = match by {
+      BreakdownKey::MetadataKey(key) => pin.metadata.keyvalues.as_ref()
+        .and_then(|keyvalues| keyvalues.get(key))
+        .and_then(metadata_value_to_string)
+        .unwrap_or_default(),
+      BreakdownKey::Group => pin_groups.get(&pin.ipfs_pin_hash).cloned().unwrap_or_default(),
+    };
+
+    let cost = breakdown.entry(bucket).or_default();
+    cost.pin_count += 1;
+    cost.total_size += pin.size;
+    cost.cost = cost.total_size as f64 / BYTES_PER_GB * price_per_gb;
+  }
+}
+
+/// Bytes in a MB used when bucketing pins by size
+const BYTES_PER_MB: u64 = 1_048_576;
+
+/// Adds the pins to the size bucket matching their size
+pub(crate) fn add_to_size_summary(summary: &mut PinnedDataSummary, pins: &[PinRecord]) {
+  for pin in pins {
+    let bucket = match pin.size {
+      size if size < BYTES_PER_MB => &mut summary.under_1mb,
+      size if size < 10 * BYTES_PER_MB => &mut summary.from_1mb_to_10mb,
+      size if size < 100 * BYTES_PER_MB => &mut summary.from_10mb_to_100mb,
+      _ => &mut summary.over_100mb,
+    };
+
+    bucket.pin_count += 1;
+    bucket.total_size += pin.size;
+  }
+}
+
+fn metadata_value_to_string(value: &MetadataValue) -> Option<String> {
+  match value {
+    MetadataValue::String(value) => Some(value.clone()),
+    MetadataValue::Float(value) => Some(value.to_string()),
+    MetadataValue::Integer(value) => Some(value.to_string()),
+    MetadataValue::Bool(value) => Some(value.to_string()),
+    MetadataValue::Delete => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+  use crate::api::data::{BreakdownKey, PinRecord, PinnedDataSummary, SizeBucket};
+  use super::{add_to_cost_breakdown, add_to_size_summary};
+
+  fn pin(size: u64, keyvalues: &str) -> PinRecord {
+    pin_with_hash("QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH", size, keyvalues)
+  }
+
+  fn pin_with_hash(hash: &str, size: u64, keyvalues: &str) -> PinRecord {
+    serde_json::from_str(&format!(r#"{{
+      "id": "id",
+      "ipfs_pin_hash": "{}",
+      "size": {},
+      "user_id": "user",
+      "date_pinned": "2020-04-19T15:07:36.700Z",
+      "date_unpinned": null,
+      "metadata": {{ "name": null, "keyvalues": {} }},
+      "regions": []
+    }}"#, hash, size, keyvalues)).unwrap()
+  }
+
+  #[test]
+  fn test_pins_are_bucketed_by_size() {
+    let pins = [pin(0, "null"), pin(1_048_575, "null"), pin(1_048_576, "null"), pin(104_857_600, "null")];
+
+    let mut summary = PinnedDataSummary::default();
+    add_to_size_summary(&mut summary, &pins);
+
+    assert_eq!(summary.under_1mb, SizeBucket { pin_count: 2, total_size: 1_048_575 });
+    assert_eq!(summary.from_1mb_to_10mb, SizeBucket { pin_count: 1, total_size: 1_048_576 });
+    assert_eq!(summary.from_10mb_to_100mb, SizeBucket::default());
+    assert_eq!(summary.over_100mb, SizeBucket { pin_count: 1, total_size: 104_857_600 });
+  }
+
+  #[test]
+  fn test_costs_are_broken_down_by_metadata_key() {
+    let pins = [
+      pin(536_870_912, r#"{"tenant": "acme"}"#),
+      pin(536_870_912, r#"{"tenant": "acme"}"#),
+      pin(1_073_741_824, r#"{"tenant": "globex"}"#),
+      pin(100, "null"),
+    ];
+
+    let mut breakdown = HashMap::new();
+    let by = BreakdownKey::MetadataKey("tenant".to_string());
+    add_to_cost_breakdown(&mut breakdown, &pins, &by, &HashMap::new(), 0.15);
+
+    let acme = &breakdown["acme"];
+    assert_eq!(acme.pin_count, 2);
+    assert_eq!(acme.total_size, 1_073_741_824);
+    assert!((acme.cost - 0.15).abs() < f64::EPSILON);
+
+    assert_eq!(breakdown["globex"].pin_count, 1);
+    assert_eq!(breakdown[""].total_size, 100);
+  }
+
+  #[test]
+  fn test_costs_are_broken_down_by_group() {
+    let pins = [
+      pin_with_hash("QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH", 1_073_741_824, "null"),
+      pin_with_hash("QmWsZfQw98k9dfG1sDZB3z8YqMtxG9gYCyddgZGWq4w6Z3", 100, "null"),
+    ];
+    let mut pin_groups = HashMap::new();
+    pin_groups.insert("QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH".to_string(), "group_id".to_string());
+
+    let mut breakdown = HashMap::new();
+    add_to_cost_breakdown(&mut breakdown, &pins, &BreakdownKey::Group, &pin_groups, 0.15);
+
+    assert_eq!(breakdown["group_id"].pin_count, 1);
+    assert!((breakdown["group_id"].cost - 0.15).abs() < f64::EPSILON);
+    assert_eq!(breakdown[""].total_size, 100);
+  }
+}
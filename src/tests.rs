@@ -1,23 +1,68 @@
 use insta::assert_debug_snapshot;
 use serde::Serialize;
 use std::collections::HashMap;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use wiremock::matchers::{body_json, header, method, path, query_param};
 use super::*;
 
 fn get_api() -> PinataApi {
-  let api_key = std::env::var("API_KEY").expect("API_KEY env required to run test");
-  let secret_api_key = std::env::var("SECRET_API_KEY").expect("SECRET_API_KEY env required to run test");
-  super::PinataApi::new(api_key, secret_api_key).unwrap()
+  PinataApi::from_env().expect("PINATA_API_KEY and PINATA_SECRET_API_KEY env required to run test")
+}
+
+/// Transport answering requests to `path` with `body`, and any other request with a 404
+struct CannedTransport {
+  path: &'static str,
+  body: serde_json::Value,
+}
+
+impl HttpTransport for CannedTransport {
+  fn execute(&self, request: reqwest::Request) -> TransportFuture<'_> {
+    let response = if request.url().path() == self.path {
+      TransportResponse::new(&request, reqwest::StatusCode::OK, self.body.to_string())
+    } else {
+      TransportResponse::new(&request, reqwest::StatusCode::NOT_FOUND, "")
+    };
+    Box::pin(async move { Ok(response) })
+  }
+}
+
+fn get_canned_api(path: &'static str, body: serde_json::Value) -> PinataApi {
+  PinataApi::builder()
+    .api_keys("api_key", "secret_api_key")
+    .transport(CannedTransport { path, body })
+    .build()
+    .unwrap()
 }
 
 #[tokio::test]
 async fn test_authentication_works() {
-  let result = get_api().test_authentication().await;
+  let api = get_canned_api("/data/testAuthentication", serde_json::json!({
+    "message": "Congratulations! You are communicating with the Pinata API!"
+  }));
+  let result = api.test_authentication().await;
   match result {
     Ok(_) => assert!(true),
     Err(_) => assert!(false),
   }
 }
 
+#[tokio::test]
+async fn test_authentication_against_base_url() {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/data/testAuthentication"))
+    .and(header("pinata_api_key", "api_key"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "message": "Congratulations! You are communicating with the Pinata API!"
+    })))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", format!("{}/", server.uri())).unwrap();
+  api.test_authentication().await.unwrap();
+}
+
 #[tokio::test]
 async fn test_set_hash_pin_policy_works() {
   // Note the hash provided
@@ -195,19 +240,11 @@ async fn test_change_hash_metadata_pin_querying_works() {
     .await
     .unwrap();
 
-  // update metadata information
-  let mut new_metadata = HashMap::new();
-  new_metadata.insert("new_value".to_string(), MetadataValue::String("awesome".into()));
-
-  // deletes existing metadata
-  new_metadata.insert("to_be_deleted".to_string(), MetadataValue::Delete);
-  api.change_hash_metadata(ChangePinMetadata {
-    ipfs_pin_hash: pin_result.ipfs_hash.clone(),
-    metadata: PinMetadata {
-      name: None, // we don't want to change the existing name
-      keyvalues: new_metadata
-    }
-  }).await.unwrap();
+  // update metadata information, deleting existing metadata and keeping the existing name
+  api.change_hash_metadata(ChangePinMetadata::new(pin_result.ipfs_hash.clone())
+    .add_keyvalue("new_value", MetadataValue::String("awesome".into()))
+    .delete_keyvalue("to_be_deleted")
+  ).await.unwrap();
 
   // confirm metadata is updated
   let result = api.get_pin_list(PinListFilterBuilder::default()
@@ -228,7 +265,12 @@ async fn test_change_hash_metadata_pin_querying_works() {
 
 #[tokio::test]
 async fn test_get_total_user_pinned_data() {
-  let result = get_api().get_total_user_pinned_data().await;
+  let api = get_canned_api("/data/userPinnedDataTotal", serde_json::json!({
+    "pin_count": 12,
+    "pin_size_total": "3456",
+    "pin_size_with_replications_total": "6912"
+  }));
+  let result = api.get_total_user_pinned_data().await;
 
   match result {
     Ok(data) => {
@@ -239,4 +281,2254 @@ async fn test_get_total_user_pinned_data() {
     }
     Err(e) => assert!(false, "{}", e),
   }
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_get_account_usage() {
+  let api = get_canned_api("/data/userPinnedDataTotal", serde_json::json!({
+    "pin_count": 12,
+    "pin_size_total": "3456",
+    "pin_size_with_replications_total": "6912"
+  }));
+  let usage = api.get_account_usage().await.unwrap();
+
+  assert_eq!(usage, AccountUsage {
+    pin_count: 12,
+    total_size: 3456,
+    total_size_with_replications: 6912,
+    storage_limit: None,
+    remaining: None,
+  });
+}
+
+#[tokio::test]
+async fn test_from_client_attaches_auth_headers() {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/data/testAuthentication"))
+    .and(header("pinata_api_key", "api_key"))
+    .and(header("pinata_secret_api_key", "secret_api_key"))
+    .respond_with(ResponseTemplate::new(200))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let mut api = PinataApi::from_client(Client::new(), "api_key", "secret_api_key").unwrap();
+  api.base_url = server.uri();
+  api.test_authentication().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_timed_out_request_returns_timeout_error() {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/data/testAuthentication"))
+    .respond_with(ResponseTemplate::new(200).set_delay(std::time::Duration::from_secs(2)))
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::builder()
+    .api_keys("api_key", "secret_api_key")
+    .base_url(server.uri())
+    .timeout(std::time::Duration::from_millis(100))
+    .build()
+    .unwrap();
+
+  match api.test_authentication().await {
+    Err(ApiError::Timeout()) => {},
+    other => panic!("expected a timeout error, got {:?}", other),
+  }
+}
+
+#[tokio::test]
+async fn test_retries_server_errors() {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/data/testAuthentication"))
+    .respond_with(ResponseTemplate::new(503))
+    .up_to_n_times(2)
+    .expect(2)
+    .mount(&server)
+    .await;
+  Mock::given(method("GET"))
+    .and(path("/data/testAuthentication"))
+    .respond_with(ResponseTemplate::new(200))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::builder()
+    .api_keys("api_key", "secret_api_key")
+    .base_url(server.uri())
+    .max_retries(2)
+    .retry_base_delay(std::time::Duration::from_millis(10))
+    .build()
+    .unwrap();
+
+  api.test_authentication().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_pin_file_retry_rebuilds_upload() {
+  let server = MockServer::start().await;
+  Mock::given(method("POST"))
+    .and(path("/pinning/pinFileToIPFS"))
+    .respond_with(ResponseTemplate::new(503))
+    .up_to_n_times(1)
+    .expect(1)
+    .mount(&server)
+    .await;
+  Mock::given(method("POST"))
+    .and(path("/pinning/pinFileToIPFS"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "IpfsHash": "QmHash",
+      "PinSize": 10,
+      "Timestamp": "2021-01-01T00:00:00.000Z"
+    })))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::builder()
+    .api_keys("api_key", "secret_api_key")
+    .base_url(server.uri())
+    .max_retries(1)
+    .retry_base_delay(std::time::Duration::from_millis(10))
+    .build()
+    .unwrap();
+  let pin_data = PinByFile::new("./test-file.txt")
+    .add_file(FileSource::Bytes(b"in memory".to_vec()), "memory.txt");
+
+  let result = api.pin_file(pin_data).await.unwrap();
+  assert_eq!(result.ipfs_hash, "QmHash");
+
+  let requests = server.received_requests().await.unwrap();
+  assert_eq!(requests.len(), 2);
+  for request in &requests {
+    let body = String::from_utf8_lossy(&request.body);
+    assert!(body.contains("This is a unique test file."));
+    assert!(body.contains("in memory"));
+  }
+}
+
+#[tokio::test]
+async fn test_pin_file_field_name() {
+  let server = MockServer::start().await;
+  Mock::given(method("POST"))
+    .and(path("/pinning/pinFileToIPFS"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "IpfsHash": "QmHash",
+      "PinSize": 10,
+      "Timestamp": "2021-01-01T00:00:00.000Z"
+    })))
+    .expect(2)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  api.pin_file(PinByFile::from_bytes("a.txt", b"a".to_vec())).await.unwrap();
+  api.pin_file(PinByFile::from_bytes("b.txt", b"b".to_vec()).field_name("upload")).await.unwrap();
+
+  let requests = server.received_requests().await.unwrap();
+  let bodies: Vec<_> = requests.iter().map(|request| String::from_utf8_lossy(&request.body).into_owned()).collect();
+  assert!(bodies[0].contains(r#"name="file"; filename="a.txt""#));
+  assert!(bodies[1].contains(r#"name="upload"; filename="b.txt""#));
+}
+
+#[tokio::test]
+async fn test_pin_file_verified() {
+  let server = MockServer::start().await;
+  for (cid, content) in &[("QmGood", "content"), ("QmBad", "corrupted")] {
+    Mock::given(method("GET"))
+      .and(path(format!("/ipfs/{}", cid)))
+      .respond_with(ResponseTemplate::new(200).set_body_string(*content))
+      .mount(&server)
+      .await;
+  }
+  for (file_name, cid) in &[("good.txt", "QmGood"), ("bad.txt", "QmBad")] {
+    Mock::given(method("POST"))
+      .and(path("/pinning/pinFileToIPFS"))
+      .and(wiremock::matchers::body_string_contains(*file_name))
+      .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+        "IpfsHash": cid,
+        "PinSize": 7,
+        "Timestamp": "2021-01-01T00:00:00.000Z"
+      })))
+      .mount(&server)
+      .await;
+  }
+
+  let api = PinataApi::builder()
+    .api_keys("api_key", "secret_api_key")
+    .base_url(server.uri())
+    .gateway_url(server.uri())
+    .build()
+    .unwrap();
+
+  let pinned = api.pin_file_verified(PinByFile::from_bytes("good.txt", b"content".to_vec())).await.unwrap();
+  assert_eq!(pinned.ipfs_hash, "QmGood");
+
+  match api.pin_file_verified(PinByFile::from_bytes("bad.txt", b"content".to_vec())).await {
+    Err(ApiError::IntegrityMismatch { cid, expected, actual }) => {
+      assert_eq!(cid, "QmBad");
+      assert_eq!(expected, "ed7002b439e9ac845f22357d822bac1444730fbdb6016d3ec9432297b9ec9f73");
+      assert_ne!(actual, expected);
+    },
+    other => panic!("expected an integrity mismatch, got {:?}", other.map(|pinned| pinned.ipfs_hash)),
+  }
+
+  match api.pin_file_verified(PinByFile::new("./test-dir")).await {
+    Err(ApiError::NotSingleFile { count }) => assert!(count > 1),
+    other => panic!("expected a directory to be rejected, got {:?}", other.map(|pinned| pinned.ipfs_hash)),
+  }
+}
+
+#[tokio::test]
+async fn test_pin_file_verified_fetches_wrapped_file() {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/ipfs/QmWrapper/wrapped.txt"))
+    .respond_with(ResponseTemplate::new(200).set_body_string("content"))
+    .expect(2)
+    .mount(&server)
+    .await;
+  Mock::given(method("POST"))
+    .and(path("/pinning/pinFileToIPFS"))
+    .and(wiremock::matchers::body_string_contains("\"wrapWithDirectory\":true"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "IpfsHash": "QmWrapper",
+      "PinSize": 60,
+      "Timestamp": "2021-01-01T00:00:00.000Z"
+    })))
+    .expect(2)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::builder()
+    .api_keys("api_key", "secret_api_key")
+    .base_url(server.uri())
+    .gateway_url(server.uri())
+    .build()
+    .unwrap();
+  let pin_data = PinByFile::from_bytes("wrapped.txt", b"content".to_vec())
+    .set_options(PinOptions::new().wrap_with_directory(true));
+  assert_eq!(api.pin_file_verified(pin_data).await.unwrap().ipfs_hash, "QmWrapper");
+
+  let api = PinataApi::builder()
+    .api_keys("api_key", "secret_api_key")
+    .base_url(server.uri())
+    .gateway_url(server.uri())
+    .default_pin_options(PinOptions::new().wrap_with_directory(true))
+    .build()
+    .unwrap();
+  let pin_data = PinByFile::from_bytes("wrapped.txt", b"content".to_vec());
+  assert_eq!(api.pin_file_verified(pin_data).await.unwrap().ipfs_hash, "QmWrapper");
+}
+
+#[tokio::test]
+async fn test_exhausted_retries_report_attempts() {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/data/testAuthentication"))
+    .respond_with(ResponseTemplate::new(429).set_body_json(serde_json::json!({ "error": "Rate limited" })))
+    .expect(2)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::builder()
+    .api_keys("api_key", "secret_api_key")
+    .base_url(server.uri())
+    .max_retries(1)
+    .retry_base_delay(std::time::Duration::from_millis(10))
+    .build()
+    .unwrap();
+
+  match api.test_authentication().await {
+    Err(ApiError::RetriesExhausted { attempts, .. }) => assert_eq!(attempts, 2),
+    other => panic!("expected retries to be exhausted, got {:?}", other),
+  }
+}
+
+/// Peak resident set size of the test process, in bytes.
+#[cfg(target_os = "linux")]
+fn peak_rss() -> u64 {
+  let status = std::fs::read_to_string("/proc/self/status").unwrap();
+  let line = status.lines().find(|line| line.starts_with("VmHWM:")).unwrap();
+  let kb: u64 = line.split_whitespace().nth(1).unwrap().parse().unwrap();
+  kb * 1024
+}
+
+// wiremock keeps every request body in memory, so the upload goes to a bare server that only counts
+// the body bytes it receives.
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn test_pin_file_streams_file_content() {
+  use std::io::Write;
+  use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+  const FILE_SIZE: usize = 50 * 1024 * 1024;
+
+  let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let base_url = format!("http://{}", listener.local_addr().unwrap());
+  let server = tokio::spawn(async move {
+    let (mut socket, _) = listener.accept().await.unwrap();
+    let mut received = Vec::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    let header_end = loop {
+      let read = socket.read(&mut buf).await.unwrap();
+      received.extend_from_slice(&buf[..read]);
+      if let Some(end) = received.windows(4).position(|window| window == b"\r\n\r\n") {
+        break end + 4;
+      }
+    };
+    let headers = String::from_utf8_lossy(&received[..header_end]).to_lowercase();
+    let content_length: Option<usize> = headers.lines()
+      .find_map(|line| line.strip_prefix("content-length:"))
+      .map(|value| value.trim().parse().unwrap());
+
+    let mut body_len = received.len() - header_end;
+    let mut body_start = received[header_end..].to_vec();
+    let mut tail = body_start.clone();
+    loop {
+      match content_length {
+        Some(length) if body_len >= length => break,
+        None if tail.ends_with(b"0\r\n\r\n") => break,
+        _ => {},
+      }
+      let read = socket.read(&mut buf).await.unwrap();
+      assert!(read > 0, "connection closed before the end of the body");
+      body_len += read;
+      if body_start.len() < 512 {
+        body_start.extend_from_slice(&buf[..read]);
+      }
+      tail.extend_from_slice(&buf[..read]);
+      let keep = tail.len().saturating_sub(5);
+      tail.drain(..keep);
+    }
+
+    let response = serde_json::json!({
+      "IpfsHash": "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH",
+      "PinSize": FILE_SIZE,
+      "Timestamp": "2020-04-19T15:07:36.700Z"
+    }).to_string();
+    let reply = format!(
+      "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+      response.len(), response
+    );
+    socket.write_all(reply.as_bytes()).await.unwrap();
+    (String::from_utf8_lossy(&body_start[..512]).into_owned(), body_len)
+  });
+
+  let dir = tempfile::tempdir().unwrap();
+  let file_path = dir.path().join("large-file.bin");
+  let mut file = std::fs::File::create(&file_path).unwrap();
+  let chunk = vec![7u8; 1024 * 1024];
+  for _ in 0..FILE_SIZE / chunk.len() {
+    file.write_all(&chunk).unwrap();
+  }
+  drop(file);
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", base_url).unwrap();
+  let rss_before = peak_rss();
+  api.pin_file(PinByFile::new(file_path.to_str().unwrap())).await.unwrap();
+  let rss_growth = peak_rss().saturating_sub(rss_before);
+
+  let (body_start, body_len) = server.await.unwrap();
+  assert!(body_len > FILE_SIZE);
+  assert!(body_start.contains("filename=\"large-file.bin\""));
+  assert!(
+    rss_growth < FILE_SIZE as u64 / 4,
+    "peak memory grew by {} bytes while uploading a {} byte file", rss_growth, FILE_SIZE
+  );
+}
+
+#[tokio::test]
+async fn test_pin_file_from_bytes() {
+  let server = MockServer::start().await;
+  Mock::given(method("POST"))
+    .and(path("/pinning/pinFileToIPFS"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "IpfsHash": "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH",
+      "PinSize": 26,
+      "Timestamp": "2020-04-19T15:07:36.700Z"
+    })))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  api.pin_file(PinByFile::from_bytes("in-memory.txt", b"content from memory".to_vec())).await.unwrap();
+
+  let requests = server.received_requests().await.unwrap();
+  let body = String::from_utf8_lossy(&requests[0].body);
+  assert!(body.contains("filename=\"in-memory.txt\""));
+  assert!(body.contains("content from memory"));
+}
+
+#[tokio::test]
+async fn test_pin_files_added_to_folder() {
+  let server = MockServer::start().await;
+  Mock::given(method("POST"))
+    .and(path("/pinning/pinFileToIPFS"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "IpfsHash": "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH",
+      "PinSize": 26,
+      "Timestamp": "2020-04-19T15:07:36.700Z"
+    })))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  let pin = PinByFile::folder("bundle")
+    .add_file("./test-file.txt", "notes.txt")
+    .add_file(b"first in memory".to_vec(), "one.txt")
+    .add_file(b"second in memory".to_vec(), "nested/two.txt");
+  api.pin_file(pin).await.unwrap();
+
+  let requests = server.received_requests().await.unwrap();
+  let body = String::from_utf8_lossy(&requests[0].body);
+  assert!(body.contains("filename=\"bundle/notes.txt\""));
+  assert!(body.contains("filename=\"bundle/one.txt\""));
+  assert!(body.contains("filename=\"bundle/nested/two.txt\""));
+  assert!(body.contains("first in memory") && body.contains("second in memory"));
+}
+
+#[tokio::test]
+async fn test_pin_file_cancelled_mid_upload() {
+  let server = MockServer::start().await;
+  Mock::given(method("POST"))
+    .and(path("/pinning/pinFileToIPFS"))
+    .respond_with(ResponseTemplate::new(200)
+      .set_delay(std::time::Duration::from_secs(30))
+      .set_body_json(serde_json::json!({
+        "IpfsHash": "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH",
+        "PinSize": 7,
+        "Timestamp": "2020-04-19T15:07:36.700Z"
+      })))
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  let token = CancellationToken::new();
+  let cancel = token.clone();
+  tokio::spawn(async move {
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    cancel.cancel();
+  });
+
+  let started = std::time::Instant::now();
+  let pin = PinByFile::from_bytes("large.bin", b"content".to_vec()).cancellation_token(token.clone());
+  match api.pin_file(pin).await {
+    Err(ApiError::Cancelled()) => {},
+    other => panic!("expected the upload to be cancelled, got {:?}", other),
+  }
+  assert!(started.elapsed() < std::time::Duration::from_secs(5));
+  assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+  let pin = PinByFile::from_bytes("large.bin", b"content".to_vec()).cancellation_token(token);
+  assert!(matches!(api.pin_file(pin).await, Err(ApiError::Cancelled())));
+}
+
+#[tokio::test]
+async fn test_pin_file_from_entries() {
+  let server = MockServer::start().await;
+  Mock::given(method("POST"))
+    .and(path("/pinning/pinFileToIPFS"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "IpfsHash": "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH",
+      "PinSize": 48,
+      "Timestamp": "2020-04-19T15:07:36.700Z"
+    })))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  let pin = PinByFile::from_entries("site", vec![
+    ("index.html".to_string(), b"<h1>home</h1>".to_vec()),
+    ("./blog/index.html".to_string(), b"<h1>blog</h1>".to_vec()),
+    ("blog\\posts\\first.html".to_string(), b"<h1>first</h1>".to_vec()),
+  ]).unwrap();
+  api.pin_file(pin).await.unwrap();
+
+  let requests = server.received_requests().await.unwrap();
+  let body = String::from_utf8_lossy(&requests[0].body);
+  assert!(body.contains("filename=\"site/index.html\""));
+  assert!(body.contains("filename=\"site/blog/index.html\""));
+  assert!(body.contains("filename=\"site/blog/posts/first.html\""));
+  assert!(body.contains("<h1>first</h1>"));
+
+  for path in &["../x", "blog/../../x", "..\\x"] {
+    match PinByFile::from_entries("site", vec![(path.to_string(), b"x".to_vec())]) {
+      Err(ApiError::InvalidPath(invalid)) => assert_eq!(invalid, std::path::PathBuf::from(path)),
+      other => panic!("expected an invalid path error for {}, got {:?}", path, other.map(|_| ())),
+    }
+  }
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_pin_directory_uploads_symlinked_files_by_default() {
+  use std::os::unix::fs::symlink;
+
+  let server = MockServer::start().await;
+  Mock::given(method("POST"))
+    .and(path("/pinning/pinFileToIPFS"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "IpfsHash": "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH",
+      "PinSize": 48,
+      "Timestamp": "2020-04-19T15:07:36.700Z"
+    })))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let dir = tempfile::tempdir().unwrap();
+  let base = dir.path().join("site");
+  std::fs::create_dir(&base).unwrap();
+  std::fs::write(dir.path().join("shared.css"), "body { margin: 0 }").unwrap();
+  std::fs::write(base.join("index.html"), "<h1>home</h1>").unwrap();
+  symlink(dir.path().join("shared.css"), base.join("style.css")).unwrap();
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  api.pin_file(PinByFile::new(base.to_str().unwrap())).await.unwrap();
+
+  let requests = server.received_requests().await.unwrap();
+  let body = String::from_utf8_lossy(&requests[0].body);
+  assert!(body.contains("filename=\"site/index.html\""));
+  assert!(body.contains("filename=\"site/style.css\""));
+  assert!(body.contains("body { margin: 0 }"));
+}
+
+#[tokio::test]
+async fn test_pin_directory_detailed_lists_uploaded_files() {
+  let server = MockServer::start().await;
+  Mock::given(method("POST"))
+    .and(path("/pinning/pinFileToIPFS"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "IpfsHash": "QmYTyd2A15snZbRbWi2cbZkis45DzDdPSdzdF3wXdMEWVk",
+      "PinSize": 291,
+      "Timestamp": "2020-04-19T15:07:36.700Z"
+    })))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  let result = api.pin_directory_detailed(PinByFile::new("./test-dir")).await.unwrap();
+  assert_eq!(result.pinned.ipfs_hash, "QmYTyd2A15snZbRbWi2cbZkis45DzDdPSdzdF3wXdMEWVk");
+
+  let mut files = result.files;
+  files.sort();
+  let expected: Vec<(String, u64)> = ["a.txt", "b.txt", "inside/a.txt"].iter()
+    .map(|path| (path.to_string(), std::fs::metadata(format!("./test-dir/{}", path)).unwrap().len()))
+    .collect();
+  assert_eq!(files, expected);
+}
+
+#[tokio::test]
+async fn test_pin_file_skips_excluded_directories() {
+  let server = MockServer::start().await;
+  Mock::given(method("POST"))
+    .and(path("/pinning/pinFileToIPFS"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "IpfsHash": "QmYTyd2A15snZbRbWi2cbZkis45DzDdPSdzdF3wXdMEWVk",
+      "PinSize": 291,
+      "Timestamp": "2020-04-19T15:07:36.700Z"
+    })))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let dir = tempfile::tempdir().unwrap();
+  let project = dir.path().join("project");
+  std::fs::create_dir_all(project.join(".git/objects")).unwrap();
+  std::fs::write(project.join(".git/HEAD"), "ref: refs/heads/main").unwrap();
+  std::fs::write(project.join(".git/objects/ab"), "object").unwrap();
+  std::fs::write(project.join("README.md"), "# project").unwrap();
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  api.pin_file(
+    PinByFile::new(project.to_str().unwrap()).exclude_globs(vec![".git".to_string()])
+  ).await.unwrap();
+
+  let requests = server.received_requests().await.unwrap();
+  let body = String::from_utf8_lossy(&requests[0].body);
+  assert!(body.contains("filename=\"project/README.md\""));
+  assert!(!body.contains(".git"));
+}
+
+#[tokio::test]
+async fn test_pin_file_with_progress_reports_directory_total() {
+  let server = MockServer::start().await;
+  Mock::given(method("POST"))
+    .and(path("/pinning/pinFileToIPFS"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "IpfsHash": "QmYTyd2A15snZbRbWi2cbZkis45DzDdPSdzdF3wXdMEWVk",
+      "PinSize": 291,
+      "Timestamp": "2020-04-19T15:07:36.700Z"
+    })))
+    .mount(&server)
+    .await;
+
+  let total_size: u64 = ["./test-dir/a.txt", "./test-dir/b.txt", "./test-dir/inside/a.txt"].iter()
+    .map(|path| std::fs::metadata(path).unwrap().len())
+    .sum();
+  let reports = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+  let recorded = reports.clone();
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  api.pin_file_with_progress(PinByFile::new("./test-dir"), move |uploaded, total| {
+    recorded.lock().unwrap().push((uploaded, total));
+  }).await.unwrap();
+
+  let reports = reports.lock().unwrap();
+  assert_eq!(*reports.last().unwrap(), (total_size, total_size));
+}
+
+#[tokio::test]
+async fn test_error_responses_keep_status_and_reason() {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/data/testAuthentication"))
+    .respond_with(ResponseTemplate::new(401).set_body_json(serde_json::json!({
+      "error": { "reason": "INVALID_API_KEYS", "details": "Invalid API key provided" }
+    })))
+    .mount(&server)
+    .await;
+  Mock::given(method("DELETE"))
+    .and(path("/pinning/unpin/QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH"))
+    .respond_with(ResponseTemplate::new(502).set_body_string("<html>Bad Gateway</html>"))
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::builder()
+    .api_keys("api_key", "secret_api_key")
+    .base_url(server.uri())
+    .max_retries(0)
+    .build()
+    .unwrap();
+
+  let error = api.test_authentication().await.unwrap_err();
+  assert_eq!(error.status_code(), Some(401));
+  match error {
+    ApiError::Http { status, message, reason } => {
+      assert_eq!(status, 401);
+      assert_eq!(message, "Invalid API key provided");
+      assert_eq!(reason, Some("INVALID_API_KEYS".to_string()));
+    },
+    other => panic!("expected an http error, got {:?}", other),
+  }
+
+  let error = api.unpin("QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH").await.unwrap_err();
+  assert_eq!(error.status_code(), Some(502));
+  match error {
+    ApiError::UnexpectedResponse { status, body } => {
+      assert_eq!(status, 502);
+      assert_eq!(body, "<html>Bad Gateway</html>");
+    },
+    other => panic!("expected an unexpected response error, got {:?}", other),
+  }
+
+  assert_eq!(api.unpin("not a cid").await.unwrap_err().status_code(), None);
+}
+
+#[tokio::test]
+async fn test_check_connection_classifies_errors() {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/data/testAuthentication"))
+    .and(header("pinata_api_key", "revoked_key"))
+    .respond_with(ResponseTemplate::new(401).set_body_json(serde_json::json!({
+      "error": { "reason": "INVALID_API_KEYS", "details": "Invalid API key provided" }
+    })))
+    .mount(&server)
+    .await;
+  Mock::given(method("GET"))
+    .and(path("/data/testAuthentication"))
+    .and(header("pinata_api_key", "scoped_key"))
+    .respond_with(ResponseTemplate::new(403))
+    .mount(&server)
+    .await;
+  Mock::given(method("GET"))
+    .and(path("/data/testAuthentication"))
+    .and(header("pinata_api_key", "api_key"))
+    .respond_with(ResponseTemplate::new(503).set_body_string("<html>Service Unavailable</html>"))
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("revoked_key", "secret_api_key", server.uri()).unwrap();
+  match api.check_connection().await {
+    Err(ApiError::Unauthorized { status, message }) => {
+      assert_eq!(status, 401);
+      assert_eq!(message, "Invalid API key provided");
+    },
+    other => panic!("expected an unauthorized error, got {:?}", other),
+  }
+
+  let api = PinataApi::new_with_base_url("scoped_key", "secret_api_key", server.uri()).unwrap();
+  match api.check_connection().await {
+    Err(ApiError::Unauthorized { status, .. }) => assert_eq!(status, 403),
+    other => panic!("expected an unauthorized error, got {:?}", other),
+  }
+
+  let api = PinataApi::builder()
+    .api_keys("api_key", "secret_api_key")
+    .base_url(server.uri())
+    .retry_base_delay(std::time::Duration::from_millis(1))
+    .build()
+    .unwrap();
+  match api.check_connection().await {
+    Err(ApiError::ServiceUnavailable { status, message }) => {
+      assert_eq!(status, 503);
+      assert_eq!(message, "<html>Service Unavailable</html>");
+    },
+    other => panic!("expected a service unavailable error, got {:?}", other),
+  }
+}
+
+#[tokio::test]
+async fn test_check_connection_network_errors() {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/data/testAuthentication"))
+    .respond_with(ResponseTemplate::new(200).set_delay(std::time::Duration::from_secs(2)))
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::builder()
+    .api_keys("api_key", "secret_api_key")
+    .base_url(server.uri())
+    .timeout(std::time::Duration::from_millis(100))
+    .build()
+    .unwrap();
+  match api.check_connection().await {
+    Err(ApiError::Network(error)) => assert!(matches!(*error, ApiError::Timeout())),
+    other => panic!("expected a network error, got {:?}", other),
+  }
+
+  let unreachable = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", format!("http://{}", unreachable)).unwrap();
+  match api.check_connection().await {
+    Err(ApiError::Network(error)) => assert!(matches!(*error, ApiError::Request(_))),
+    other => panic!("expected a network error, got {:?}", other),
+  }
+}
+
+#[tokio::test]
+async fn test_gateway_urls_and_fetch() {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/ipfs/QmYTyd2A15snZbRbWi2cbZkis45DzDdPSdzdF3wXdMEWVk/inside/a.txt"))
+    .respond_with(ResponseTemplate::new(200).set_body_string("file content"))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new("api_key", "secret_api_key").unwrap();
+  assert_eq!(
+    api.gateway_url("QmYTyd2A15snZbRbWi2cbZkis45DzDdPSdzdF3wXdMEWVk"),
+    "https://gateway.pinata.cloud/ipfs/QmYTyd2A15snZbRbWi2cbZkis45DzDdPSdzdF3wXdMEWVk"
+  );
+
+  let api = PinataApi::builder()
+    .api_keys("api_key", "secret_api_key")
+    .gateway_url(format!("{}/", server.uri()))
+    .build()
+    .unwrap();
+  let content = api.fetch("QmYTyd2A15snZbRbWi2cbZkis45DzDdPSdzdF3wXdMEWVk/inside/a.txt").await.unwrap();
+  assert_eq!(&content[..], b"file content");
+  assert_eq!(
+    api.gateway_url_with_path("QmYTyd2A15snZbRbWi2cbZkis45DzDdPSdzdF3wXdMEWVk", "/inside/a.txt"),
+    format!("{}/ipfs/QmYTyd2A15snZbRbWi2cbZkis45DzDdPSdzdF3wXdMEWVk/inside/a.txt", server.uri())
+  );
+
+  let requests = server.received_requests().await.unwrap();
+  assert!(requests[0].headers.keys().all(|name| name.as_str() != "pinata_secret_api_key"));
+}
+
+#[tokio::test]
+async fn test_cost_breakdown_by_group() {
+  let server = MockServer::start().await;
+  let row = |hash: &str, size: u64| serde_json::json!({
+    "id": "cc7f924f-7246-42ce-a1c7-f0067ac02144",
+    "ipfs_pin_hash": hash,
+    "size": size,
+    "user_id": "6176135e-fd99-4af9-a27c-23dd9d8e0461",
+    "date_pinned": "2020-04-19T15:07:36.700Z",
+    "date_unpinned": null,
+    "metadata": { "name": null, "keyvalues": null },
+    "regions": []
+  });
+  Mock::given(method("GET"))
+    .and(path("/groups"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+      {
+        "id": "group-id",
+        "name": "photos",
+        "user_id": "user-id",
+        "createdAt": "2021-01-01T00:00:00.000Z",
+        "updatedAt": "2021-01-01T00:00:00.000Z"
+      },
+      {
+        "id": "other-group-id",
+        "name": "backups",
+        "user_id": "user-id",
+        "createdAt": "2021-01-01T00:00:00.000Z",
+        "updatedAt": "2021-01-01T00:00:00.000Z"
+      }
+    ])))
+    .mount(&server)
+    .await;
+  // the same content in both groups is only counted in the first one
+  for group_id in &["group-id", "other-group-id"] {
+    Mock::given(method("GET"))
+      .and(path("/data/pinList"))
+      .and(query_param("groupId", *group_id))
+      .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+        "count": 1,
+        "rows": [row("QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH", 1_073_741_824)]
+      })))
+      .expect(1)
+      .mount(&server)
+      .await;
+  }
+  Mock::given(method("GET"))
+    .and(path("/data/pinList"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "count": 2,
+      "rows": [
+        row("QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH", 1_073_741_824),
+        row("QmWsZfQw98k9dfG1sDZB3z8YqMtxG9gYCyddgZGWq4w6Z3", 100)
+      ]
+    })))
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  match api.cost_breakdown(BreakdownKey::Group).await {
+    Err(ApiError::PriceNotConfigured()) => {},
+    other => panic!("expected the missing price to be reported, got {:?}", other),
+  }
+
+  let api = PinataApi::builder()
+    .api_keys("api_key", "secret_api_key")
+    .base_url(server.uri())
+    .price_per_gb(0.15)
+    .build()
+    .unwrap();
+  let breakdown = api.cost_breakdown(BreakdownKey::Group).await.unwrap();
+
+  assert_eq!(breakdown.len(), 2);
+  assert_eq!(breakdown["group-id"].pin_count, 1);
+  assert!((breakdown["group-id"].cost - 0.15).abs() < f64::EPSILON);
+  assert_eq!(breakdown[""].total_size, 100);
+}
+
+#[tokio::test]
+async fn test_groups() {
+  let server = MockServer::start().await;
+  let group = serde_json::json!({
+    "id": "group-id",
+    "name": "photos",
+    "user_id": "user-id",
+    "createdAt": "2021-01-01T00:00:00.000Z",
+    "updatedAt": "2021-01-01T00:00:00.000Z"
+  });
+  Mock::given(method("POST"))
+    .and(path("/groups"))
+    .and(body_json(serde_json::json!({ "name": "photos" })))
+    .respond_with(ResponseTemplate::new(200).set_body_json(group.clone()))
+    .expect(1)
+    .mount(&server)
+    .await;
+  Mock::given(method("GET"))
+    .and(path("/groups"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([group])))
+    .expect(1)
+    .mount(&server)
+    .await;
+  Mock::given(method("PUT"))
+    .and(path("/groups/group-id/cids"))
+    .and(body_json(serde_json::json!({ "cids": ["QmHash"] })))
+    .respond_with(ResponseTemplate::new(200).set_body_string("OK"))
+    .expect(1)
+    .mount(&server)
+    .await;
+  Mock::given(method("DELETE"))
+    .and(path("/groups/group-id"))
+    .respond_with(ResponseTemplate::new(200).set_body_string("OK"))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  let created = api.create_group("photos").await.unwrap();
+  assert_eq!(created.id, "group-id");
+  assert_eq!(created.user_id, Some("user-id".to_string()));
+
+  let groups = api.list_groups().await.unwrap();
+  assert_eq!(groups.len(), 1);
+  assert_eq!(groups[0].name, "photos");
+
+  api.add_cids_to_group("group-id", vec!["QmHash".to_string()]).await.unwrap();
+  api.delete_group("group-id").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_generate_and_revoke_api_key() {
+  let server = MockServer::start().await;
+  Mock::given(method("POST"))
+    .and(path("/users/generateApiKey"))
+    .and(body_json(serde_json::json!({
+      "keyName": "ci",
+      "permissions": {
+        "admin": false,
+        "endpoints": {
+          "data": { "pinList": true, "userPinnedDataTotal": false },
+          "pinning": {
+            "hashMetadata": false,
+            "hashPinPolicy": false,
+            "pinByHash": false,
+            "pinFileToIPFS": true,
+            "pinJSONToIPFS": false,
+            "pinJobs": false,
+            "unpin": false,
+            "userPinPolicy": false
+          }
+        }
+      },
+      "maxUses": 5
+    })))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "JWT": "child.jwt.token",
+      "pinata_api_key": "child_key",
+      "pinata_api_secret": "child_secret"
+    })))
+    .expect(1)
+    .mount(&server)
+    .await;
+  Mock::given(method("PUT"))
+    .and(path("/users/revokeApiKey"))
+    .and(body_json(serde_json::json!({ "apiKey": "child_key" })))
+    .respond_with(ResponseTemplate::new(200).set_body_string("Revoked"))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  let mut scopes = ApiKeyScopes::default();
+  scopes.endpoints.data.pin_list = true;
+  scopes.endpoints.pinning.pin_file_to_ipfs = true;
+
+  let key = api.generate_api_key(GenerateApiKeyRequest::new("ci", scopes).set_max_uses(5)).await.unwrap();
+  assert_eq!(key.api_key, "child_key");
+  assert_eq!(key.secret_api_key, "child_secret");
+  assert_eq!(key.jwt, "child.jwt.token");
+
+  api.revoke_api_key(&key.api_key).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_list_api_keys_reads_all_pages() {
+  let key = |id: &str, revoked: bool| serde_json::json!({
+    "id": id,
+    "name": format!("key {}", id),
+    "key": format!("key_{}", id),
+    "max_uses": if revoked { serde_json::json!(10) } else { serde_json::Value::Null },
+    "uses": 3,
+    "user_id": "user",
+    "scopes": { "admin": false, "endpoints": { "pinning": { "pinJSONToIPFS": true } } },
+    "revoked": revoked,
+    "createdAt": "2021-05-10T12:00:00.000Z",
+    "updatedAt": "2021-05-10T12:00:00.000Z"
+  });
+
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/users/apiKeys"))
+    .and(query_param("offset", "0"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "keys": [key("1", false), key("2", true)],
+      "count": 3
+    })))
+    .expect(1)
+    .mount(&server)
+    .await;
+  Mock::given(method("GET"))
+    .and(path("/users/apiKeys"))
+    .and(query_param("offset", "2"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "keys": [key("3", false)],
+      "count": 3
+    })))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  let keys = api.list_api_keys().await.unwrap();
+
+  assert_eq!(keys.iter().map(|key| key.key.as_str()).collect::<Vec<_>>(), vec!["key_1", "key_2", "key_3"]);
+  assert!(keys[1].revoked);
+  assert_eq!(keys[1].max_uses, Some(10));
+  assert_eq!(keys[0].max_uses, None);
+  assert!(keys[0].scopes.endpoints.pinning.pin_json_to_ipfs);
+  assert!(!keys[0].scopes.endpoints.pinning.unpin);
+}
+
+/// Answers pin_json requests with a hash made from the `id` of the pinned content, and a 400 for id 3
+struct PinByContentId;
+
+impl wiremock::Respond for PinByContentId {
+  fn respond(&self, request: &wiremock::Request) -> ResponseTemplate {
+    let body: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+    match body["pinataContent"]["id"].as_u64().unwrap() {
+      3 => ResponseTemplate::new(400).set_body_json(serde_json::json!({ "error": "Invalid content" })),
+      id => ResponseTemplate::new(200).set_body_json(serde_json::json!({
+        "IpfsHash": format!("QmHash{}", id),
+        "PinSize": 10,
+        "Timestamp": "2021-01-01T00:00:00.000Z"
+      })),
+    }
+  }
+}
+
+#[tokio::test]
+async fn test_pin_json_many_keeps_order() {
+  let server = MockServer::start().await;
+  Mock::given(method("POST"))
+    .and(path("/pinning/pinJSONToIPFS"))
+    .respond_with(PinByContentId)
+    .expect(20)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  let items = (0..20).map(|id| PinByJson::new(serde_json::json!({ "id": id }))).collect();
+  let results = api.pin_json_many(items, Some(4)).await;
+
+  assert_eq!(results.len(), 20);
+  for (id, result) in results.iter().enumerate() {
+    match (id, result) {
+      (3, Err(err)) => assert_eq!(err.status_code(), Some(400)),
+      (_, Ok(pinned)) => assert_eq!(pinned.ipfs_hash, format!("QmHash{}", id)),
+      (_, other) => panic!("unexpected result for {}: {:?}", id, other.as_ref().map(|pinned| &pinned.ipfs_hash)),
+    }
+  }
+}
+
+#[tokio::test]
+async fn test_pin_json_with_group_id() {
+  let server = MockServer::start().await;
+  Mock::given(method("POST"))
+    .and(path("/pinning/pinJSONToIPFS"))
+    .and(body_json(serde_json::json!({
+      "pinataContent": { "name": "user" },
+      "pinataOptions": {
+        "groupId": "group-id"
+      }
+    })))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "IpfsHash": "QmHash",
+      "PinSize": 16,
+      "Timestamp": "2021-01-01T00:00:00.000Z"
+    })))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  let mut json_data = HashMap::new();
+  json_data.insert("name", "user");
+  let options = PinOptions { group_id: Some("group-id".to_string()), ..Default::default() };
+
+  let result = api.pin_json(PinByJson::new(json_data).set_options(options)).await.unwrap();
+  assert_eq!(result.ipfs_hash, "QmHash");
+}
+
+#[tokio::test]
+async fn test_get_pin_job() {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/pinning/pinJobs"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "count": 2,
+      "rows": [
+        {
+          "id": "job-a",
+          "ipfs_pin_hash": "QmHashA",
+          "date_queued": "2021-01-01T00:00:00.000Z",
+          "status": "searching",
+          "name": null,
+          "keyvalues": null,
+          "host_nodes": null,
+          "pin_policy": null
+        },
+        {
+          "id": "job-b",
+          "ipfs_pin_hash": "QmHashB",
+          "date_queued": "2021-01-01T00:00:00.000Z",
+          "status": "prechecking",
+          "name": "b",
+          "keyvalues": null,
+          "host_nodes": null,
+          "pin_policy": null
+        }
+      ]
+    })))
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  let job = api.get_pin_job("job-b").await.unwrap();
+  assert_eq!(job.ipfs_pin_hash, "QmHashB");
+
+  match api.get_pin_job("job-c").await {
+    Err(ApiError::NotFound(id)) => assert_eq!(id, "job-c"),
+    other => panic!("expected NotFound, got {:?}", other.map(|job| job.id)),
+  }
+}
+
+fn pin_jobs_page(statuses: &[&str]) -> ResponseTemplate {
+  let rows: Vec<_> = statuses.iter().map(|status| serde_json::json!({
+    "id": "job-a",
+    "ipfs_pin_hash": "QmHashA",
+    "date_queued": "2021-01-01T00:00:00.000Z",
+    "status": status,
+    "name": null,
+    "keyvalues": null,
+    "host_nodes": null,
+    "pin_policy": null
+  })).collect();
+
+  ResponseTemplate::new(200).set_body_json(serde_json::json!({ "count": rows.len(), "rows": rows }))
+}
+
+#[tokio::test]
+async fn test_get_pin_jobs_stream_pages_with_filters() {
+  let job = |id: &str| serde_json::json!({
+    "id": id,
+    "ipfs_pin_hash": "QmHashA",
+    "date_queued": "2021-01-01T00:00:00.000Z",
+    "status": "searching",
+    "name": null,
+    "keyvalues": null,
+    "host_nodes": null,
+    "pin_policy": null
+  });
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/pinning/pinJobs"))
+    .and(query_param("status", "searching"))
+    .and(query_param("limit", "2"))
+    .and(query_param("offset", "0"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "count": 3,
+      "rows": [job("job-a"), job("job-b")]
+    })))
+    .expect(1)
+    .mount(&server)
+    .await;
+  Mock::given(method("GET"))
+    .and(path("/pinning/pinJobs"))
+    .and(query_param("status", "searching"))
+    .and(query_param("limit", "2"))
+    .and(query_param("offset", "2"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "count": 3,
+      "rows": [job("job-c")]
+    })))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  let filters = PinJobsFilterBuilder::default()
+    .set_status(JobStatus::Searching)
+    .set_limit(2u16)
+    .build()
+    .unwrap();
+  let jobs: Vec<PinJob> = api.get_pin_jobs_stream(filters).try_collect().await.unwrap();
+
+  let ids: Vec<_> = jobs.iter().map(|job| job.id.as_str()).collect();
+  assert_eq!(ids, vec!["job-a", "job-b", "job-c"]);
+}
+
+#[tokio::test]
+async fn test_cancel_pin_job() {
+  let job = |id: &str, status: &str, hash: &str| serde_json::json!({
+    "id": id,
+    "ipfs_pin_hash": hash,
+    "date_queued": "2021-01-01T00:00:00.000Z",
+    "status": status,
+    "name": null,
+    "keyvalues": null,
+    "host_nodes": null,
+    "pin_policy": null
+  });
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/pinning/pinJobs"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "count": 3,
+      "rows": [
+        job("job-searching", "searching", "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH"),
+        job("job-expired", "expired", "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH"),
+        job("job-duplicate", "searching", "QmPinned")
+      ]
+    })))
+    .mount(&server)
+    .await;
+  Mock::given(method("GET"))
+    .and(path("/data/pinList"))
+    .and(query_param("hashContains", "QmPinned"))
+    .respond_with(pin_list_page(1, &["Pinned"]))
+    .mount(&server)
+    .await;
+  Mock::given(method("GET"))
+    .and(path("/data/pinList"))
+    .respond_with(pin_list_page(0, &[]))
+    .mount(&server)
+    .await;
+  Mock::given(method("DELETE"))
+    .and(path("/pinning/unpin/QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH"))
+    .respond_with(ResponseTemplate::new(200))
+    .expect(1)
+    .mount(&server)
+    .await;
+  Mock::given(method("DELETE"))
+    .and(path("/pinning/unpin/QmPinned"))
+    .respond_with(ResponseTemplate::new(200))
+    .expect(0)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  api.cancel_pin_job("job-searching").await.unwrap();
+
+  match api.cancel_pin_job("job-expired").await {
+    Err(ApiError::PinJobFinished { status }) => assert_eq!(status, JobStatus::Expired),
+    other => panic!("expected the expired job to be finished, got {:?}", other),
+  }
+  match api.cancel_pin_job("job-pinned").await {
+    Err(ApiError::NotFound(id)) => assert_eq!(id, "job-pinned"),
+    other => panic!("expected the job to be missing, got {:?}", other),
+  }
+  match api.cancel_pin_job("job-duplicate").await {
+    Err(ApiError::AlreadyPinned { cid }) => assert_eq!(cid, "QmPinned"),
+    other => panic!("expected the pinned CID to be kept, got {:?}", other),
+  }
+}
+
+#[tokio::test]
+async fn test_wait_for_pin_until_job_leaves_queue() {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/pinning/pinJobs"))
+    .respond_with(pin_jobs_page(&["searching"]))
+    .up_to_n_times(1)
+    .mount(&server)
+    .await;
+  Mock::given(method("GET"))
+    .and(path("/pinning/pinJobs"))
+    .respond_with(pin_jobs_page(&["retrieving"]))
+    .up_to_n_times(1)
+    .mount(&server)
+    .await;
+  Mock::given(method("GET"))
+    .and(path("/pinning/pinJobs"))
+    .respond_with(pin_jobs_page(&[]))
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  let job = api.wait_for_pin("job-a", Duration::from_millis(10), Duration::from_secs(5)).await.unwrap();
+  assert_eq!(job.status, JobStatus::Retrieving);
+}
+
+#[tokio::test]
+async fn test_wait_for_pin_failures_and_timeout() {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/pinning/pinJobs"))
+    .respond_with(pin_jobs_page(&["expired"]))
+    .up_to_n_times(1)
+    .mount(&server)
+    .await;
+  Mock::given(method("GET"))
+    .and(path("/pinning/pinJobs"))
+    .respond_with(pin_jobs_page(&["searching"]))
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  match api.wait_for_pin("job-a", Duration::from_millis(10), Duration::from_secs(5)).await {
+    Err(ApiError::PinFailed { status }) => assert_eq!(status, JobStatus::Expired),
+    other => panic!("expected PinFailed, got {:?}", other),
+  }
+
+  match api.wait_for_pin("job-a", Duration::from_millis(10), Duration::from_millis(100)).await {
+    Err(ApiError::Timeout()) => (),
+    other => panic!("expected Timeout, got {:?}", other),
+  }
+}
+
+#[tokio::test]
+async fn test_unpin_many_reports_each_hash() {
+  let good = "QmbFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1AwQH";
+  let bad = "bafybeihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku";
+  let server = MockServer::start().await;
+  Mock::given(method("DELETE"))
+    .and(path(format!("/pinning/unpin/{}", good)))
+    .respond_with(ResponseTemplate::new(200).set_body_string("OK"))
+    .expect(1)
+    .mount(&server)
+    .await;
+  // the first hash completes last, results are still returned in the order of the hashes
+  Mock::given(method("DELETE"))
+    .and(path(format!("/pinning/unpin/{}", bad)))
+    .respond_with(
+      ResponseTemplate::new(400)
+        .set_body_json(serde_json::json!({ "error": "Invalid hash" }))
+        .set_delay(std::time::Duration::from_millis(200))
+    )
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  let hashes = vec![bad.to_string(), good.to_string()];
+  let results = api.unpin_many(&hashes, Some(2)).await;
+
+  let order: Vec<&str> = results.iter().map(|(hash, _)| hash.as_str()).collect();
+  assert_eq!(order, vec![bad, good]);
+  assert!(results[1].1.is_ok());
+  match &results[0].1 {
+    Err(ApiError::Http { status, message, .. }) => {
+      assert_eq!(*status, 400);
+      assert_eq!(message, "Invalid hash");
+    },
+    other => panic!("expected Http error, got {:?}", other),
+  }
+}
+
+#[tokio::test]
+async fn test_change_hash_metadata_many_reports_each_hash() {
+  let good = "QmbFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1AwQH";
+  let bad = "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH";
+  let server = MockServer::start().await;
+  Mock::given(method("PUT"))
+    .and(path("/pinning/hashMetadata"))
+    .and(body_json(serde_json::json!({ "ipfsPinHash": good, "keyvalues": { "version": 2 } })))
+    .respond_with(ResponseTemplate::new(200).set_body_string("OK"))
+    .expect(1)
+    .mount(&server)
+    .await;
+  Mock::given(method("PUT"))
+    .and(path("/pinning/hashMetadata"))
+    .and(body_json(serde_json::json!({ "ipfsPinHash": bad, "keyvalues": { "version": 2 } })))
+    .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({ "error": "Invalid hash" })))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  let changes = vec![
+    ChangePinMetadata::new(good).add_keyvalue("version", MetadataValue::Integer(2)),
+    ChangePinMetadata::new(bad).add_keyvalue("version", MetadataValue::Integer(2)),
+  ];
+  // a concurrency of 0 still runs the changes, one at a time
+  let results = api.change_hash_metadata_many(changes, Some(0)).await;
+
+  let order: Vec<&str> = results.iter().map(|(hash, _)| hash.as_str()).collect();
+  assert_eq!(order, vec![good, bad]);
+  assert!(results[0].1.is_ok());
+  assert_eq!(results[1].1.as_ref().unwrap_err().status_code(), Some(400));
+}
+
+#[tokio::test]
+async fn test_set_user_pin_policy() {
+  let server = MockServer::start().await;
+  Mock::given(method("PUT"))
+    .and(path("/pinning/userPinPolicy"))
+    .and(body_json(serde_json::json!({
+      "newPinPolicy": {
+        "regions": [
+          { "id": "FRA1", "desiredReplicationCount": 1 },
+          { "id": "NYC1", "desiredReplicationCount": 2 }
+        ]
+      },
+      "migratePreviousPins": true
+    })))
+    .respond_with(ResponseTemplate::new(200).set_body_string("OK"))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  let policy = PinPolicy {
+    regions: vec![
+      RegionPolicy { id: Region::FRA1, desired_replication_count: 1 },
+      RegionPolicy { id: Region::NYC1, desired_replication_count: 2 },
+    ],
+  };
+
+  api.set_user_pin_policy(policy, true).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_default_pin_options_fallback_and_override() {
+  let server = MockServer::start().await;
+  let pinned = serde_json::json!({
+    "IpfsHash": "QmHash",
+    "PinSize": 16,
+    "Timestamp": "2021-01-01T00:00:00.000Z"
+  });
+  Mock::given(method("POST"))
+    .and(path("/pinning/pinJSONToIPFS"))
+    .and(body_json(serde_json::json!({
+      "pinataContent": { "name": "default" },
+      "pinataOptions": { "cidVersion": 1 }
+    })))
+    .respond_with(ResponseTemplate::new(200).set_body_json(pinned.clone()))
+    .expect(1)
+    .mount(&server)
+    .await;
+  Mock::given(method("POST"))
+    .and(path("/pinning/pinJSONToIPFS"))
+    .and(body_json(serde_json::json!({
+      "pinataContent": { "name": "override" },
+      "pinataOptions": { "wrapWithDirectory": true }
+    })))
+    .respond_with(ResponseTemplate::new(200).set_body_json(pinned))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::builder()
+    .api_keys("api_key", "secret_api_key")
+    .base_url(server.uri())
+    .default_pin_options(PinOptions::new().cid_version(1))
+    .build()
+    .unwrap();
+
+  let mut json_data = HashMap::new();
+  json_data.insert("name", "default");
+  api.pin_json(PinByJson::new(json_data)).await.unwrap();
+
+  let mut json_data = HashMap::new();
+  json_data.insert("name", "override");
+  api.pin_json(
+    PinByJson::new(json_data).set_options(PinOptions::new().wrap_with_directory(true))
+  ).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_pin_stream() {
+  let server = MockServer::start().await;
+  Mock::given(method("POST"))
+    .and(path("/pinning/pinFileToIPFS"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "IpfsHash": "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH",
+      "PinSize": 19,
+      "Timestamp": "2020-04-19T15:07:36.700Z"
+    })))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  let mut keyvalues = HashMap::new();
+  keyvalues.insert("source".to_string(), MetadataValue::String("socket".into()));
+  let metadata = PinMetadata { name: Some("streamed".to_string()), keyvalues };
+  let reader = std::io::Cursor::new(b"content from reader".to_vec());
+
+  api.pin_stream("streamed.json", reader, Some(metadata), Some(PinOptions::new().cid_version(1)))
+    .await
+    .unwrap();
+
+  let requests = server.received_requests().await.unwrap();
+  let body = String::from_utf8_lossy(&requests[0].body);
+  assert!(body.contains("filename=\"streamed.json\""));
+  assert!(body.to_lowercase().contains("content-type: application/json"));
+  assert!(body.contains("content from reader"));
+  assert!(body.contains(r#"{"name":"streamed","keyvalues":{"source":"socket"}}"#));
+  assert!(body.contains(r#"{"cidVersion":1}"#));
+}
+
+#[tokio::test]
+async fn test_unexpected_pin_list_shape_keeps_body() {
+  let row = serde_json::json!({
+    "id": "a",
+    "ipfs_pin_hash": "Qma",
+    "size": "10 bytes",
+    "user_id": "user",
+    "date_pinned": "2020-04-19T15:07:36.700Z",
+    "date_unpinned": null,
+    "metadata": { "name": null, "keyvalues": null },
+    "regions": []
+  });
+  let api = get_canned_api("/data/pinList", serde_json::json!({ "count": 1, "rows": [row] }));
+
+  match api.get_pin_list(PinListFilter::default()).await {
+    Err(ApiError::Deserialize { target, body, source }) => {
+      assert_eq!(target, "pinata_sdk::api::data::PinList");
+      assert!(body.contains(r#""size":"10 bytes""#));
+      assert!(source.to_string().contains("invalid type"));
+    },
+    other => panic!("expected a deserialize error, got {:?}", other.map(|list| list.count)),
+  }
+}
+
+#[tokio::test]
+async fn test_non_json_success_body_is_unexpected_response() {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/data/userPinnedDataTotal"))
+    .respond_with(ResponseTemplate::new(200).set_body_string("<html>Checking your browser</html>"))
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+
+  match api.get_total_user_pinned_data().await {
+    Err(ApiError::UnexpectedResponse { status, body }) => {
+      assert_eq!(status, 200);
+      assert_eq!(body, "<html>Checking your browser</html>");
+    },
+    other => panic!("expected an unexpected response error, got {:?}", other),
+  }
+}
+
+#[tokio::test]
+async fn test_get_hash_metadata() {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/data/pinList"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "count": 1,
+      "rows": [{
+        "id": "cc7f924f-7246-42ce-a1c7-f0067ac02144",
+        "ipfs_pin_hash": "Qme5npQ51psDHssScnXqeKoUvA7UizxhRCfZi6ewMiiHcn",
+        "size": 36,
+        "user_id": "6176135e-fd99-4af9-a27c-23dd9d8e0461",
+        "date_pinned": "2020-04-19T15:07:36.700Z",
+        "date_unpinned": null,
+        "metadata": { "name": "report", "keyvalues": { "year": "2020" } },
+        "regions": []
+      }]
+    })))
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  let metadata = api.get_hash_metadata("Qme5npQ51psDHssScnXqeKoUvA7UizxhRCfZi6ewMiiHcn").await.unwrap();
+  assert_eq!(metadata.name, Some("report".to_string()));
+  assert_eq!(metadata.keyvalues["year"], MetadataValue::String("2020".to_string()));
+
+  match api.get_hash_metadata("QmMissing").await {
+    Err(ApiError::NotFound(_)) => (),
+    other => panic!("expected NotFound, got {:?}", other),
+  }
+}
+
+#[tokio::test]
+async fn test_pin_json_if_absent() {
+  let pinned_cid = compute_cid(br#"{"report":"2020"}"#, &CidConfig::default()).unwrap();
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/data/pinList"))
+    .and(query_param("hashContains", pinned_cid.as_str()))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "count": 1,
+      "rows": [{
+        "id": "cc7f924f-7246-42ce-a1c7-f0067ac02144",
+        "ipfs_pin_hash": pinned_cid,
+        "size": 17,
+        "user_id": "6176135e-fd99-4af9-a27c-23dd9d8e0461",
+        "date_pinned": "2020-04-19T15:07:36.700Z",
+        "date_unpinned": null,
+        "metadata": { "name": null, "keyvalues": null },
+        "regions": []
+      }]
+    })))
+    .mount(&server)
+    .await;
+  Mock::given(method("GET"))
+    .and(path("/data/pinList"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "count": 0, "rows": [] })))
+    .mount(&server)
+    .await;
+  Mock::given(method("POST"))
+    .and(path("/pinning/pinJSONToIPFS"))
+    .and(body_json(serde_json::json!({ "pinataContent": { "report": "2021" } })))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "IpfsHash": "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH",
+      "PinSize": 25,
+      "Timestamp": "2020-04-19T15:07:36.700Z"
+    })))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  match api.pin_json_if_absent(PinByJson::new(serde_json::json!({ "report": "2020" }))).await.unwrap() {
+    PinOutcome::AlreadyPinned(cid) => assert_eq!(cid, pinned_cid),
+    other => panic!("expected AlreadyPinned, got {:?}", other),
+  }
+  match api.pin_json_if_absent(PinByJson::new(serde_json::json!({ "report": "2021" }))).await.unwrap() {
+    PinOutcome::NewlyPinned(pinned) => assert_eq!(pinned.ipfs_hash, "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH"),
+    other => panic!("expected NewlyPinned, got {:?}", other),
+  }
+}
+
+#[tokio::test]
+async fn test_pin_json_full() {
+  let server = MockServer::start().await;
+  Mock::given(method("POST"))
+    .and(path("/pinning/pinJSONToIPFS"))
+    .and(body_json(serde_json::json!({
+      "pinataContent": { "report": "2021" },
+      "pinataMetadata": { "name": "report", "keyvalues": { "year": 2021, "team": "finance" } },
+      "pinataOptions": { "cidVersion": 1, "groupId": "group-id" }
+    })))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "IpfsHash": "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH",
+      "PinSize": 25,
+      "Timestamp": "2020-04-19T15:07:36.700Z"
+    })))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let mut keyvalues = HashMap::new();
+  keyvalues.insert("year".to_string(), MetadataValue::Integer(2021));
+  keyvalues.insert("team".to_string(), MetadataValue::String("finance".to_string()));
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  let pinned = api.pin_json_full(serde_json::json!({ "report": "2021" }), "report", keyvalues.clone(), "group-id", 1)
+    .await
+    .unwrap();
+  assert_eq!(pinned.ipfs_hash, "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH");
+
+  match api.pin_json_full(serde_json::json!({}), "report", keyvalues, "group-id", 2).await {
+    Err(ApiError::InvalidCidVersion(2)) => {},
+    other => panic!("expected an invalid CID version error, got {:?}", other),
+  }
+}
+
+#[tokio::test]
+async fn test_last_rate_limit_is_recorded() {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/data/testAuthentication"))
+    .respond_with(ResponseTemplate::new(200)
+      .insert_header("x-ratelimit-limit", "180")
+      .insert_header("x-ratelimit-remaining", "179")
+      .insert_header("x-ratelimit-reset", "1600000000"))
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  assert_eq!(api.last_rate_limit(), None);
+
+  api.test_authentication().await.unwrap();
+  assert_eq!(api.last_rate_limit(), Some(RateLimitInfo {
+    limit: Some(180),
+    remaining: Some(179),
+    reset: Some(1600000000),
+  }));
+}
+
+#[tokio::test]
+async fn test_typed_metadata_values_round_trip() {
+  let server = MockServer::start().await;
+  Mock::given(method("PUT"))
+    .and(path("/pinning/hashMetadata"))
+    .and(body_json(serde_json::json!({
+      "ipfsPinHash": "QmHash",
+      "keyvalues": { "count": 3, "ratio": 0.5, "public": false }
+    })))
+    .respond_with(ResponseTemplate::new(200))
+    .expect(1)
+    .mount(&server)
+    .await;
+  Mock::given(method("GET"))
+    .and(path("/data/pinList"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "count": 1,
+      "rows": [{
+        "id": "id",
+        "ipfs_pin_hash": "QmHash",
+        "size": 10,
+        "user_id": "user",
+        "date_pinned": "2020-04-19T15:07:36.700Z",
+        "date_unpinned": null,
+        "metadata": { "name": null, "keyvalues": { "count": 3, "ratio": 0.5, "public": false } },
+        "regions": []
+      }]
+    })))
+    .mount(&server)
+    .await;
+
+  let mut keyvalues = HashMap::new();
+  keyvalues.insert("count".to_string(), MetadataValue::Integer(3));
+  keyvalues.insert("ratio".to_string(), MetadataValue::Float(0.5));
+  keyvalues.insert("public".to_string(), MetadataValue::Bool(false));
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  api.change_hash_metadata(ChangePinMetadata {
+    ipfs_pin_hash: "QmHash".to_string(),
+    metadata: PinMetadata { name: None, keyvalues: keyvalues.clone() },
+  }).await.unwrap();
+
+  let pins = api.get_pin_list(PinListFilter::default()).await.unwrap();
+  assert_eq!(pins.rows[0].metadata.keyvalues, Some(keyvalues));
+}
+
+#[tokio::test]
+async fn test_too_many_keyvalues_are_rejected_before_sending() {
+  let server = MockServer::start().await;
+  Mock::given(method("POST"))
+    .respond_with(ResponseTemplate::new(200))
+    .expect(0)
+    .mount(&server)
+    .await;
+
+  let keyvalues: HashMap<_, _> = (0..11)
+    .map(|index| (format!("key{}", index), MetadataValue::Integer(index)))
+    .collect();
+  assert!(PinByJson::new("data").try_set_metadata(keyvalues.clone()).is_err());
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  match api.pin_json(PinByJson::new("data").set_metadata(keyvalues)).await {
+    Err(ApiError::TooManyKeyValues { count: 11 }) => (),
+    other => panic!("expected TooManyKeyValues, got {:?}", other),
+  }
+}
+
+#[tokio::test]
+async fn test_pin_name_is_trimmed_and_limited() {
+  let server = MockServer::start().await;
+  Mock::given(method("POST"))
+    .and(path("/pinning/pinJSONToIPFS"))
+    .and(body_json(serde_json::json!({
+      "pinataContent": "data",
+      "pinataMetadata": { "name": "report", "keyvalues": {} }
+    })))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "IpfsHash": "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH",
+      "PinSize": 6,
+      "Timestamp": "2020-04-19T15:07:36.700Z"
+    })))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  api.pin_json(PinByJson::new("data").set_name("  report \t")).await.unwrap();
+
+  match api.pin_json(PinByJson::new("data").set_metadata_with_name("x".repeat(300), HashMap::new())).await {
+    Err(ApiError::NameTooLong { len: 300, max: 255 }) => (),
+    other => panic!("expected NameTooLong, got {:?}", other),
+  }
+}
+
+#[tokio::test]
+async fn test_malformed_host_node_is_rejected_before_sending() {
+  let server = MockServer::start().await;
+  Mock::given(method("POST"))
+    .respond_with(ResponseTemplate::new(200))
+    .expect(0)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  let pin = PinByHash::new("QmbFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1AwQH")
+    .add_host_node("1.2.3.4:4001");
+  match api.pin_by_hash(pin).await {
+    Err(ApiError::InvalidMultiaddr(addr)) => assert_eq!(addr, "1.2.3.4:4001"),
+    other => panic!("expected InvalidMultiaddr, got {:?}", other),
+  }
+}
+
+#[tokio::test]
+async fn test_default_headers_are_sent_with_auth_headers() {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/data/testAuthentication"))
+    .and(header("x-tenant-id", "acme"))
+    .and(header("pinata_api_key", "api_key"))
+    .respond_with(ResponseTemplate::new(200))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::builder()
+    .api_keys("api_key", "secret_api_key")
+    .base_url(server.uri())
+    .default_header("x-tenant-id", "acme")
+    .default_header("pinata_api_key", "not_replaced")
+    .build()
+    .unwrap();
+  api.test_authentication().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_user_agent_identifies_sdk() {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/data/testAuthentication"))
+    .and(header("user-agent", format!("pinata-sdk-rust/{}", env!("CARGO_PKG_VERSION")).as_str()))
+    .respond_with(ResponseTemplate::new(200))
+    .expect(1)
+    .mount(&server)
+    .await;
+  Mock::given(method("GET"))
+    .and(path("/data/testAuthentication"))
+    .and(header("user-agent", "my-app/2.0"))
+    .respond_with(ResponseTemplate::new(200))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  api.test_authentication().await.unwrap();
+
+  let api = PinataApi::builder()
+    .api_keys("api_key", "secret_api_key")
+    .base_url(server.uri())
+    .user_agent("my-app/2.0")
+    .build()
+    .unwrap();
+  api.test_authentication().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_redirects_to_another_origin_are_not_followed() {
+  let server = MockServer::start().await;
+  let other_origin = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/data/testAuthentication"))
+    .respond_with(ResponseTemplate::new(302).insert_header("location", format!("{}/captured", other_origin.uri()).as_str()))
+    .mount(&server)
+    .await;
+  Mock::given(method("GET"))
+    .and(path("/moved"))
+    .respond_with(ResponseTemplate::new(302).insert_header("location", "/moved/here"))
+    .mount(&server)
+    .await;
+  Mock::given(method("GET"))
+    .and(path("/moved/here"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+    .mount(&server)
+    .await;
+  Mock::given(method("GET"))
+    .and(path("/captured"))
+    .respond_with(ResponseTemplate::new(200))
+    .mount(&other_origin)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  assert!(api.test_authentication().await.is_err());
+  assert!(other_origin.received_requests().await.unwrap().is_empty());
+  api.request::<serde_json::Value>(Method::GET, "/moved", None, None).await.unwrap();
+
+  let api = PinataApi::builder()
+    .api_keys("api_key", "secret_api_key")
+    .base_url(server.uri())
+    .redirect(reqwest::redirect::Policy::limited(5))
+    .build()
+    .unwrap();
+  api.test_authentication().await.unwrap();
+  assert_eq!(other_origin.received_requests().await.unwrap().len(), 1);
+}
+
+#[cfg(feature = "gzip")]
+#[tokio::test]
+async fn test_gzip_can_be_disabled() {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/data/testAuthentication"))
+    .respond_with(ResponseTemplate::new(200))
+    .expect(2)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  api.test_authentication().await.unwrap();
+
+  let api = PinataApi::builder()
+    .api_keys("api_key", "secret_api_key")
+    .base_url(server.uri())
+    .gzip(false)
+    .build()
+    .unwrap();
+  api.test_authentication().await.unwrap();
+
+  let requests = server.received_requests().await.unwrap();
+  let accept_encoding = |request: &wiremock::Request| request.headers.get(&"accept-encoding".into())
+    .map(|values| values.iter().any(|value| value.as_str().contains("gzip")))
+    .unwrap_or(false);
+  assert!(accept_encoding(&requests[0]));
+  assert!(!accept_encoding(&requests[1]));
+}
+
+#[tokio::test]
+async fn test_requests_are_sent_through_proxy() {
+  let proxy = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/data/testAuthentication"))
+    .respond_with(ResponseTemplate::new(200))
+    .expect(1)
+    .mount(&proxy)
+    .await;
+
+  let api = PinataApi::builder()
+    .api_keys("api_key", "secret_api_key")
+    .base_url("http://pinata.invalid")
+    .proxy(reqwest::Proxy::http(proxy.uri()).unwrap())
+    .build()
+    .unwrap();
+  api.test_authentication().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_missing_file_error_keeps_source() {
+  use std::error::Error;
+
+  let api = PinataApi::new("api_key", "secret_api_key").unwrap();
+  let err = api.pin_file(PinByFile::new("./missing-file.txt")).await.unwrap_err();
+
+  match &err {
+    ApiError::Io { path: Some(path), .. } => assert_eq!(path, "./missing-file.txt"),
+    other => panic!("expected an io error, got {:?}", other),
+  }
+  assert!(err.to_string().starts_with("Failed to read ./missing-file.txt: "));
+
+  let source = err.source().unwrap().downcast_ref::<std::io::Error>().unwrap();
+  assert_eq!(source.kind(), std::io::ErrorKind::NotFound);
+}
+
+fn pin_list_page(count: u64, ids: &[&str]) -> ResponseTemplate {
+  let rows: Vec<_> = ids.iter().map(|id| serde_json::json!({
+    "id": id,
+    "ipfs_pin_hash": format!("Qm{}", id),
+    "size": 10,
+    "user_id": "user",
+    "date_pinned": "2020-04-19T15:07:36.700Z",
+    "date_unpinned": null,
+    "metadata": { "name": null, "keyvalues": null },
+    "regions": []
+  })).collect();
+
+  ResponseTemplate::new(200).set_body_json(serde_json::json!({ "count": count, "rows": rows }))
+}
+
+#[tokio::test]
+async fn test_get_all_pins_rereads_pin_list_changed_mid_walk() {
+  let server = MockServer::start().await;
+  // First walk: a pin is added between the two pages, shifting "b" onto the second page
+  Mock::given(method("GET"))
+    .and(path("/data/pinList"))
+    .and(query_param("pageOffset", "0"))
+    .respond_with(pin_list_page(3, &["a", "b"]))
+    .up_to_n_times(1)
+    .mount(&server)
+    .await;
+  Mock::given(method("GET"))
+    .and(path("/data/pinList"))
+    .and(query_param("pageOffset", "2"))
+    .respond_with(pin_list_page(4, &["b", "c"]))
+    .mount(&server)
+    .await;
+  // Second walk: the new pin is first in the list
+  Mock::given(method("GET"))
+    .and(path("/data/pinList"))
+    .and(query_param("pageOffset", "0"))
+    .respond_with(pin_list_page(4, &["new", "a"]))
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  let filters = PinListFilterBuilder::default().set_page_limit(2).build().unwrap();
+  let pins = api.get_all_pins(filters).await.unwrap();
+
+  let ids: Vec<_> = pins.iter().map(|pin| pin.id.as_str()).collect();
+  assert_eq!(ids, vec!["a", "b", "c", "new"]);
+}
+
+#[cfg(feature = "blocking")]
+#[test]
+fn test_blocking_api() {
+  let runtime = tokio::runtime::Runtime::new().unwrap();
+  let server = runtime.block_on(async {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+      .and(path("/data/testAuthentication"))
+      .respond_with(ResponseTemplate::new(200))
+      .expect(1)
+      .mount(&server)
+      .await;
+    Mock::given(method("POST"))
+      .and(path("/pinning/pinJSONToIPFS"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+        "IpfsHash": "QmHash",
+        "PinSize": 16,
+        "Timestamp": "2021-01-01T00:00:00.000Z"
+      })))
+      .expect(1)
+      .mount(&server)
+      .await;
+    server
+  });
+
+  let api = blocking::PinataApi::from_async(
+    PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap()
+  ).unwrap();
+  api.test_authentication().unwrap();
+
+  let mut json_data = HashMap::new();
+  json_data.insert("name", "user");
+  let pinned = api.pin_json(PinByJson::new(json_data)).unwrap();
+  assert_eq!(pinned.ipfs_hash, "QmHash");
+
+  runtime.block_on(server.verify());
+}
+
+#[cfg(feature = "blocking")]
+#[tokio::test]
+async fn test_blocking_api_within_async_runtime() {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/data/testAuthentication"))
+    .respond_with(ResponseTemplate::new(200))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = blocking::PinataApi::from_async(
+    PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap()
+  ).unwrap();
+  api.test_authentication().unwrap();
+  drop(api);
+}
+
+#[tokio::test]
+async fn test_unpin_by_filter_unpins_matching_pins() {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/data/pinList"))
+    .and(query_param("status", "pinned"))
+    .and(query_param("hashContains", "Qm"))
+    .respond_with(pin_list_page(3, &[
+      "bFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1AwQH",
+      "YW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH",
+      "broken",
+    ]))
+    .expect(1)
+    .mount(&server)
+    .await;
+  Mock::given(method("DELETE"))
+    .respond_with(ResponseTemplate::new(200))
+    .expect(2)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  match api.unpin_by_filter(PinListFilter::default()).await {
+    Err(ApiError::EmptyFilter()) => (),
+    other => panic!("expected EmptyFilter, got {:?}", other),
+  }
+
+  let filters = PinListFilterBuilder::default().set_hash_contains("Qm".to_string()).build().unwrap();
+  let summary = api.unpin_by_filter(filters).await.unwrap();
+  assert_eq!(summary.unpinned, 2);
+  assert_eq!(summary.failed.len(), 1);
+  assert_eq!(summary.failed[0].0, "Qmbroken");
+}
+
+#[tokio::test]
+async fn test_try_unpin_reports_whether_content_was_pinned() {
+  let server = MockServer::start().await;
+  Mock::given(method("DELETE"))
+    .and(path("/pinning/unpin/QmbFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1AwQH"))
+    .respond_with(ResponseTemplate::new(200))
+    .mount(&server)
+    .await;
+  Mock::given(method("DELETE"))
+    .and(path("/pinning/unpin/QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH"))
+    .respond_with(ResponseTemplate::new(400).set_body_json(serde_json::json!({
+      "error": { "reason": "CURRENT_USER_HAS_NOT_PINNED_CID", "details": "The current user has not pinned the cid" }
+    })))
+    .mount(&server)
+    .await;
+  Mock::given(method("DELETE"))
+    .and(path("/pinning/unpin/bafybeihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku"))
+    .respond_with(ResponseTemplate::new(500))
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::builder()
+    .api_keys("api_key", "secret_api_key")
+    .base_url(server.uri())
+    .retry_base_delay(std::time::Duration::from_millis(1))
+    .build()
+    .unwrap();
+  assert!(api.try_unpin("QmbFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1AwQH").await.unwrap());
+  assert!(!api.try_unpin("QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH").await.unwrap());
+  // no mock matches, so the server responds with a 404
+  assert!(!api.try_unpin("QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n").await.unwrap());
+  let error = api.try_unpin("bafybeihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku").await.unwrap_err();
+  assert_eq!(error.status_code(), Some(500));
+}
+
+#[tokio::test]
+async fn test_list_and_restrict_gateways() {
+  let server = MockServer::start().await;
+  Mock::given(method("GET"))
+    .and(path("/v3/ingress/gateways"))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+      "rows": [{
+        "id": "gw-1",
+        "domain": "example",
+        "restrict": false,
+        "customDomains": [{ "id": "cd-1", "domain": "ipfs.example.com", "domainStatus": "active" }],
+        "createdAt": "2021-05-10T12:00:00.000Z"
+      }]
+    })))
+    .expect(1)
+    .mount(&server)
+    .await;
+  Mock::given(method("PUT"))
+    .and(path("/v3/ingress/gateways/example"))
+    .and(body_json(serde_json::json!({ "restrict": true })))
+    .respond_with(ResponseTemplate::new(200))
+    .expect(1)
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  let gateways = api.list_gateways().await.unwrap();
+  assert_eq!(gateways.len(), 1);
+  assert_eq!(gateways[0].domain, "example");
+  assert!(!gateways[0].restricted);
+  assert_eq!(gateways[0].custom_domains[0].domain, "ipfs.example.com");
+
+  api.update_gateway_access(&gateways[0].domain, true).await.unwrap();
+}
+
+#[cfg(feature = "tracing")]
+type RecordedFields = Vec<(String, String)>;
+
+/// Subscriber keeping the name and recorded fields of every span, and the fields of every event
+#[cfg(feature = "tracing")]
+#[derive(Clone, Default)]
+struct SpanRecorder {
+  spans: std::sync::Arc<Mutex<Vec<(String, RecordedFields)>>>,
+  events: std::sync::Arc<Mutex<RecordedFields>>,
+}
+
+#[cfg(feature = "tracing")]
+impl SpanRecorder {
+  fn field(&self, span: &str, field: &str) -> Option<String> {
+    self.spans.lock().unwrap().iter()
+      .filter(|(name, _)| name == span)
+      .flat_map(|(_, fields)| fields.iter())
+      .find(|(name, _)| name == field)
+      .map(|(_, value)| value.clone())
+  }
+}
+
+#[cfg(feature = "tracing")]
+struct FieldVisitor<'a>(&'a mut RecordedFields);
+
+#[cfg(feature = "tracing")]
+impl tracing::field::Visit for FieldVisitor<'_> {
+  fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+    self.0.push((field.name().to_string(), format!("{:?}", value)));
+  }
+}
+
+#[cfg(feature = "tracing")]
+impl tracing::Subscriber for SpanRecorder {
+  fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+    true
+  }
+
+  fn new_span(&self, attributes: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+    let mut fields = Vec::new();
+    attributes.record(&mut FieldVisitor(&mut fields));
+    let mut spans = self.spans.lock().unwrap();
+    spans.push((attributes.metadata().name().to_string(), fields));
+    tracing::span::Id::from_u64(spans.len() as u64)
+  }
+
+  fn record(&self, span: &tracing::span::Id, values: &tracing::span::Record<'_>) {
+    let mut spans = self.spans.lock().unwrap();
+    values.record(&mut FieldVisitor(&mut spans[span.into_u64() as usize - 1].1));
+  }
+
+  fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+  fn event(&self, event: &tracing::Event<'_>) {
+    event.record(&mut FieldVisitor(&mut self.events.lock().unwrap()));
+  }
+
+  fn enter(&self, _: &tracing::span::Id) {}
+
+  fn exit(&self, _: &tracing::span::Id) {}
+}
+
+#[cfg(feature = "tracing")]
+#[tokio::test]
+async fn test_api_calls_emit_spans() {
+  let recorder = SpanRecorder::default();
+  let _guard = tracing::subscriber::set_default(recorder.clone());
+
+  let api = get_canned_api("/pinning/unpin/QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH", serde_json::json!({}));
+  api.unpin("QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH").await.unwrap();
+  assert!(api.get_hash_metadata("QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH").await.is_err());
+
+  assert_eq!(recorder.field("unpin", "cid").unwrap(), "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH");
+  assert_eq!(
+    recorder.field("pinata_request", "endpoint").unwrap(),
+    "\"/pinning/unpin/QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH\"",
+  );
+  assert_eq!(recorder.field("pinata_request", "status").unwrap(), "200");
+  assert!(recorder.field("pinata_request", "elapsed_ms").is_some());
+  assert!(recorder.events.lock().unwrap().iter().any(|(name, _)| name == "error"));
+}
+
+#[tokio::test]
+async fn test_request_escape_hatch() {
+  let server = MockServer::start().await;
+  Mock::given(method("POST"))
+    .and(path("/v3/new/endpoint"))
+    .and(header("pinata_api_key", "api_key"))
+    .and(query_param("dryRun", "true"))
+    .and(body_json(serde_json::json!({ "cid": "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH" })))
+    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "accepted": true })))
+    .expect(1)
+    .mount(&server)
+    .await;
+  Mock::given(method("GET"))
+    .and(path("/v3/new/endpoint"))
+    .respond_with(ResponseTemplate::new(403).set_body_json(serde_json::json!({ "error": "Forbidden" })))
+    .mount(&server)
+    .await;
+
+  let api = PinataApi::new_with_base_url("api_key", "secret_api_key", server.uri()).unwrap();
+  let response: serde_json::Value = api.request(
+    Method::POST,
+    "/v3/new/endpoint",
+    Some(serde_json::json!({ "cid": "QmYW6YYCco35LGEmpm6oyJVijjTR5fPxvKxRULEauefXNH" })),
+    Some(serde_json::json!({ "dryRun": true })),
+  ).await.unwrap();
+  assert_eq!(response, serde_json::json!({ "accepted": true }));
+
+  let err = api.request::<serde_json::Value>(Method::GET, "/v3/new/endpoint", None, None).await.unwrap_err();
+  assert_eq!(err.status_code(), Some(403));
+}
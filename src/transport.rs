@@ -0,0 +1,164 @@
+use bytes::Bytes;
+use futures::future::FutureExt;
+use reqwest::{Client, Request, StatusCode, Url};
+use reqwest::header::HeaderMap;
+use crate::errors::ApiError;
+
+/// Future returned by [HttpTransport::execute](trait.HttpTransport.html#tymethod.execute)
+#[cfg(not(target_arch = "wasm32"))]
+pub type TransportFuture<'a> = futures::future::BoxFuture<'a, Result<TransportResponse, ApiError>>;
+
+/// Future returned by [HttpTransport::execute](trait.HttpTransport.html#tymethod.execute).
+/// Requests sent by the browser can't be moved across threads, so it isn't `Send` on wasm.
+#[cfg(target_arch = "wasm32")]
+pub type TransportFuture<'a> = futures::future::LocalBoxFuture<'a, Result<TransportResponse, ApiError>>;
+
+/// Response to a request sent by an [HttpTransport](trait.HttpTransport.html), with its body
+/// already read.
+#[derive(Clone, Debug)]
+pub struct TransportResponse {
+  /// Url of the response, used to tell Pinata API responses apart from gateway responses
+  pub url: Url,
+  /// HTTP status of the response
+  pub status: StatusCode,
+  /// Headers of the response
+  pub headers: HeaderMap,
+  /// Body of the response
+  pub body: Bytes,
+}
+
+impl TransportResponse {
+  /// Creates a response to `request` with the given status and body, and no headers.
+  pub fn new<B: Into<Bytes>>(request: &Request, status: StatusCode, body: B) -> TransportResponse {
+    TransportResponse {
+      url: request.url().clone(),
+      status,
+      headers: HeaderMap::new(),
+      body: body.into(),
+    }
+  }
+
+  pub(crate) fn text(&self) -> String {
+    String::from_utf8_lossy(&self.body).into_owned()
+  }
+}
+
+/// Sends the HTTP requests made by a [PinataApi](struct.PinataApi.html).
+///
+/// Requests are sent with reqwest by default. Implement this trait and pass it to
+/// [PinataApiBuilder::transport](struct.PinataApiBuilder.html#method.transport) to answer requests
+/// with canned responses, e.g. to test code using the SDK without network access.
+///
+/// ## Example
+/// ```
+/// use pinata_sdk::{HttpTransport, PinataApi, TransportFuture, TransportResponse};
+/// use reqwest::{Request, StatusCode};
+///
+/// struct Authenticated;
+///
+/// impl HttpTransport for Authenticated {
+///   fn execute(&self, request: Request) -> TransportFuture<'_> {
+///     let response = TransportResponse::new(&request, StatusCode::OK, "{}");
+///     Box::pin(async move { Ok(response) })
+///   }
+/// }
+///
+/// let api = PinataApi::builder()
+///   .api_keys("api_key", "secret_api_key")
+///   .transport(Authenticated)
+///   .build()
+///   .unwrap();
+/// ```
+pub trait HttpTransport: Send + Sync {
+  /// Sends `request` and reads its response
+  fn execute(&self, request: Request) -> TransportFuture<'_>;
+}
+
+/// Transport sending requests with a reqwest client
+pub(crate) struct ReqwestTransport {
+  client: Client,
+}
+
+impl ReqwestTransport {
+  pub(crate) fn new(client: Client) -> ReqwestTransport {
+    ReqwestTransport { client }
+  }
+}
+
+/// Sends `request` with `transport`. With the `tracing` feature, the request is sent inside a
+/// `pinata_request` span recording its method, endpoint, HTTP status and duration.
+#[cfg(not(feature = "tracing"))]
+pub(crate) async fn execute(transport: &dyn HttpTransport, request: Request) -> Result<TransportResponse, ApiError> {
+  transport.execute(request).await
+}
+
+/// Sends `request` with `transport`. With the `tracing` feature, the request is sent inside a
+/// `pinata_request` span recording its method, endpoint, HTTP status and duration.
+#[cfg(feature = "tracing")]
+pub(crate) async fn execute(transport: &dyn HttpTransport, request: Request) -> Result<TransportResponse, ApiError> {
+  use tracing::{field, Instrument};
+
+  let span = tracing::info_span!(
+    "pinata_request",
+    method = %request.method(),
+    endpoint = request.url().path(),
+    status = field::Empty,
+    elapsed_ms = field::Empty,
+    error = field::Empty,
+  );
+  let stopwatch = Stopwatch::start();
+  let response = transport.execute(request).instrument(span.clone()).await;
+
+  span.record("elapsed_ms", stopwatch.elapsed_ms());
+  match &response {
+    Ok(response) => { span.record("status", response.status.as_u16()); },
+    Err(error) => { span.record("error", field::display(error)); },
+  }
+  response
+}
+
+/// Measures how long a request took. `Instant` isn't available in the browser, so the clock of
+/// the page is used on wasm.
+#[cfg(feature = "tracing")]
+struct Stopwatch {
+  #[cfg(not(target_arch = "wasm32"))]
+  started: std::time::Instant,
+  #[cfg(target_arch = "wasm32")]
+  started: f64,
+}
+
+#[cfg(feature = "tracing")]
+impl Stopwatch {
+  fn start() -> Stopwatch {
+    #[cfg(not(target_arch = "wasm32"))]
+    { Stopwatch { started: std::time::Instant::now() } }
+    #[cfg(target_arch = "wasm32")]
+    { Stopwatch { started: js_sys::Date::now() } }
+  }
+
+  fn elapsed_ms(&self) -> u64 {
+    #[cfg(not(target_arch = "wasm32"))]
+    { self.started.elapsed().as_millis() as u64 }
+    #[cfg(target_arch = "wasm32")]
+    { (js_sys::Date::now() - self.started).max(0.0) as u64 }
+  }
+}
+
+impl HttpTransport for ReqwestTransport {
+  fn execute(&self, request: Request) -> TransportFuture<'_> {
+    let response = async move {
+      let response = self.client.execute(request).await?;
+      Ok(TransportResponse {
+        url: response.url().clone(),
+        status: response.status(),
+        headers: response.headers().clone(),
+        body: response.bytes().await?,
+      })
+    };
+
+    #[cfg(not(target_arch = "wasm32"))]
+    { response.boxed() }
+    #[cfg(target_arch = "wasm32")]
+    { response.boxed_local() }
+  }
+}
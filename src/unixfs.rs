@@ -0,0 +1,267 @@
+//! Computes the CID content gets when it's added to IPFS as a UnixFS file, without uploading it.
+//!
+//! The content is split in chunks, laid out in a balanced DAG of at most 174 links per node, the
+//! layout of the IPFS nodes Pinata runs. CIDv1 DAGs use raw leaves, as IPFS does when adding
+//! content with `--cid-version=1`.
+
+use cid::Cid;
+use cid::multihash::Multihash;
+use sha2::{Digest, Sha256, Sha512};
+use crate::errors::ApiError;
+use crate::utils::{DAG_PB_CODEC, SHA2_256_CODE};
+
+/// Size of the chunks IPFS splits content in by default
+pub(crate) const CHUNK_SIZE: usize = 262_144;
+/// Maximum number of links of a node of the DAG
+const MAX_LINKS: usize = 174;
+/// Multicodec code of raw blocks, used for the leaves of CIDv1 DAGs
+const RAW_CODEC: u64 = 0x55;
+/// Multihash code of sha2-512
+const SHA2_512_CODE: u64 = 0x13;
+/// UnixFS type of file nodes
+const UNIXFS_FILE: u64 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+/// Hash functions a CID can be computed with
+pub enum CidHash {
+  /// sha2-256, used by IPFS by default and the only hash function of CIDv0
+  Sha2_256,
+  /// sha2-512
+  Sha2_512,
+}
+
+impl CidHash {
+  fn multihash(self, bytes: &[u8]) -> Result<Multihash<64>, ApiError> {
+    let hash = match self {
+      CidHash::Sha2_256 => Multihash::wrap(SHA2_256_CODE, &Sha256::digest(bytes)),
+      CidHash::Sha2_512 => Multihash::wrap(SHA2_512_CODE, &Sha512::digest(bytes)),
+    };
+    hash.map_err(|err| ApiError::InvalidCidConfig(err.to_string()))
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+/// How content is chunked and hashed to compute its CID with [compute_cid](fn.compute_cid.html).
+///
+/// The default config matches how Pinata adds content to IPFS by default: chunks of 256KiB, CIDv0
+/// and sha2-256. Set the CID version to the `cid_version` of the pin options when pinning with one.
+///
+/// ```
+/// use pinata_sdk::CidConfig;
+///
+/// let config = CidConfig::default().cid_version(1);
+/// ```
+pub struct CidConfig {
+  /// Size in bytes of the chunks the content is split in
+  pub chunk_size: usize,
+  /// CID version, 0 or 1. The leaves of a CIDv1 DAG are raw blocks.
+  pub cid_version: u8,
+  /// Hash function of the CIDs
+  pub hash: CidHash,
+}
+
+impl Default for CidConfig {
+  fn default() -> CidConfig {
+    CidConfig {
+      chunk_size: CHUNK_SIZE,
+      cid_version: 0,
+      hash: CidHash::Sha2_256,
+    }
+  }
+}
+
+impl CidConfig {
+  /// Consumes the CidConfig and returns a new CidConfig with the chunk size set.
+  pub fn chunk_size(mut self, chunk_size: usize) -> CidConfig {
+    self.chunk_size = chunk_size;
+    self
+  }
+
+  /// Consumes the CidConfig and returns a new CidConfig with the CID version set.
+  pub fn cid_version(mut self, cid_version: u8) -> CidConfig {
+    self.cid_version = cid_version;
+    self
+  }
+
+  /// Consumes the CidConfig and returns a new CidConfig with the hash function set.
+  pub fn hash(mut self, hash: CidHash) -> CidConfig {
+    self.hash = hash;
+    self
+  }
+
+  fn validate(&self) -> Result<(), ApiError> {
+    if self.cid_version > 1 {
+      return Err(ApiError::InvalidCidVersion(self.cid_version));
+    }
+    if self.chunk_size == 0 {
+      return Err(ApiError::InvalidCidConfig("chunk size must not be 0".to_string()));
+    }
+    if self.cid_version == 0 && self.hash != CidHash::Sha2_256 {
+      return Err(ApiError::InvalidCidConfig("CIDv0 can only be computed with sha2-256".to_string()));
+    }
+    Ok(())
+  }
+}
+
+/// A node of the DAG of a file
+struct Node {
+  cid: Cid,
+  /// Size of the file content under the node
+  file_size: u64,
+  /// Size of the encoded node and all the nodes under it
+  cumulative_size: u64,
+}
+
+/// Returns the CID IPFS gives `bytes` added as a file with `config`
+pub(crate) fn file_cid(bytes: &[u8], config: &CidConfig) -> Result<String, ApiError> {
+  config.validate()?;
+
+  let mut nodes = if bytes.is_empty() {
+    vec![leaf(bytes, config)?]
+  } else {
+    bytes.chunks(config.chunk_size).map(|chunk| leaf(chunk, config)).collect::<Result<Vec<_>, _>>()?
+  };
+  while nodes.len() > 1 {
+    nodes = nodes.chunks(MAX_LINKS).map(|children| parent(children, config)).collect::<Result<Vec<_>, _>>()?;
+  }
+
+  Ok(nodes.remove(0).cid.to_string())
+}
+
+fn leaf(chunk: &[u8], config: &CidConfig) -> Result<Node, ApiError> {
+  if config.cid_version == 1 {
+    return Ok(Node {
+      cid: Cid::new_v1(RAW_CODEC, config.hash.multihash(chunk)?),
+      file_size: chunk.len() as u64,
+      cumulative_size: chunk.len() as u64,
+    });
+  }
+
+  let block = dag_pb_node(&[], &unixfs_file(chunk, chunk.len() as u64, &[]));
+  Ok(Node {
+    cid: dag_pb_cid(&block, config)?,
+    file_size: chunk.len() as u64,
+    cumulative_size: block.len() as u64,
+  })
+}
+
+fn parent(children: &[Node], config: &CidConfig) -> Result<Node, ApiError> {
+  let block_sizes: Vec<u64> = children.iter().map(|child| child.file_size).collect();
+  let file_size = block_sizes.iter().sum();
+  let block = dag_pb_node(children, &unixfs_file(&[], file_size, &block_sizes));
+
+  Ok(Node {
+    cid: dag_pb_cid(&block, config)?,
+    file_size,
+    cumulative_size: block.len() as u64 + children.iter().map(|child| child.cumulative_size).sum::<u64>(),
+  })
+}
+
+fn dag_pb_cid(block: &[u8], config: &CidConfig) -> Result<Cid, ApiError> {
+  let hash = config.hash.multihash(block)?;
+  match config.cid_version {
+    0 => Cid::new_v0(hash).map_err(|err| ApiError::InvalidCidConfig(err.to_string())),
+    _ => Ok(Cid::new_v1(DAG_PB_CODEC, hash)),
+  }
+}
+
+/// Encodes the UnixFS data of a file node
+fn unixfs_file(data: &[u8], file_size: u64, block_sizes: &[u64]) -> Vec<u8> {
+  let mut encoded = Vec::new();
+  put_varint_field(&mut encoded, 1, UNIXFS_FILE);
+  if !data.is_empty() {
+    put_bytes_field(&mut encoded, 2, data);
+  }
+  put_varint_field(&mut encoded, 3, file_size);
+  for size in block_sizes {
+    put_varint_field(&mut encoded, 4, *size);
+  }
+  encoded
+}
+
+/// Encodes a dag-pb node, the links come before the data
+fn dag_pb_node(links: &[Node], data: &[u8]) -> Vec<u8> {
+  let mut encoded = Vec::new();
+  for link in links {
+    let mut encoded_link = Vec::new();
+    put_bytes_field(&mut encoded_link, 1, &link.cid.to_bytes());
+    put_bytes_field(&mut encoded_link, 2, b"");
+    put_varint_field(&mut encoded_link, 3, link.cumulative_size);
+    put_bytes_field(&mut encoded, 2, &encoded_link);
+  }
+  put_bytes_field(&mut encoded, 1, data);
+  encoded
+}
+
+fn put_varint_field(buffer: &mut Vec<u8>, field: u64, value: u64) {
+  put_varint(buffer, field << 3);
+  put_varint(buffer, value);
+}
+
+fn put_bytes_field(buffer: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+  put_varint(buffer, field << 3 | 2);
+  put_varint(buffer, bytes.len() as u64);
+  buffer.extend_from_slice(bytes);
+}
+
+fn put_varint(buffer: &mut Vec<u8>, mut value: u64) {
+  while value >= 0x80 {
+    buffer.push(value as u8 | 0x80);
+    value >>= 7;
+  }
+  buffer.push(value as u8);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{file_cid, CidConfig, CidHash, CHUNK_SIZE};
+
+  #[test]
+  fn test_file_cid_of_known_content() {
+    let v0 = CidConfig::default();
+    let v1 = CidConfig::default().cid_version(1);
+
+    assert_eq!(file_cid(b"", &v0).unwrap(), "QmbFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1AwQH");
+    assert_eq!(file_cid(b"hello world", &v0).unwrap(), "Qmf412jQZiuVUtdgnB36FXFX7xg5V6KEbSJ4dpQuhkLyfD");
+    assert_eq!(file_cid(b"", &v1).unwrap(), "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku");
+    assert_eq!(file_cid(b"hello world", &v1).unwrap(), "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e");
+  }
+
+  #[test]
+  fn test_file_cid_of_chunked_content() {
+    let content = vec![7u8; CHUNK_SIZE + 1];
+    let cid = file_cid(&content, &CidConfig::default()).unwrap();
+
+    assert!(cid.starts_with("Qm"));
+    assert_ne!(cid, file_cid(&content[..CHUNK_SIZE], &CidConfig::default()).unwrap());
+    assert_ne!(cid, file_cid(&content, &CidConfig::default().chunk_size(1024)).unwrap());
+    assert!(file_cid(&content, &CidConfig::default().cid_version(1)).unwrap().starts_with("bafybei"));
+  }
+
+  #[test]
+  fn test_file_cid_of_multi_chunk_content() {
+    let v0 = CidConfig::default();
+    let v1 = CidConfig::default().cid_version(1);
+
+    // 1MiB of zeros is 4 chunks under a single parent node
+    let content = vec![0u8; 1 << 20];
+    assert_eq!(file_cid(&content, &v0).unwrap(), "QmVkbauSDEaMP4Tkq6Epm9uW75mWm136n81YH8fGtfwdHU");
+    assert_eq!(file_cid(&content, &v1).unwrap(), "bafybeiggzq4ryi7hscq5hzvzcnk4urnxt3asp37dhgvnjilf7exskximla");
+
+    // 175 chunks are one more than a node can link to, so the DAG has two levels of parents
+    let content = vec![0u8; 175 * 1024];
+    assert_eq!(file_cid(&content, &v0.chunk_size(1024)).unwrap(), "QmPAAkqYNw6NBGrXi22TMZbMobd97Dknmwxeuk72Rdx54F");
+    assert_eq!(
+      file_cid(&content, &v1.chunk_size(1024)).unwrap(),
+      "bafybeifmi7jxpnuv53patszh2ragbkinpvwso3iidxlglgugmvrv6yfnu4"
+    );
+  }
+
+  #[test]
+  fn test_invalid_cid_config() {
+    assert!(file_cid(b"", &CidConfig::default().cid_version(2)).is_err());
+    assert!(file_cid(b"", &CidConfig::default().chunk_size(0)).is_err());
+    assert!(file_cid(b"", &CidConfig::default().hash(CidHash::Sha2_512)).is_err());
+    assert!(file_cid(b"", &CidConfig::default().cid_version(1).hash(CidHash::Sha2_512)).is_ok());
+  }
+}
@@ -0,0 +1,689 @@
+use std::collections::HashSet;
+use reqwest::multipart::Part;
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  pin::Pin,
+  sync::{Arc, Mutex},
+  sync::atomic::{AtomicU64, Ordering},
+};
+#[cfg(not(target_arch = "wasm32"))]
+use bytes::Bytes;
+#[cfg(not(target_arch = "wasm32"))]
+use futures::{stream, StreamExt, TryStreamExt};
+#[cfg(not(target_arch = "wasm32"))]
+use reqwest::Body;
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::{fs::File, io::AsyncRead};
+#[cfg(not(target_arch = "wasm32"))]
+use tokio_util::codec::{BytesCodec, FramedRead};
+#[cfg(not(target_arch = "wasm32"))]
+use globset::{Glob, GlobSet, GlobSetBuilder};
+#[cfg(not(target_arch = "wasm32"))]
+use walkdir::WalkDir;
+use std::future::Future;
+#[cfg(not(target_arch = "wasm32"))]
+use futures::future::{self, Either};
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use tokio_util::sync::CancellationToken;
+use crate::api::data::{DuplicatePathPolicy, FileData, UploadOptions};
+use crate::errors::ApiError;
+
+/// Multipart field name Pinata expects the files of an upload in
+pub(crate) const FILE_FIELD_NAME: &str = "file";
+
+/// Where the content of an uploaded file comes from
+pub(crate) enum UploadSource {
+  /// Location of the file on disk
+  #[cfg(not(target_arch = "wasm32"))]
+  Path(PathBuf),
+  /// File content in memory
+  Bytes(Vec<u8>),
+  /// File content read from a reader of unknown length
+  #[cfg(not(target_arch = "wasm32"))]
+  Reader(Pin<Box<dyn AsyncRead + Send>>),
+}
+
+/// Size of the chunks in-memory content is sent in when tracking upload progress
+#[cfg(not(target_arch = "wasm32"))]
+const PROGRESS_CHUNK_SIZE: usize = 64 * 1024;
+
+/// A single file to be sent as a part of a pin_file multipart upload
+pub(crate) struct UploadFile {
+  pub(crate) source: UploadSource,
+  /// File name used for the multipart part. For directories this includes the directory name.
+  pub(crate) part_name: String,
+  /// Size of the file content in bytes, 0 when read from a reader as the size is unknown
+  pub(crate) size: u64,
+  /// MIME type the part is sent with
+  pub(crate) content_type: String,
+}
+
+impl UploadFile {
+  /// Creates an UploadFile whose content is streamed from `reader`
+  #[cfg(not(target_arch = "wasm32"))]
+  pub(crate) fn from_reader<R>(part_name: String, reader: R) -> UploadFile
+    where R: AsyncRead + Send + 'static
+  {
+    UploadFile {
+      source: UploadSource::Reader(Box::pin(reader)),
+      content_type: guess_content_type(&part_name),
+      part_name,
+      size: 0,
+    }
+  }
+
+  /// Copies the file to upload it again, e.g. when the upload is retried. Files on disk are reopened
+  /// when the copy is uploaded, content in memory is cloned. Content read from a reader can only
+  /// be uploaded once, so `None` is returned for it.
+  pub(crate) fn try_clone(&self) -> Option<UploadFile> {
+    let source = match &self.source {
+      #[cfg(not(target_arch = "wasm32"))]
+      UploadSource::Path(path) => UploadSource::Path(path.clone()),
+      UploadSource::Bytes(bytes) => UploadSource::Bytes(bytes.clone()),
+      #[cfg(not(target_arch = "wasm32"))]
+      UploadSource::Reader(_) => return None,
+    };
+
+    Some(UploadFile {
+      source,
+      part_name: self.part_name.clone(),
+      size: self.size,
+      content_type: self.content_type.clone(),
+    })
+  }
+
+  /// Content of the file, when it is in memory
+  pub(crate) fn bytes(&self) -> Option<&[u8]> {
+    match &self.source {
+      UploadSource::Bytes(bytes) => Some(bytes),
+      #[cfg(not(target_arch = "wasm32"))]
+      _ => None,
+    }
+  }
+
+  /// Reads the content of a file on disk into memory on the blocking thread pool.
+  /// On wasm the content is always in memory already.
+  #[cfg(target_arch = "wasm32")]
+  pub(crate) async fn read_into_memory(self) -> Result<UploadFile, ApiError> {
+    Ok(self)
+  }
+
+  /// Reads the content of a file on disk into memory on the blocking thread pool
+  #[cfg(not(target_arch = "wasm32"))]
+  pub(crate) async fn read_into_memory(self) -> Result<UploadFile, ApiError> {
+    match self.source {
+      UploadSource::Path(path) => {
+        let bytes = tokio::task::spawn_blocking(move || fs::read(&path).map_err(|err| ApiError::io(&path, err)))
+          .await
+          .map_err(|err| ApiError::Io { path: None, source: err.into() })??;
+        Ok(UploadFile {
+          size: bytes.len() as u64,
+          source: UploadSource::Bytes(bytes),
+          ..self
+        })
+      },
+      source => Ok(UploadFile { source, ..self }),
+    }
+  }
+
+  /// Creates the multipart part for the file. Files on disk are streamed in chunks,
+  /// instead of reading the whole file into memory.
+  ///
+  /// If `progress` is provided, it is advanced as the part's content is sent.
+  #[cfg(not(target_arch = "wasm32"))]
+  pub(crate) async fn into_part(self, progress: Option<&UploadProgress>) -> Result<Part, ApiError> {
+    let part = match (self.source, progress) {
+      (UploadSource::Path(path), progress) => {
+        let file = File::open(&path).await.map_err(|err| ApiError::io(&path, err))?;
+        let chunks = FramedRead::new(file, BytesCodec::new()).map_ok(|chunk| chunk.freeze());
+        let body = match progress {
+          Some(progress) => Body::wrap_stream(progress.track(chunks)),
+          None => Body::wrap_stream(chunks),
+        };
+        Part::stream_with_length(body, self.size)
+      },
+      (UploadSource::Bytes(bytes), Some(progress)) => {
+        let bytes = Bytes::from(bytes);
+        let chunks = (0..bytes.len()).step_by(PROGRESS_CHUNK_SIZE)
+          .map(|start| Ok(bytes.slice(start..bytes.len().min(start + PROGRESS_CHUNK_SIZE))))
+          .collect::<Vec<Result<Bytes, std::io::Error>>>();
+        Part::stream_with_length(Body::wrap_stream(progress.track(stream::iter(chunks))), self.size)
+      },
+      (UploadSource::Bytes(bytes), None) => Part::bytes(bytes),
+      (UploadSource::Reader(reader), progress) => {
+        let chunks = FramedRead::new(reader, BytesCodec::new()).map_ok(|chunk| chunk.freeze());
+        match progress {
+          Some(progress) => Part::stream(Body::wrap_stream(progress.track(chunks))),
+          None => Part::stream(Body::wrap_stream(chunks)),
+        }
+      },
+    };
+
+    Ok(part.file_name(self.part_name).mime_str(&self.content_type)?)
+  }
+
+  /// Creates the multipart part for the file. On wasm only content in memory can be uploaded.
+  #[cfg(target_arch = "wasm32")]
+  pub(crate) async fn into_part(self, _progress: Option<&UploadProgress>) -> Result<Part, ApiError> {
+    let part = match self.source {
+      UploadSource::Bytes(bytes) => Part::bytes(bytes),
+    };
+
+    Ok(part.file_name(self.part_name).mime_str(&self.content_type)?)
+  }
+}
+
+/// Uploads can't be cancelled on wasm, where there is no tokio runtime,
+/// so no value of this type can exist there.
+#[cfg(target_arch = "wasm32")]
+#[derive(Clone)]
+pub(crate) enum CancellationToken {}
+
+/// Runs `upload` to completion, or until `cancellation` is cancelled. The upload and the request
+/// it is sending are dropped when cancelled, and `ApiError::Cancelled` is returned.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn until_cancelled<T, F>(cancellation: Option<CancellationToken>, upload: F) -> Result<T, ApiError>
+  where F: Future<Output = Result<T, ApiError>>
+{
+  let token = match cancellation {
+    Some(token) => token,
+    None => return upload.await,
+  };
+  if token.is_cancelled() {
+    return Err(ApiError::Cancelled());
+  }
+
+  let cancelled = token.cancelled();
+  futures::pin_mut!(upload, cancelled);
+  match future::select(upload, cancelled).await {
+    Either::Left((result, _)) => result,
+    Either::Right(_) => Err(ApiError::Cancelled()),
+  }
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn until_cancelled<T, F>(cancellation: Option<CancellationToken>, upload: F) -> Result<T, ApiError>
+  where F: Future<Output = Result<T, ApiError>>
+{
+  match cancellation {
+    Some(token) => match token {},
+    None => upload.await,
+  }
+}
+
+/// Reports the progress of an upload made of many parts to a callback
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Clone)]
+pub(crate) struct UploadProgress {
+  uploaded: Arc<AtomicU64>,
+  total: u64,
+  callback: Arc<Mutex<dyn FnMut(u64, u64) + Send>>,
+}
+
+/// Upload progress can't be reported on wasm where request bodies are not streamed,
+/// so no value of this type can exist there.
+#[cfg(target_arch = "wasm32")]
+pub(crate) enum UploadProgress {}
+
+#[cfg(target_arch = "wasm32")]
+impl UploadProgress {
+  pub(crate) fn reset(&self) {
+    match *self {}
+  }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl UploadProgress {
+  pub(crate) fn new<F>(total: u64, callback: F) -> UploadProgress
+    where F: FnMut(u64, u64) + Send + 'static
+  {
+    UploadProgress {
+      uploaded: Arc::new(AtomicU64::new(0)),
+      total,
+      callback: Arc::new(Mutex::new(callback)),
+    }
+  }
+
+  /// Wraps the chunks stream so progress is advanced as each chunk is polled by the request body
+  fn track<S>(&self, chunks: S) -> impl futures::Stream<Item = Result<Bytes, std::io::Error>>
+    where S: futures::Stream<Item = Result<Bytes, std::io::Error>>
+  {
+    let progress = self.clone();
+    chunks.inspect_ok(move |chunk| progress.advance(chunk.len() as u64))
+  }
+
+  /// Starts counting the uploaded bytes from 0 again, when the upload is retried
+  pub(crate) fn reset(&self) {
+    self.uploaded.store(0, Ordering::SeqCst);
+  }
+
+  fn advance(&self, bytes: u64) {
+    let uploaded = self.uploaded.fetch_add(bytes, Ordering::SeqCst) + bytes;
+    if let Ok(mut callback) = self.callback.lock() {
+      callback(uploaded, self.total);
+    }
+  }
+}
+
+/// Collects the files to upload and, if a read concurrency is set, reads them into memory.
+pub(crate) async fn prepare_files(
+  files: Vec<FileData>,
+  options: &UploadOptions,
+) -> Result<Vec<UploadFile>, ApiError> {
+  let files = collect_files(files, options)?;
+  #[cfg(not(target_arch = "wasm32"))]
+  if let Some(concurrency) = options.read_concurrency {
+    return read_files(files, concurrency).await;
+  }
+  Ok(files)
+}
+
+/// Reads the files on disk into memory, `concurrency` files at a time.
+/// The files are returned in the same order they were passed in.
+#[cfg(not(target_arch = "wasm32"))]
+async fn read_files(files: Vec<UploadFile>, concurrency: usize) -> Result<Vec<UploadFile>, ApiError> {
+  let mut read: Vec<(usize, UploadFile)> = stream::iter(files.into_iter().enumerate())
+    .map(|(index, file)| async move {
+      file.read_into_memory().await.map(|file| (index, file))
+    })
+    .buffer_unordered(concurrency.max(1))
+    .try_collect()
+    .await?;
+
+  read.sort_by_key(|(index, _)| *index);
+  Ok(read.into_iter().map(|(_, file)| file).collect())
+}
+
+/// Include and exclude globs selecting the directory entries to upload
+#[cfg(not(target_arch = "wasm32"))]
+struct PathFilter {
+  include: Option<GlobSet>,
+  exclude: GlobSet,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PathFilter {
+  fn new(options: &UploadOptions) -> Result<PathFilter, ApiError> {
+    let include = match options.include_globs.is_empty() {
+      true => None,
+      false => Some(glob_set(&options.include_globs)?),
+    };
+
+    Ok(PathFilter { include, exclude: glob_set(&options.exclude_globs)? })
+  }
+
+  /// Whether the entry, and for directories everything inside it, is skipped
+  fn is_excluded(&self, relative_path: &Path) -> bool {
+    self.exclude.is_match(relative_path)
+  }
+
+  /// Whether the file is uploaded
+  fn is_included(&self, relative_path: &Path) -> bool {
+    match &self.include {
+      Some(include) => include.is_match(relative_path),
+      None => true,
+    }
+  }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn glob_set(globs: &[String]) -> Result<GlobSet, ApiError> {
+  let mut builder = GlobSetBuilder::new();
+  for glob in globs {
+    builder.add(Glob::new(glob)?);
+  }
+  Ok(builder.build()?)
+}
+
+/// Path of an uploaded file within the pinned content: the part name without the name of the
+/// directory it was uploaded from, which Pinata uses as the root of the pin.
+pub(crate) fn path_in_upload(part_name: &str) -> &str {
+  part_name.split_once('/').map_or(part_name, |(_, path)| path)
+}
+
+/// MIME type for the file name's extension, `application/octet-stream` when unknown
+fn guess_content_type(file_name: &str) -> String {
+  mime_guess::from_path(file_name).first_or_octet_stream().to_string()
+}
+
+/// Checks the file is not larger than the maximum file size, if one is set
+fn check_file_size(path: &str, size: u64, options: &UploadOptions) -> Result<(), ApiError> {
+  match options.max_file_size {
+    Some(limit) if size > limit => Err(ApiError::FileTooLarge { path: path.to_string(), size, limit }),
+    _ => Ok(()),
+  }
+}
+
+/// Resolves all the files (walking directories recursively) that should be uploaded
+/// and the part file names they should be uploaded with.
+pub(crate) fn collect_files(
+  files: Vec<FileData>,
+  options: &UploadOptions,
+) -> Result<Vec<UploadFile>, ApiError> {
+  let mut upload_files = Vec::new();
+  let mut seen_part_names = HashSet::new();
+  #[cfg(not(target_arch = "wasm32"))]
+  let path_filter = PathFilter::new(options)?;
+
+  let mut add_file = |source: UploadSource, part_name: String, size: u64| {
+    if seen_part_names.insert(part_name.clone()) {
+      let content_type = guess_content_type(&part_name);
+      upload_files.push(UploadFile { source, part_name, size, content_type });
+      Ok(())
+    } else {
+      match options.duplicate_path_policy {
+        DuplicatePathPolicy::Reject => Err(ApiError::DuplicatePath { path: part_name }),
+        DuplicatePathPolicy::KeepFirst => Ok(()),
+      }
+    }
+  };
+
+  for file_data in files {
+    match file_data {
+      FileData::Bytes { file_name, bytes } => {
+        let size = bytes.len() as u64;
+        check_file_size(&file_name, size, options)?;
+        add_file(UploadSource::Bytes(bytes), file_name, size)?;
+      },
+      #[cfg(not(target_arch = "wasm32"))]
+      FileData::Path { path: file_path, name } => {
+        let base_path = Path::new(&file_path);
+        let base_name = match &name {
+          Some(name) => name.as_str(),
+          None => base_path.file_name()
+            .and_then(|file_name| file_name.to_str())
+            .ok_or_else(|| ApiError::InvalidPath(base_path.to_path_buf()))?,
+        };
+        if base_path.is_dir() {
+          // recursively read the directory
+          let entries = WalkDir::new(base_path)
+            .follow_links(options.follow_symlinks)
+            .into_iter()
+            // skip excluded entries, without walking into excluded directories
+            .filter_entry(|entry| match entry.path().strip_prefix(base_path) {
+              Ok(path_name) => entry.depth() == 0 || !path_filter.is_excluded(path_name),
+              Err(_) => true,
+            });
+          let mut dir_files = Vec::new();
+          for entry_result in entries {
+            let entry = entry_result?;
+            let path = entry.path();
+
+            // not interested in reading directory, symlinked directories that are not followed
+            // included, symlinked files are read through the link
+            if path.is_dir() { continue }
+
+            let path_name = path.strip_prefix(base_path)
+              .map_err(|_| ApiError::InvalidPath(path.to_path_buf()))?;
+            if !path_filter.is_included(path_name) { continue }
+            let size = fs::metadata(path).map_err(|err| ApiError::io(path, err))?.len();
+            check_file_size(&path.display().to_string(), size, options)?;
+            dir_files.push((path_name.to_path_buf(), path.to_path_buf(), size));
+          }
+
+          if dir_files.is_empty() {
+            return Err(ApiError::EmptyDirectory(file_path));
+          }
+
+          // the order of the parts can change the directory CID, so files are uploaded sorted by
+          // their relative path instead of the order the file system lists them in
+          dir_files.sort_by(|(a, _, _), (b, _, _)| a.cmp(b));
+          for (path_name, path, size) in dir_files {
+            let path_name = path_name.to_str().ok_or_else(|| ApiError::InvalidPath(path.clone()))?;
+            let part_name = format!("{}/{}", base_name, path_name);
+            add_file(UploadSource::Path(path), part_name, size)?;
+          }
+        } else {
+          let size = fs::metadata(base_path).map_err(|err| ApiError::io(base_path, err))?.len();
+          check_file_size(&file_path, size, options)?;
+          add_file(UploadSource::Path(base_path.to_path_buf()), String::from(base_name), size)?;
+        }
+      },
+    }
+  }
+
+  if let (Some(content_type), [file]) = (&options.content_type, upload_files.as_mut_slice()) {
+    file.content_type = content_type.clone();
+  }
+
+  Ok(upload_files)
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::api::data::{DuplicatePathPolicy, FileData, UploadOptions};
+  use crate::errors::ApiError;
+  use super::{collect_files, read_files, UploadSource};
+
+  fn file(path: &str) -> FileData {
+    FileData::Path { path: path.to_string(), name: None }
+  }
+
+  fn options(duplicate_path_policy: DuplicatePathPolicy) -> UploadOptions {
+    UploadOptions { duplicate_path_policy, ..Default::default() }
+  }
+
+  #[test]
+  fn test_duplicate_part_names_are_rejected() {
+    let result = collect_files(
+      vec![file("./test-file.txt"), file("./test-dir/a.txt"), file("./test-file.txt")],
+      &options(DuplicatePathPolicy::Reject),
+    );
+
+    match result {
+      Err(ApiError::DuplicatePath { path }) => assert_eq!(path, "test-file.txt"),
+      _ => panic!("duplicate test-file.txt should be rejected"),
+    }
+  }
+
+  #[test]
+  fn test_duplicate_part_names_keep_first() {
+    let files = collect_files(
+      vec![file("./test-file.txt"), file("./test-dir/a.txt"), file("./test-file.txt")],
+      &options(DuplicatePathPolicy::KeepFirst),
+    ).unwrap();
+
+    let part_names: Vec<&str> = files.iter().map(|f| f.part_name.as_str()).collect();
+    assert_eq!(part_names, vec!["test-file.txt", "a.txt"]);
+  }
+
+  #[test]
+  fn test_globs_filter_directory_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("site/css")).unwrap();
+    std::fs::create_dir_all(dir.path().join("site/drafts")).unwrap();
+    std::fs::write(dir.path().join("site/index.html"), "<html></html>").unwrap();
+    std::fs::write(dir.path().join("site/css/main.css"), "body {}").unwrap();
+    std::fs::write(dir.path().join("site/drafts/post.html"), "<html></html>").unwrap();
+    std::fs::write(dir.path().join("site/build.log"), "done").unwrap();
+
+    let options = UploadOptions {
+      include_globs: vec!["*.html".to_string(), "*.log".to_string()],
+      exclude_globs: vec!["drafts".to_string(), "*.log".to_string()],
+      ..Default::default()
+    };
+    let files = collect_files(vec![file(dir.path().join("site").to_str().unwrap())], &options).unwrap();
+
+    let part_names: Vec<&str> = files.iter().map(|f| f.part_name.as_str()).collect();
+    assert_eq!(part_names, vec!["site/index.html"]);
+
+    let options = UploadOptions { exclude_globs: vec!["[".to_string()], ..Default::default() };
+    match collect_files(vec![file(dir.path().join("site").to_str().unwrap())], &options) {
+      Err(err @ ApiError::InvalidGlob(_)) => assert!(std::error::Error::source(&err).is_some()),
+      other => panic!("expected an invalid glob error, got {:?}", other.map(|files| files.len())),
+    }
+  }
+
+  #[test]
+  fn test_directory_files_are_sorted_by_relative_path() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("root");
+    for path in &["z.txt", "b/y.txt", "a-b/x.txt", "a/z.txt", "a/b.txt", "A.txt"] {
+      let path = root.join(path);
+      std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+      std::fs::write(path, "content").unwrap();
+    }
+
+    let files = collect_files(vec![file(root.to_str().unwrap())], &UploadOptions::default()).unwrap();
+    let part_names: Vec<&str> = files.iter().map(|f| f.part_name.as_str()).collect();
+    assert_eq!(part_names, vec![
+      "root/A.txt", "root/a/b.txt", "root/a/z.txt", "root/a-b/x.txt", "root/b/y.txt", "root/z.txt",
+    ]);
+  }
+
+  #[test]
+  fn test_empty_directories_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let dir_path = dir.path().to_str().unwrap().to_string();
+
+    match collect_files(vec![file(&dir_path)], &UploadOptions::default()) {
+      Err(ApiError::EmptyDirectory(path)) => assert_eq!(path, dir_path),
+      _ => panic!("empty directory should be rejected"),
+    }
+
+    std::fs::create_dir_all(dir.path().join("empty/nested")).unwrap();
+    match collect_files(vec![file(&dir_path)], &UploadOptions::default()) {
+      Err(ApiError::EmptyDirectory(path)) => assert_eq!(path, dir_path),
+      _ => panic!("directory with only empty subdirectories should be rejected"),
+    }
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_non_utf8_file_names_are_rejected() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let bad_name = dir.path().join(OsStr::from_bytes(b"bad-\xff.txt"));
+    std::fs::write(&bad_name, "content").unwrap();
+
+    match collect_files(vec![file(dir.path().to_str().unwrap())], &UploadOptions::default()) {
+      Err(ApiError::InvalidPath(path)) => assert_eq!(path, bad_name),
+      _ => panic!("the non UTF-8 file name should be rejected"),
+    }
+  }
+
+  #[test]
+  fn test_files_over_max_size_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("small.txt"), "small").unwrap();
+    std::fs::write(dir.path().join("large.txt"), "x".repeat(100)).unwrap();
+    let options = UploadOptions { max_file_size: Some(10), ..Default::default() };
+
+    let large_path = dir.path().join("large.txt").display().to_string();
+    match collect_files(vec![file(dir.path().to_str().unwrap())], &options) {
+      Err(ApiError::FileTooLarge { path, size, limit }) => {
+        assert_eq!((path, size, limit), (large_path, 100, 10));
+      },
+      _ => panic!("large.txt should be rejected"),
+    }
+
+    let small = collect_files(vec![file(dir.path().join("small.txt").to_str().unwrap())], &options).unwrap();
+    assert_eq!(small.len(), 1);
+  }
+
+  #[test]
+  fn test_upload_progress_reports_totals() {
+    use std::sync::{Arc, Mutex};
+    use super::UploadProgress;
+
+    let reports = Arc::new(Mutex::new(vec![]));
+    let recorded = reports.clone();
+    let progress = UploadProgress::new(10, move |uploaded, total| {
+      recorded.lock().unwrap().push((uploaded, total));
+    });
+
+    progress.advance(4);
+    progress.clone().advance(6);
+    assert_eq!(*reports.lock().unwrap(), vec![(4, 10), (10, 10)]);
+  }
+
+  #[test]
+  fn test_content_type_is_guessed_from_extension() {
+    let dir = tempfile::tempdir().unwrap();
+    let json_path = dir.path().join("data.json");
+    std::fs::write(&json_path, "{}").unwrap();
+
+    let files = collect_files(
+      vec![file(json_path.to_str().unwrap()), file("./test-file.txt"), FileData::Bytes {
+        file_name: "blob".to_string(),
+        bytes: vec![1, 2, 3],
+      }],
+      &UploadOptions::default(),
+    ).unwrap();
+
+    let content_types: Vec<&str> = files.iter().map(|f| f.content_type.as_str()).collect();
+    assert_eq!(content_types, vec!["application/json", "text/plain", "application/octet-stream"]);
+  }
+
+  #[test]
+  fn test_content_type_override_for_single_file() {
+    let files = collect_files(
+      vec![file("./test-file.txt")],
+      &UploadOptions { content_type: Some("text/html".to_string()), ..Default::default() },
+    ).unwrap();
+
+    assert_eq!(files[0].content_type, "text/html");
+  }
+
+  #[cfg(unix)]
+  #[test]
+  fn test_symlinks_are_skipped_or_followed() {
+    use std::os::unix::fs::symlink;
+
+    let dir = tempfile::tempdir().unwrap();
+    let base = dir.path().join("site");
+    std::fs::create_dir(&base).unwrap();
+    std::fs::write(base.join("index.html"), "<html></html>").unwrap();
+    symlink(std::fs::canonicalize("./test-dir").unwrap(), base.join("assets")).unwrap();
+    let base_path = base.to_str().unwrap();
+
+    let mut skipped: Vec<String> = collect_files(vec![file(base_path)], &UploadOptions::default())
+      .unwrap()
+      .into_iter()
+      .map(|f| f.part_name)
+      .collect();
+    skipped.sort();
+    assert_eq!(skipped, vec!["site/index.html"]);
+
+    let followed = collect_files(
+      vec![file(base_path)],
+      &UploadOptions { follow_symlinks: true, ..Default::default() },
+    ).unwrap();
+    assert!(followed.iter().any(|f| f.part_name == "site/assets/a.txt"));
+
+    symlink(&base, base.join("loop")).unwrap();
+    match collect_files(vec![file(base_path)], &UploadOptions { follow_symlinks: true, ..Default::default() }) {
+      Err(ApiError::SymlinkLoop { path }) => assert!(path.ends_with("loop")),
+      _ => panic!("symlink loop should be reported"),
+    }
+  }
+
+  #[tokio::test]
+  async fn test_read_files_concurrently_keeps_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let base = dir.path().join("tiny");
+    std::fs::create_dir(&base).unwrap();
+    for i in 0..500 {
+      std::fs::write(base.join(format!("{:03}.txt", i)), format!("file {}", i)).unwrap();
+    }
+
+    let files = collect_files(vec![file(base.to_str().unwrap())], &UploadOptions::default()).unwrap();
+    let part_names: Vec<String> = files.iter().map(|f| f.part_name.clone()).collect();
+
+    let read = read_files(files, 16).await.unwrap();
+    assert_eq!(read.len(), 500);
+    assert_eq!(read.iter().map(|f| f.part_name.clone()).collect::<Vec<_>>(), part_names);
+    for file in &read {
+      let index: u32 = file.part_name[5..8].parse().unwrap();
+      match &file.source {
+        UploadSource::Bytes(bytes) => assert_eq!(bytes, format!("file {}", index).as_bytes()),
+        _ => panic!("{} should be read into memory", file.part_name),
+      }
+      assert_eq!(file.size, format!("file {}", index).len() as u64);
+    }
+  }
+}
@@ -1,6 +1,30 @@
+use std::convert::TryFrom;
+use std::time::Duration;
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::SystemTime;
+use cid::{Cid, Version};
+use reqwest::{Method, StatusCode, Url};
+use sha2::{Digest, Sha256};
+use reqwest::header::{AUTHORIZATION, HeaderMap, RETRY_AFTER};
+use crate::api::data::RateLimitInfo;
 use crate::errors::{ApiError, Error};
+use crate::unixfs::{self, CidConfig};
 
-static BASE_URL: &'static str = "https://api.pinata.cloud";
+pub(crate) static BASE_URL: &'static str = "https://api.pinata.cloud";
+
+/// Public Pinata gateway used to retrieve pinned content
+pub(crate) static GATEWAY_URL: &str = "https://gateway.pinata.cloud";
+
+/// `User-Agent` sent by default, identifying the SDK and its version
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) const USER_AGENT: &str = concat!("pinata-sdk-rust/", env!("CARGO_PKG_VERSION"));
+
+/// Environment variable the JWT is read from
+pub(crate) const JWT_ENV: &str = "PINATA_JWT";
+/// Environment variable the api key is read from
+pub(crate) const API_KEY_ENV: &str = "PINATA_API_KEY";
+/// Environment variable the secret api key is read from
+pub(crate) const SECRET_API_KEY_ENV: &str = "PINATA_SECRET_API_KEY";
 
 /// Checks to ensure keys are not empty
 pub(crate) fn validate_keys(api_key: &str, secret_api_key: &str) -> Result<(), Error> {
@@ -15,6 +39,489 @@ pub(crate) fn validate_keys(api_key: &str, secret_api_key: &str) -> Result<(), E
   Ok(())
 }
 
-pub(crate) fn api_url(path: &str) -> String {
-  format!("{}{}", BASE_URL, path)
+/// Checks to ensure the jwt is not blank
+pub(crate) fn validate_jwt(jwt: &str) -> Result<(), Error> {
+  if jwt.trim().is_empty() {
+    Err(ApiError::InvalidJwt())?
+  }
+
+  Ok(())
+}
+
+/// Headers used to authenticate with an api key and secret api key
+pub(crate) fn api_key_headers(api_key: &str, secret_api_key: &str) -> Result<HeaderMap, Error> {
+  let mut headers = HeaderMap::new();
+  headers.insert("pinata_api_key", api_key.parse()?);
+  headers.insert("pinata_secret_api_key", secret_api_key.parse()?);
+  Ok(headers)
+}
+
+/// Headers used to authenticate with a JWT
+pub(crate) fn jwt_headers(jwt: &str) -> Result<HeaderMap, Error> {
+  let mut headers = HeaderMap::new();
+  headers.insert(AUTHORIZATION, format!("Bearer {}", jwt).parse()?);
+  Ok(headers)
+}
+
+/// Length of a base58 encoded CIDv0
+const CID_V0_LEN: usize = 46;
+/// Shortest base32 CIDv1 accepted, a multibase prefix followed by the version, codec and a tiny multihash
+const CID_V1_MIN_LEN: usize = 8;
+
+/// Rejects hashes that are obviously not a CID before they are sent to Pinata.
+///
+/// Only the shape of CIDv0 (`Qm...` base58) and base32 CIDv1 (`b...`) hashes is checked, other
+/// multibase encodings are only required to be alphanumeric, so unusual but valid CIDs aren't rejected.
+pub(crate) fn validate_cid(cid: &str) -> Result<(), ApiError> {
+  let is_base58 = |c: char| c.is_ascii_alphanumeric() && !matches!(c, '0' | 'O' | 'I' | 'l');
+  let is_base32 = |c: char| c.is_ascii_lowercase() || ('2'..='7').contains(&c);
+
+  let valid = if cid.starts_with("Qm") {
+    cid.len() == CID_V0_LEN && cid.chars().all(is_base58)
+  } else if let Some(encoded) = cid.strip_prefix('b') {
+    cid.len() >= CID_V1_MIN_LEN && encoded.chars().all(is_base32)
+  } else {
+    !cid.is_empty() && cid.chars().all(|c| c.is_ascii_alphanumeric())
+  };
+
+  if valid {
+    Ok(())
+  } else {
+    Err(ApiError::InvalidCid(cid.to_string()))
+  }
+}
+
+/// Multicodec code of dag-pb, the only codec a CIDv0 can have
+pub(crate) const DAG_PB_CODEC: u64 = 0x70;
+/// Multihash code of sha2-256, the only hash a CIDv0 can have
+pub(crate) const SHA2_256_CODE: u64 = 0x12;
+
+/// Converts a CIDv0 (`Qm...`) to its CIDv1 form in base32, e.g. to use it on a subdomain gateway.
+///
+/// A CIDv1 is returned re-encoded in base32. `ApiError::InvalidCid` is returned if `cid` can't be parsed.
+///
+/// ```
+/// let cid = pinata_sdk::cid_v0_to_v1("QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n").unwrap();
+/// assert_eq!(cid, "bafybeihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku");
+/// ```
+pub fn cid_v0_to_v1(cid: &str) -> Result<String, ApiError> {
+  let parsed = parse_cid(cid)?;
+  let v1 = parsed.into_v1().map_err(|_| ApiError::InvalidCid(cid.to_string()))?;
+  Ok(v1.to_string())
+}
+
+/// Converts a CIDv1 to its CIDv0 (`Qm...`) form.
+///
+/// Only CIDs of dag-pb content hashed with sha2-256 have a CIDv0 form, `ApiError::InvalidCid` is
+/// returned for other CIDs and if `cid` can't be parsed. A CIDv0 is returned unchanged.
+pub fn cid_v1_to_v0(cid: &str) -> Result<String, ApiError> {
+  let parsed = parse_cid(cid)?;
+  if parsed.version() == Version::V0 {
+    return Ok(parsed.to_string());
+  }
+
+  let hash = parsed.hash();
+  if parsed.codec() != DAG_PB_CODEC || hash.code() != SHA2_256_CODE || hash.size() != 32 {
+    return Err(ApiError::InvalidCid(cid.to_string()));
+  }
+
+  Cid::new_v0(*hash)
+    .map(|v0| v0.to_string())
+    .map_err(|_| ApiError::InvalidCid(cid.to_string()))
+}
+
+fn parse_cid(cid: &str) -> Result<Cid, ApiError> {
+  Cid::try_from(cid).map_err(|_| ApiError::InvalidCid(cid.to_string()))
+}
+
+/// Computes the CID `bytes` get when they are pinned as a file, without uploading them, e.g. to
+/// check whether content is already pinned.
+///
+/// The content is chunked and hashed as set in `config`. The CID only matches the one Pinata
+/// gives the content when Pinata adds it the same way, which is the case for the default config
+/// when pinning without a `cid_version` option. `ApiError::InvalidCidConfig` is returned for a
+/// config that can't be used, e.g. CIDv0 with a hash other than sha2-256.
+///
+/// ```
+/// use pinata_sdk::{compute_cid, CidConfig};
+///
+/// let cid = compute_cid(b"hello world", &CidConfig::default()).unwrap();
+/// assert_eq!(cid, "Qmf412jQZiuVUtdgnB36FXFX7xg5V6KEbSJ4dpQuhkLyfD");
+/// ```
+pub fn compute_cid(bytes: &[u8], config: &CidConfig) -> Result<String, ApiError> {
+  unixfs::file_cid(bytes, config)
+}
+
+/// Version of `cid`, or `None` if it can't be parsed
+pub(crate) fn cid_version(cid: &str) -> Option<u8> {
+  match parse_cid(cid).ok()?.version() {
+    Version::V0 => Some(0),
+    Version::V1 => Some(1),
+  }
+}
+
+/// Hex encoded sha2-256 digest of `bytes`
+pub(crate) fn sha256_hex(bytes: &[u8]) -> String {
+  Sha256::digest(bytes).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Protocols a host node multiaddr can start with
+const MULTIADDR_PREFIXES: &[&str] = &["/ip4/", "/ip6/", "/dns/", "/dns4/", "/dns6/", "/dnsaddr/"];
+
+/// Rejects host nodes that are obviously not a multiaddr before they are sent to Pinata.
+///
+/// The multiaddr must start with an address protocol and have a peer id segment (`/p2p/<id>`,
+/// or the older `/ipfs/<id>`), the protocols in between aren't checked.
+pub(crate) fn validate_multiaddr(addr: &str) -> Result<(), ApiError> {
+  let is_peer_id = |id: &str| !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric());
+  let has_prefix = MULTIADDR_PREFIXES.iter().any(|prefix| addr.starts_with(prefix));
+  let segments: Vec<&str> = addr.split('/').collect();
+  let has_peer_id = segments.windows(2)
+    .any(|pair| (pair[0] == "p2p" || pair[0] == "ipfs") && is_peer_id(pair[1]));
+
+  if has_prefix && has_peer_id {
+    Ok(())
+  } else {
+    Err(ApiError::InvalidMultiaddr(addr.to_string()))
+  }
+}
+
+/// Removes trailing slashes so paths can be appended to the base url
+pub(crate) fn normalize_base_url(base_url: &str) -> String {
+  base_url.trim_end_matches('/').to_string()
+}
+
+/// Whether `url` has the same origin as `base_url` and a path at or below its path, comparing path
+/// segments so `/v1` doesn't match `/v10`
+pub(crate) fn is_under_base_url(url: &Url, base_url: &str) -> bool {
+  let base = match Url::parse(base_url) {
+    Ok(base) => base,
+    Err(_) => return false,
+  };
+  if url.origin() != base.origin() {
+    return false;
+  }
+
+  let base_path = base.path().trim_end_matches('/');
+  base_path.is_empty()
+    || url.path() == base_path
+    || matches!(url.path().strip_prefix(base_path), Some(rest) if rest.starts_with('/'))
+}
+
+/// Parses an ISO8601 timestamp returned by Pinata into a UTC datetime
+#[cfg(feature = "chrono")]
+pub(crate) fn parse_timestamp(timestamp: &str) -> Result<chrono::DateTime<chrono::Utc>, ApiError> {
+  chrono::DateTime::parse_from_rfc3339(timestamp)
+    .map(|date| date.with_timezone(&chrono::Utc))
+    .map_err(|err| ApiError::Parse(format!("invalid timestamp {:?}: {}", timestamp, err)))
+}
+
+/// Maximum number of bytes of an unexpected response body kept in `ApiError::UnexpectedResponse`
+/// and `ApiError::Deserialize`
+const MAX_UNEXPECTED_BODY_LEN: usize = 1024;
+
+/// Shortens a response body to at most `MAX_UNEXPECTED_BODY_LEN` bytes, without splitting a character
+pub(crate) fn truncate_body(mut body: String) -> String {
+  if body.len() > MAX_UNEXPECTED_BODY_LEN {
+    let mut end = MAX_UNEXPECTED_BODY_LEN;
+    while !body.is_char_boundary(end) {
+      end -= 1;
+    }
+    body.truncate(end);
+    body.push_str("...");
+  }
+  body
+}
+
+
+/// Retry behavior for requests that fail with 429 or 5xx responses
+#[derive(Clone, Debug)]
+pub(crate) struct RetryConfig {
+  pub(crate) max_retries: u32,
+  pub(crate) base_delay: Duration,
+  /// Longest delay waited before a retry, whether it comes from the backoff or `Retry-After`
+  pub(crate) max_delay: Duration,
+}
+
+impl Default for RetryConfig {
+  fn default() -> Self {
+    RetryConfig {
+      max_retries: 3,
+      base_delay: Duration::from_millis(500),
+      max_delay: Duration::from_secs(30),
+    }
+  }
+}
+
+impl RetryConfig {
+  /// Delay before the retry: the `Retry-After` delay of the response when it has one, otherwise
+  /// the backoff delay. Capped to `max_delay`, so a bogus `Retry-After` can't stall the request.
+  pub(crate) fn retry_delay(&self, retries: u32, headers: &HeaderMap) -> Duration {
+    retry_after(headers)
+      .unwrap_or_else(|| self.backoff_delay(retries))
+      .min(self.max_delay)
+  }
+
+  /// Exponential backoff delay for the retry, with up to `base_delay` of jitter added
+  pub(crate) fn backoff_delay(&self, retries: u32) -> Duration {
+    let base_millis = self.base_delay.as_millis() as u64;
+    let jitter = match base_millis {
+      0 => 0,
+      _ => jitter_millis(base_millis),
+    };
+
+    Duration::from_millis(base_millis.saturating_mul(1 << retries.min(16)) + jitter)
+  }
+}
+
+/// Pseudo random number of milliseconds below `max_millis`
+#[cfg(not(target_arch = "wasm32"))]
+fn jitter_millis(max_millis: u64) -> u64 {
+  SystemTime::now()
+    .duration_since(SystemTime::UNIX_EPOCH)
+    .map(|now| u64::from(now.subsec_nanos()) % max_millis)
+    .unwrap_or(0)
+}
+
+/// Pseudo random number of milliseconds below `max_millis`.
+/// The system time is not available on wasm, so the browser's random numbers are used.
+#[cfg(target_arch = "wasm32")]
+fn jitter_millis(max_millis: u64) -> u64 {
+  (js_sys::Math::random() * max_millis as f64) as u64
+}
+
+/// Waits for `duration` before retrying a request
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn sleep(duration: Duration) {
+  tokio::time::sleep(duration).await
+}
+
+/// Waits for `duration` before retrying a request
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn sleep(duration: Duration) {
+  gloo_timers::future::sleep(duration).await
+}
+
+/// Requests that can safely be sent more than once
+pub(crate) fn is_idempotent(method: &Method) -> bool {
+  method == Method::GET || method == Method::PUT || method == Method::DELETE
+}
+
+/// Responses that are worth retrying the request for
+pub(crate) fn is_retryable_status(status: StatusCode) -> bool {
+  status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+}
+
+/// Reads the delay from a `Retry-After` header given in seconds
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+  headers.get(RETRY_AFTER)?
+    .to_str().ok()?
+    .trim()
+    .parse()
+    .ok()
+    .map(Duration::from_secs)
+}
+
+/// Header with the number of requests allowed in the rate limit window
+const RATE_LIMIT_LIMIT: &str = "x-ratelimit-limit";
+/// Header with the number of requests left in the rate limit window
+const RATE_LIMIT_REMAINING: &str = "x-ratelimit-remaining";
+/// Header with the time the rate limit window resets
+const RATE_LIMIT_RESET: &str = "x-ratelimit-reset";
+
+/// Reads the rate limit headers of a response. Returns `None` when none of them are set.
+pub(crate) fn rate_limit_info(headers: &HeaderMap) -> Option<RateLimitInfo> {
+  let header_value = |name| headers.get(name)?.to_str().ok()?.trim().parse().ok();
+  let info = RateLimitInfo {
+    limit: header_value(RATE_LIMIT_LIMIT),
+    remaining: header_value(RATE_LIMIT_REMAINING),
+    reset: header_value(RATE_LIMIT_RESET),
+  };
+
+  if info == RateLimitInfo::default() {
+    None
+  } else {
+    Some(info)
+  }
+}
+
+/// Credentials used to authenticate with Pinata
+#[derive(Debug, PartialEq)]
+pub(crate) enum Credentials {
+  Jwt(String),
+  ApiKeys(String, String),
+}
+
+/// Reads the credentials using the `lookup` function.
+///
+/// The JWT is preferred when set, otherwise both the api key and secret api key are required.
+/// All missing api key variables are listed in the returned error.
+pub(crate) fn credentials_from_env<F>(lookup: F) -> Result<Credentials, ApiError>
+  where F: Fn(&str) -> Option<String>
+{
+  if let Some(jwt) = lookup(JWT_ENV) {
+    return Ok(Credentials::Jwt(jwt));
+  }
+
+  let api_key = lookup(API_KEY_ENV);
+  let secret_api_key = lookup(SECRET_API_KEY_ENV);
+
+  match (api_key, secret_api_key) {
+    (Some(api_key), Some(secret_api_key)) => Ok(Credentials::ApiKeys(api_key, secret_api_key)),
+    (api_key, secret_api_key) => {
+      let mut missing = vec![];
+      if api_key.is_none() { missing.push(API_KEY_ENV) }
+      if secret_api_key.is_none() { missing.push(SECRET_API_KEY_ENV) }
+      Err(ApiError::MissingEnv(missing.join(", ")))
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+  use crate::errors::ApiError;
+  use std::time::Duration;
+  use reqwest::header::HeaderMap;
+  use crate::api::data::RateLimitInfo;
+  use super::{
+    cid_v0_to_v1, cid_v1_to_v0, credentials_from_env, is_under_base_url, jwt_headers, normalize_base_url, rate_limit_info, retry_after, truncate_body,
+    validate_cid, validate_jwt, validate_multiaddr, Credentials, RetryConfig,
+  };
+
+  #[test]
+  fn test_credentials_from_env() {
+    let mut env = HashMap::new();
+    env.insert("PINATA_API_KEY", "key".to_string());
+    env.insert("PINATA_SECRET_API_KEY", "secret".to_string());
+
+    let credentials = credentials_from_env(|name| env.get(name).cloned()).unwrap();
+    assert_eq!(credentials, Credentials::ApiKeys("key".to_string(), "secret".to_string()));
+
+    env.insert("PINATA_JWT", "jwt".to_string());
+    let credentials = credentials_from_env(|name| env.get(name).cloned()).unwrap();
+    assert_eq!(credentials, Credentials::Jwt("jwt".to_string()));
+  }
+
+  #[test]
+  fn test_credentials_from_env_lists_missing_variables() {
+    match credentials_from_env(|_| None) {
+      Err(ApiError::MissingEnv(vars)) => assert_eq!(vars, "PINATA_API_KEY, PINATA_SECRET_API_KEY"),
+      _ => panic!("missing variables should be reported"),
+    }
+  }
+
+  #[test]
+  fn test_jwt_is_sent_as_bearer_token() {
+    let headers = jwt_headers("dummy.jwt.token").unwrap();
+    assert_eq!(headers.get("authorization").unwrap(), "Bearer dummy.jwt.token");
+    assert!(headers.get("pinata_api_key").is_none());
+  }
+
+  #[test]
+  fn test_blank_jwt_is_rejected() {
+    assert!(validate_jwt("  ").is_err());
+    assert!(validate_jwt("dummy.jwt.token").is_ok());
+  }
+
+  #[test]
+  fn test_validate_cid() {
+    assert!(validate_cid("QmbFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1AwQH").is_ok());
+    assert!(validate_cid("bafybeihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku").is_ok());
+
+    for junk in &["", "not a cid", "QmTooShort", "QmbFMke1KXqnYyBBWxB74N4c5SBnJMVAiMNRcGu6x1Aw0H", "bafyBEIH", "Qm/../etc"] {
+      match validate_cid(junk) {
+        Err(ApiError::InvalidCid(cid)) => assert_eq!(&cid, junk),
+        other => panic!("{:?} should be rejected, got {:?}", junk, other),
+      }
+    }
+  }
+
+  #[test]
+  fn test_cid_conversion() {
+    let v0 = "QmdfTbBqBPQ7VNxZEYEj14VmRuZBkqFbiwReogJgS1zR1n";
+    let v1 = "bafybeihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku";
+
+    assert_eq!(cid_v0_to_v1(v0).unwrap(), v1);
+    assert_eq!(cid_v1_to_v0(v1).unwrap(), v0);
+    assert_eq!(cid_v0_to_v1(v1).unwrap(), v1);
+    assert_eq!(cid_v1_to_v0(v0).unwrap(), v0);
+
+    // raw codec content has no CIDv0 form
+    let raw = "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku";
+    assert!(matches!(cid_v1_to_v0(raw), Err(ApiError::InvalidCid(cid)) if cid == raw));
+    assert!(matches!(cid_v0_to_v1("QmNotACid"), Err(ApiError::InvalidCid(_))));
+  }
+
+  #[test]
+  fn test_validate_multiaddr() {
+    assert!(validate_multiaddr("/ip4/1.2.3.4/tcp/4001/p2p/QmNnooDu7bfjPFoTZYxMNLWUQJyrVwtbZg5gBMjTezGAJN").is_ok());
+    assert!(validate_multiaddr("/dnsaddr/node.example.com/ipfs/QmNnooDu7bfjPFoTZYxMNLWUQJyrVwtbZg5gBMjTezGAJN").is_ok());
+
+    for junk in &["1.2.3.4:4001", "/ip4/1.2.3.4/tcp/4001", "/ip4/1.2.3.4/tcp/4001/p2p/", "/http/1.2.3.4/p2p/QmNode"] {
+      match validate_multiaddr(junk) {
+        Err(ApiError::InvalidMultiaddr(addr)) => assert_eq!(&addr, junk),
+        other => panic!("{:?} should be rejected, got {:?}", junk, other),
+      }
+    }
+  }
+
+  #[test]
+  fn test_base_url_trailing_slash_is_trimmed() {
+    assert_eq!(normalize_base_url("http://localhost:8080/"), "http://localhost:8080");
+    assert_eq!(normalize_base_url("http://localhost:8080"), "http://localhost:8080");
+  }
+
+  #[test]
+  fn test_urls_under_base_url() {
+    let url = |url: &str| reqwest::Url::parse(url).unwrap();
+    assert!(is_under_base_url(&url("https://api.pinata.cloud/data/pinList"), "https://api.pinata.cloud"));
+    assert!(is_under_base_url(&url("https://api.pinata.cloud:443/data"), "https://api.pinata.cloud"));
+    assert!(is_under_base_url(&url("http://proxy/v1/data/pinList"), "http://proxy/v1"));
+    assert!(is_under_base_url(&url("http://proxy/v1"), "http://proxy/v1"));
+
+    // a host sharing the base url as a prefix, another scheme or port, a sibling path
+    assert!(!is_under_base_url(&url("https://api.pinata.cloud.evil.com/data"), "https://api.pinata.cloud"));
+    assert!(!is_under_base_url(&url("http://api.pinata.cloud/data"), "https://api.pinata.cloud"));
+    assert!(!is_under_base_url(&url("http://localhost:8081/data"), "http://localhost:8080"));
+    assert!(!is_under_base_url(&url("http://proxy/v10/data"), "http://proxy/v1"));
+    assert!(!is_under_base_url(&url("https://gateway.pinata.cloud/ipfs/Qm"), "https://api.pinata.cloud"));
+  }
+
+  #[test]
+  fn test_retry_delays() {
+    let mut headers = HeaderMap::new();
+    headers.insert("retry-after", "3".parse().unwrap());
+    assert_eq!(retry_after(&headers), Some(Duration::from_secs(3)));
+    assert_eq!(retry_after(&HeaderMap::new()), None);
+
+    let retry = RetryConfig { max_retries: 3, base_delay: Duration::from_millis(100), max_delay: Duration::from_secs(5) };
+    let delay = retry.backoff_delay(2);
+    assert!(delay >= Duration::from_millis(400) && delay < Duration::from_millis(500));
+    assert_eq!(retry.retry_delay(2, &headers), Duration::from_secs(3));
+    assert_eq!(retry.retry_delay(10, &HeaderMap::new()), Duration::from_secs(5));
+
+    headers.insert("retry-after", "999999".parse().unwrap());
+    assert_eq!(retry.retry_delay(0, &headers), Duration::from_secs(5));
+  }
+
+  #[test]
+  fn test_rate_limit_info() {
+    let mut headers = HeaderMap::new();
+    assert_eq!(rate_limit_info(&headers), None);
+
+    headers.insert("x-ratelimit-limit", "180".parse().unwrap());
+    headers.insert("x-ratelimit-remaining", "42".parse().unwrap());
+    headers.insert("x-ratelimit-reset", "not a number".parse().unwrap());
+    assert_eq!(rate_limit_info(&headers), Some(RateLimitInfo { limit: Some(180), remaining: Some(42), reset: None }));
+  }
+
+  #[test]
+  fn test_truncate_body() {
+    assert_eq!(truncate_body("<html>Bad Gateway</html>".to_string()), "<html>Bad Gateway</html>");
+
+    let truncated = truncate_body("é".repeat(600));
+    assert_eq!(truncated.len(), 1024 + 3);
+    assert!(truncated.ends_with("é..."));
+  }
 }